@@ -16,3 +16,37 @@ function setup_buttons() {
 }
 
 setup_buttons();
+
+function setup_sortable_tables() {
+    let headers = document.querySelectorAll("table.sortable th");
+    for (let i = 0; i < headers.length; i++) {
+        headers[i].addEventListener("click", function(e) {
+            e.preventDefault();
+
+            let column = Array.prototype.indexOf.call(this.parentNode.children, this);
+            let numeric = this.classList.contains("numeric");
+            // Sort in descending order first, and switch order on every click
+            let descending = !this.classList.contains("sorted-desc");
+
+            let siblings = this.parentNode.children;
+            for (let i = 0; i < siblings.length; i++) {
+                siblings[i].classList.remove("sorted-asc", "sorted-desc");
+            }
+            this.classList.add(descending ? "sorted-desc" : "sorted-asc");
+
+            let tbody = this.closest("table").querySelector("tbody");
+            let rows = Array.prototype.slice.call(tbody.querySelectorAll("tr"));
+            rows.sort(function(a, b) {
+                let va = a.children[column].getAttribute("data-value");
+                let vb = b.children[column].getAttribute("data-value");
+                let order = numeric ? parseFloat(va) - parseFloat(vb) : va.localeCompare(vb);
+                return descending ? -order : order;
+            });
+            for (let i = 0; i < rows.length; i++) {
+                tbody.appendChild(rows[i]);
+            }
+        }.bind(headers[i]));
+    }
+}
+
+setup_sortable_tables();
@@ -1,13 +1,22 @@
-use crate::actions::{experiments::ExperimentError, Action, ActionsCtx};
+use crate::actions::{
+    experiments::{check_baseline, check_queries, check_toolchains, ExperimentError},
+    Action, ActionsCtx,
+};
 use crate::db::QueryUtils;
-use crate::experiments::{Assignee, CapLints, CrateSelect, Experiment, GitHubIssue, Mode, Status};
+use crate::experiments::{
+    Assignee, CapLints, CargoProfile, CrateSelect, Experiment, FeatureSet, GitHubIssue,
+    LockfilePolicy, Mode, Query, Status,
+};
 use crate::prelude::*;
 use crate::toolchain::Toolchain;
 use chrono::Utc;
+use rand::{thread_rng, Rng};
 
 pub struct CreateExperiment {
     pub name: String,
-    pub toolchains: [Toolchain; 2],
+    /// The first toolchain is the baseline the other ones are compared against. If a baseline
+    /// experiment is provided, its last toolchain is prepended to this list.
+    pub toolchains: Vec<Toolchain>,
     pub mode: Mode,
     pub crates: CrateSelect,
     pub cap_lints: CapLints,
@@ -16,6 +25,13 @@ pub struct CreateExperiment {
     pub ignore_blacklist: bool,
     pub assign: Option<Assignee>,
     pub requirement: Option<String>,
+    pub verify_runs: u32,
+    /// Read the baseline results from this completed experiment instead of building them.
+    pub baseline: Option<String>,
+    pub features: FeatureSet,
+    pub profile: CargoProfile,
+    pub lockfile: LockfilePolicy,
+    pub queries: Vec<Query>,
 }
 
 impl CreateExperiment {
@@ -25,7 +41,7 @@ impl CreateExperiment {
 
         CreateExperiment {
             name: name.to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Local,
             cap_lints: CapLints::Forbid,
@@ -34,31 +50,62 @@ impl CreateExperiment {
             ignore_blacklist: false,
             assign: None,
             requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
         }
     }
 }
 
 impl Action for CreateExperiment {
-    fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
+    fn apply(mut self, ctx: &ActionsCtx) -> Fallible<()> {
         // Ensure no duplicate experiments are created
         if Experiment::exists(&ctx.db, &self.name)? {
             return Err(ExperimentError::AlreadyExists(self.name).into());
         }
 
-        // Ensure no experiment with duplicate toolchains is created
-        if self.toolchains[0] == self.toolchains[1] {
-            return Err(ExperimentError::DuplicateToolchains.into());
+        // Compare against the most recent toolchain of the baseline experiment
+        if let Some(ref baseline) = self.baseline {
+            let baseline_ex = Experiment::get(&ctx.db, baseline)?
+                .ok_or_else(|| ExperimentError::NotFound(baseline.clone()))?;
+            check_baseline(
+                &baseline_ex,
+                self.mode,
+                self.cap_lints,
+                &self.features,
+                &self.profile,
+                self.lockfile,
+            )?;
+
+            let baseline_tc = baseline_ex.toolchains.last().unwrap().clone();
+            self.toolchains.insert(0, baseline_tc);
         }
 
+        // Ensure no experiment with duplicate or missing toolchains is created
+        check_toolchains(&self.toolchains)?;
+        check_queries(self.mode, &self.queries)?;
+
+        // The seed is picked now and stored, so the same crates can be sampled again later
+        let sample = self.crates.sample_mut().map(|sample| {
+            sample.seed.get_or_insert_with(|| thread_rng().gen());
+            sample.to_string()
+        });
+
         let crates = crate::crates::lists::get_crates(&self.crates, &ctx.db, &ctx.config)?;
+        let queries = serde_json::to_string(&self.queries)?;
 
         ctx.db.transaction(|transaction| {
             transaction.execute(
                 "INSERT INTO experiments \
                  (name, mode, cap_lints, toolchain_start, toolchain_end, priority, created_at, \
                  status, github_issue, github_issue_url, github_issue_number, ignore_blacklist, \
-                 assigned_to, requirement) \
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14);",
+                 assigned_to, requirement, verify_runs, baseline, features, profile, \
+                 lockfile, queries, sample) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, \
+                 ?17, ?18, ?19, ?20, ?21);",
                 &[
                     &self.name,
                     &self.mode.to_str(),
@@ -74,8 +121,16 @@ impl Action for CreateExperiment {
                     &self.ignore_blacklist,
                     &self.assign.map(|a| a.to_string()),
                     &self.requirement,
+                    &self.verify_runs,
+                    &self.baseline,
+                    &self.features.to_string(),
+                    &self.profile.to_string(),
+                    &self.lockfile.to_str(),
+                    &queries,
+                    &sample,
                 ],
             )?;
+            Experiment::store_toolchains(transaction, &self.name, &self.toolchains)?;
 
             for krate in &crates {
                 let skipped = !self.ignore_blacklist && ctx.config.should_skip(krate);
@@ -100,7 +155,8 @@ mod tests {
     use crate::crates::Crate;
     use crate::db::{Database, QueryUtils};
     use crate::experiments::{
-        Assignee, CapLints, CrateSelect, Experiment, GitHubIssue, Mode, Status,
+        Assignee, CapLints, CargoProfile, CrateSelect, Experiment, FeatureSet, GitHubIssue,
+        LockfilePolicy, Mode, Query, Status,
     };
     use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
 
@@ -117,7 +173,7 @@ mod tests {
 
         CreateExperiment {
             name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Local,
             cap_lints: CapLints::Forbid,
@@ -130,6 +186,12 @@ mod tests {
             ignore_blacklist: true,
             assign: None,
             requirement: Some("linux".to_string()),
+            verify_runs: 2,
+            baseline: None,
+            features: FeatureSet::Powerset(8),
+            profile: "release+opt-level=2".parse().unwrap(),
+            lockfile: LockfilePolicy::MinimalVersions,
+            queries: Vec::new(),
         }
         .apply(&ctx)
         .unwrap();
@@ -138,7 +200,7 @@ mod tests {
         assert_eq!(ex.name.as_str(), "foo");
         assert_eq!(
             ex.toolchains,
-            [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()]
+            vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()]
         );
         assert_eq!(ex.mode, Mode::BuildAndTest);
         assert_eq!(
@@ -157,6 +219,10 @@ mod tests {
         assert!(ex.assigned_to.is_none());
         assert!(ex.ignore_blacklist);
         assert_eq!(ex.requirement, Some("linux".to_string()));
+        assert_eq!(ex.verify_runs, 2);
+        assert_eq!(ex.features, FeatureSet::Powerset(8));
+        assert_eq!(ex.profile.to_string(), "release+opt-level=2");
+        assert_eq!(ex.lockfile, LockfilePolicy::MinimalVersions);
     }
 
     #[test]
@@ -247,7 +313,7 @@ mod tests {
         // Ensure an experiment with duplicate toolchains can't be created
         let err = CreateExperiment {
             name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), MAIN_TOOLCHAIN.clone()],
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), MAIN_TOOLCHAIN.clone()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Local,
             cap_lints: CapLints::Forbid,
@@ -256,6 +322,12 @@ mod tests {
             ignore_blacklist: false,
             assign: None,
             requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
         }
         .apply(&ctx)
         .unwrap_err();
@@ -266,6 +338,232 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_multiple_toolchains() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        let toolchains = vec![
+            MAIN_TOOLCHAIN.clone(),
+            TEST_TOOLCHAIN.clone(),
+            "nightly".parse().unwrap(),
+        ];
+        CreateExperiment {
+            toolchains: toolchains.clone(),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        let ex = Experiment::get(&db, "foo").unwrap().unwrap();
+        assert_eq!(ex.toolchains, toolchains);
+
+        // Duplicates are also detected when they're not adjacent
+        let err = CreateExperiment {
+            toolchains: vec![
+                MAIN_TOOLCHAIN.clone(),
+                TEST_TOOLCHAIN.clone(),
+                MAIN_TOOLCHAIN.clone(),
+            ],
+            ..CreateExperiment::dummy("bar")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::DuplicateToolchains)
+        );
+
+        // At least two toolchains are needed to compare them
+        let err = CreateExperiment {
+            toolchains: vec![MAIN_TOOLCHAIN.clone()],
+            ..CreateExperiment::dummy("baz")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::NotEnoughToolchains)
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        let queries: Vec<Query> = vec![
+            "unsafe=unsafe \\{".parse().unwrap(),
+            "phantom=PhantomData".parse().unwrap(),
+        ];
+        CreateExperiment {
+            mode: Mode::Query,
+            queries: queries.clone(),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        let ex = Experiment::get(&db, "foo").unwrap().unwrap();
+        assert_eq!(ex.mode, Mode::Query);
+        assert_eq!(ex.queries, queries);
+
+        // Query experiments need something to search
+        let err = CreateExperiment {
+            mode: Mode::Query,
+            ..CreateExperiment::dummy("bar")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&ExperimentError::MissingQueries));
+
+        // Other experiments don't search anything
+        let err = CreateExperiment {
+            queries: queries.clone(),
+            ..CreateExperiment::dummy("bar")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::UnexpectedQueries)
+        );
+
+        // The names of the queries identify their results
+        let err = CreateExperiment {
+            mode: Mode::Query,
+            queries: vec![queries[0].clone(), queries[0].clone()],
+            ..CreateExperiment::dummy("bar")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::DuplicateQuery("unsafe".into()))
+        );
+    }
+
+    #[test]
+    fn test_baseline() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("base").apply(&ctx).unwrap();
+
+        // The baseline experiment must be completed
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineNotCompleted("base".into()))
+        );
+
+        let mut base = Experiment::get(&db, "base").unwrap().unwrap();
+        base.set_status(&db, Status::Completed).unwrap();
+
+        // The baseline experiment must use the same mode
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            mode: Mode::CheckOnly,
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineModeMismatch("base".into()))
+        );
+
+        // The baseline experiment must cap the lints the same way
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            cap_lints: CapLints::Warn,
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineCapLintsMismatch("base".into()))
+        );
+
+        // The baseline experiment must build the same feature combinations
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            features: FeatureSet::AllFeatures,
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineFeaturesMismatch("base".into()))
+        );
+
+        // The baseline experiment must use the same Cargo profile
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            profile: "release".parse().unwrap(),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineProfileMismatch("base".into()))
+        );
+
+        // The baseline experiment must use the same lockfile policy
+        let err = CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            lockfile: LockfilePolicy::Update,
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineLockfileMismatch("base".into()))
+        );
+
+        CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        let ex = Experiment::get(&db, "foo").unwrap().unwrap();
+        assert_eq!(ex.baseline, Some("base".into()));
+        assert_eq!(
+            ex.toolchains,
+            vec![TEST_TOOLCHAIN.clone(), "nightly".parse().unwrap()]
+        );
+        assert_eq!(ex.built_toolchains(), &ex.toolchains[1..]);
+        assert_eq!(ex.results_experiment(&TEST_TOOLCHAIN), "base");
+        assert_eq!(ex.results_experiment(&ex.toolchains[1]), "foo");
+    }
+
     #[test]
     fn test_duplicate_name() {
         let db = Database::temp().unwrap();
@@ -277,7 +575,7 @@ mod tests {
         // The first experiment can be created successfully
         CreateExperiment {
             name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Local,
             cap_lints: CapLints::Forbid,
@@ -286,6 +584,12 @@ mod tests {
             ignore_blacklist: false,
             assign: None,
             requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
         }
         .apply(&ctx)
         .unwrap();
@@ -293,7 +597,7 @@ mod tests {
         // While the second one fails
         let err = CreateExperiment {
             name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Local,
             cap_lints: CapLints::Forbid,
@@ -302,6 +606,12 @@ mod tests {
             ignore_blacklist: false,
             assign: None,
             requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
         }
         .apply(&ctx)
         .unwrap_err();
@@ -311,4 +621,39 @@ mod tests {
             Some(&ExperimentError::AlreadyExists("foo".into()))
         );
     }
+
+    #[test]
+    fn test_sample_seed() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        let mut create = CreateExperiment::dummy("seeded");
+        create.crates = "sample-10:42".parse().unwrap();
+        create.apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "seeded").unwrap().unwrap();
+        assert_eq!(ex.sample, Some("sample-10:42".parse().unwrap()));
+
+        // Without a seed one is picked and stored, so the sample can be drawn again
+        let mut create = CreateExperiment::dummy("unseeded");
+        create.crates = "sample-10::100=60,rest=40".parse().unwrap();
+        create.apply(&ctx).unwrap();
+        let sample = Experiment::get(&db, "unseeded")
+            .unwrap()
+            .unwrap()
+            .sample
+            .unwrap();
+        assert!(sample.seed.is_some());
+        assert_eq!(sample.count, 10);
+        assert_eq!(sample.strata.len(), 2);
+
+        CreateExperiment::dummy("local").apply(&ctx).unwrap();
+        assert!(Experiment::get(&db, "local")
+            .unwrap()
+            .unwrap()
+            .sample
+            .is_none());
+    }
 }
@@ -13,6 +13,15 @@ impl Action for DeleteExperiment {
             return Err(ExperimentError::NotFound(self.name).into());
         }
 
+        // Deleting the results of a baseline would make every crate of the experiments using it
+        // report an unknown baseline
+        if ctx.db.exists(
+            "SELECT 1 FROM experiments WHERE baseline = ?1;",
+            &[&self.name],
+        )? {
+            return Err(ExperimentError::UsedAsBaseline(self.name).into());
+        }
+
         // This will also delete all the data related to this experiment, thanks to the foreign
         // keys in the SQLite database
         ctx.db
@@ -28,7 +37,7 @@ mod tests {
     use crate::actions::{Action, ActionsCtx, CreateExperiment, ExperimentError};
     use crate::config::Config;
     use crate::db::Database;
-    use crate::experiments::Experiment;
+    use crate::experiments::{Experiment, Status};
 
     #[test]
     fn test_delete_missing_experiment() {
@@ -68,4 +77,48 @@ mod tests {
         .unwrap();
         assert!(!Experiment::exists(&db, "dummy").unwrap());
     }
+
+    #[test]
+    fn test_delete_baseline_experiment() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("base").apply(&ctx).unwrap();
+        let mut base = Experiment::get(&db, "base").unwrap().unwrap();
+        base.set_status(&db, Status::Completed).unwrap();
+        CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        // The baseline can't be deleted while another experiment uses it
+        let err = DeleteExperiment {
+            name: "base".to_string(),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::UsedAsBaseline("base".into()))
+        );
+        assert!(Experiment::exists(&db, "base").unwrap());
+
+        DeleteExperiment {
+            name: "foo".to_string(),
+        }
+        .apply(&ctx)
+        .unwrap();
+        DeleteExperiment {
+            name: "base".to_string(),
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert!(!Experiment::exists(&db, "base").unwrap());
+    }
 }
@@ -1,12 +1,22 @@
-use crate::actions::{experiments::ExperimentError, Action, ActionsCtx};
+use crate::actions::{
+    experiments::{check_baseline, check_queries, check_toolchains, ExperimentError},
+    Action, ActionsCtx,
+};
 use crate::db::QueryUtils;
-use crate::experiments::{Assignee, CapLints, CrateSelect, Experiment, Mode, Status};
+use crate::experiments::{
+    Assignee, CapLints, CargoProfile, CrateSelect, Experiment, FeatureSet, LockfilePolicy, Mode,
+    Query, Status,
+};
 use crate::prelude::*;
 use crate::toolchain::Toolchain;
+use rand::{thread_rng, Rng};
 
 pub struct EditExperiment {
     pub name: String,
+    /// The new baseline and first compared toolchain.
     pub toolchains: [Option<Toolchain>; 2],
+    /// The new list of toolchains compared against the baseline after the first one.
+    pub extra_toolchains: Option<Vec<Toolchain>>,
     pub crates: Option<CrateSelect>,
     pub mode: Option<Mode>,
     pub cap_lints: Option<CapLints>,
@@ -14,6 +24,11 @@ pub struct EditExperiment {
     pub ignore_blacklist: Option<bool>,
     pub assign: Option<Assignee>,
     pub requirement: Option<String>,
+    pub verify_runs: Option<u32>,
+    pub features: Option<FeatureSet>,
+    pub profile: Option<CargoProfile>,
+    pub lockfile: Option<LockfilePolicy>,
+    pub queries: Option<Vec<Query>>,
 }
 
 impl EditExperiment {
@@ -22,6 +37,7 @@ impl EditExperiment {
         EditExperiment {
             name: name.to_string(),
             toolchains: [None, None],
+            extra_toolchains: None,
             mode: None,
             crates: None,
             cap_lints: None,
@@ -29,6 +45,11 @@ impl EditExperiment {
             ignore_blacklist: None,
             assign: None,
             requirement: None,
+            verify_runs: None,
+            features: None,
+            profile: None,
+            lockfile: None,
+            queries: None,
         }
     }
 }
@@ -45,24 +66,37 @@ impl Action for EditExperiment {
             return Err(ExperimentError::CanOnlyEditQueuedExperiments.into());
         }
 
+        // The first toolchain of experiments with a baseline comes from the baseline experiment
+        if ex.baseline.is_some() && self.toolchains[0].is_some() {
+            return Err(ExperimentError::CantEditBaselineToolchain.into());
+        }
+        let baseline = match ex.baseline {
+            Some(ref name) => Some(
+                Experiment::get(&ctx.db, name)?
+                    .ok_or_else(|| ExperimentError::NotFound(name.clone()))?,
+            ),
+            None => None,
+        };
+
         ctx.db.transaction(|t| {
-            // Try to update both toolchains
-            for (i, col) in ["toolchain_start", "toolchain_end"].iter().enumerate() {
-                if let Some(tc) = self.toolchains[i].take() {
+            // Try to update the toolchains
+            let mut toolchains_changed = false;
+            for (i, tc) in self.toolchains.iter_mut().enumerate() {
+                if let Some(tc) = tc.take() {
                     ex.toolchains[i] = tc;
-
-                    // Ensure no duplicate toolchain is inserted
-                    if ex.toolchains[0] == ex.toolchains[1] {
-                        return Err(ExperimentError::DuplicateToolchains.into());
-                    }
-
-                    let changes = t.execute(
-                        &format!("UPDATE experiments SET {} = ?1 WHERE name = ?2;", col),
-                        &[&ex.toolchains[i].to_string(), &self.name],
-                    )?;
-                    assert_eq!(changes, 1);
+                    toolchains_changed = true;
                 }
             }
+            if let Some(extra) = self.extra_toolchains.take() {
+                ex.toolchains.truncate(2);
+                ex.toolchains.extend(extra);
+                toolchains_changed = true;
+            }
+            if toolchains_changed {
+                // Ensure no duplicate toolchain is inserted
+                check_toolchains(&ex.toolchains)?;
+                Experiment::store_toolchains(t, &self.name, &ex.toolchains)?;
+            }
 
             // Try to update the ignore_blacklist field
             // The list of skipped crates will be recalculated afterwards
@@ -77,7 +111,22 @@ impl Action for EditExperiment {
 
             // Try to update the list of crates
             // This is also done if ignore_blacklist is changed to recalculate the skipped crates
-            let new_crates = if let Some(crates) = self.crates {
+            let new_crates = if let Some(mut crates) = self.crates {
+                // The seed is picked now and stored, so the same crates can be sampled again
+                let sample = crates.sample_mut().map(|sample| {
+                    sample.seed.get_or_insert_with(|| thread_rng().gen());
+                    sample.clone()
+                });
+                let changes = t.execute(
+                    "UPDATE experiments SET sample = ?1 WHERE name = ?2;",
+                    &[
+                        &sample.as_ref().map(|sample| sample.to_string()),
+                        &self.name,
+                    ],
+                )?;
+                assert_eq!(changes, 1);
+                ex.sample = sample;
+
                 Some(crate::crates::lists::get_crates(
                     &crates,
                     &ctx.db,
@@ -159,6 +208,70 @@ impl Action for EditExperiment {
                 ex.requirement = Some(requirement);
             }
 
+            // Try to update the number of verification runs
+            if let Some(verify_runs) = self.verify_runs {
+                let changes = t.execute(
+                    "UPDATE experiments SET verify_runs = ?1 WHERE name = ?2;",
+                    &[&verify_runs, &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.verify_runs = verify_runs;
+            }
+
+            // Try to update the feature combinations
+            if let Some(features) = self.features {
+                let changes = t.execute(
+                    "UPDATE experiments SET features = ?1 WHERE name = ?2;",
+                    &[&features.to_string(), &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.features = features;
+            }
+
+            // Try to update the Cargo profile
+            if let Some(profile) = self.profile {
+                let changes = t.execute(
+                    "UPDATE experiments SET profile = ?1 WHERE name = ?2;",
+                    &[&profile.to_string(), &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.profile = profile;
+            }
+
+            // Try to update the lockfile policy
+            if let Some(lockfile) = self.lockfile {
+                let changes = t.execute(
+                    "UPDATE experiments SET lockfile = ?1 WHERE name = ?2;",
+                    &[&lockfile.to_str(), &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.lockfile = lockfile;
+            }
+
+            // Try to update the queries
+            if let Some(queries) = self.queries {
+                let changes = t.execute(
+                    "UPDATE experiments SET queries = ?1 WHERE name = ?2;",
+                    &[&serde_json::to_string(&queries)?, &self.name],
+                )?;
+                assert_eq!(changes, 1);
+                ex.queries = queries;
+            }
+
+            // The mode and the queries can be changed independently
+            check_queries(ex.mode, &ex.queries)?;
+            // The results of the baseline must still be comparable with the edited experiment
+            if let Some(ref baseline) = baseline {
+                check_baseline(
+                    baseline,
+                    ex.mode,
+                    ex.cap_lints,
+                    &ex.features,
+                    &ex.profile,
+                    ex.lockfile,
+                )?;
+            }
+
             Ok(())
         })?;
         Ok(())
@@ -172,7 +285,10 @@ mod tests {
     use crate::config::{Config, CrateConfig};
     use crate::crates::Crate;
     use crate::db::{Database, QueryUtils};
-    use crate::experiments::{Assignee, CapLints, CrateSelect, Experiment, Mode, Status};
+    use crate::experiments::{
+        Assignee, CapLints, CargoProfile, CrateSelect, Experiment, FeatureSet, LockfilePolicy,
+        Mode, Query, Status,
+    };
     use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
 
     #[test]
@@ -198,7 +314,7 @@ mod tests {
         // Create an experiment with the data we're going to change
         CreateExperiment {
             name: "foo".to_string(),
-            toolchains: ["stable".parse().unwrap(), "beta".parse().unwrap()],
+            toolchains: vec!["stable".parse().unwrap(), "beta".parse().unwrap()],
             mode: Mode::BuildAndTest,
             crates: CrateSelect::Random(20),
             cap_lints: CapLints::Forbid,
@@ -207,6 +323,12 @@ mod tests {
             ignore_blacklist: false,
             assign: None,
             requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
         }
         .apply(&ctx)
         .unwrap();
@@ -218,6 +340,7 @@ mod tests {
                 Some("nightly-1970-01-01".parse().unwrap()),
                 Some("nightly-1970-01-02".parse().unwrap()),
             ],
+            extra_toolchains: Some(vec!["nightly-1970-01-03".parse().unwrap()]),
             mode: Some(Mode::CheckOnly),
             crates: Some(CrateSelect::Local),
             cap_lints: Some(CapLints::Warn),
@@ -225,6 +348,11 @@ mod tests {
             ignore_blacklist: Some(true),
             assign: Some(Assignee::CLI),
             requirement: Some("windows".to_string()),
+            verify_runs: Some(3),
+            features: Some(FeatureSet::NoDefaultFeatures),
+            profile: Some("release+debug-assertions=on".parse().unwrap()),
+            lockfile: Some(LockfilePolicy::Update),
+            queries: None,
         }
         .apply(&ctx)
         .unwrap();
@@ -234,12 +362,25 @@ mod tests {
 
         assert_eq!(ex.toolchains[0], "nightly-1970-01-01".parse().unwrap());
         assert_eq!(ex.toolchains[1], "nightly-1970-01-02".parse().unwrap());
+        assert_eq!(ex.toolchains[2], "nightly-1970-01-03".parse().unwrap());
+        assert_eq!(ex.toolchains.len(), 3);
         assert_eq!(ex.mode, Mode::CheckOnly);
         assert_eq!(ex.cap_lints, CapLints::Warn);
         assert_eq!(ex.priority, 10);
         assert_eq!(ex.ignore_blacklist, true);
         assert_eq!(ex.assigned_to, Some(Assignee::CLI));
         assert_eq!(ex.requirement, Some("windows".to_string()));
+        assert_eq!(ex.verify_runs, 3);
+        assert_eq!(ex.features, FeatureSet::NoDefaultFeatures);
+        assert_eq!(
+            ex.profile,
+            CargoProfile {
+                name: "release".into(),
+                opt_level: None,
+                debug_assertions: Some(true),
+            }
+        );
+        assert_eq!(ex.lockfile, LockfilePolicy::Update);
 
         assert_eq!(
             ex.get_crates(&ctx.db).unwrap(),
@@ -322,7 +463,7 @@ mod tests {
 
         // First create an experiment
         let mut dummy = CreateExperiment::dummy("foo");
-        dummy.toolchains = [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()];
+        dummy.toolchains = vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()];
         dummy.apply(&ctx).unwrap();
 
         // Then try to switch the second toolchain to MAIN_TOOLCHAIN
@@ -334,6 +475,127 @@ mod tests {
             err.downcast_ref(),
             Some(&ExperimentError::DuplicateToolchains)
         );
+
+        // Extra toolchains can't duplicate the existing ones either
+        let mut edit = EditExperiment::dummy("foo");
+        edit.extra_toolchains = Some(vec![TEST_TOOLCHAIN.clone()]);
+
+        let err = edit.apply(&ctx).unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::DuplicateToolchains)
+        );
+    }
+
+    #[test]
+    fn test_edit_queries() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("foo").apply(&ctx).unwrap();
+
+        // Switching to the query mode requires providing the queries as well
+        let err = EditExperiment {
+            mode: Some(Mode::Query),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&ExperimentError::MissingQueries));
+
+        let queries: Vec<Query> = vec!["unsafe=unsafe".parse().unwrap()];
+        EditExperiment {
+            mode: Some(Mode::Query),
+            queries: Some(queries.clone()),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        let ex = Experiment::get(&db, "foo").unwrap().unwrap();
+        assert_eq!(ex.mode, Mode::Query);
+        assert_eq!(ex.queries, queries);
+    }
+
+    #[test]
+    fn test_edit_with_baseline() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("base").apply(&ctx).unwrap();
+        let mut base = Experiment::get(&db, "base").unwrap().unwrap();
+        base.set_status(&db, Status::Completed).unwrap();
+        CreateExperiment {
+            toolchains: vec!["nightly".parse().unwrap()],
+            baseline: Some("base".into()),
+            ..CreateExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+
+        // The first toolchain comes from the baseline
+        let err = EditExperiment {
+            toolchains: [Some("beta".parse().unwrap()), None],
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::CantEditBaselineToolchain)
+        );
+
+        // The settings the results depend on must keep matching the baseline
+        let err = EditExperiment {
+            mode: Some(Mode::CheckOnly),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineModeMismatch("base".into()))
+        );
+        let err = EditExperiment {
+            cap_lints: Some(CapLints::Warn),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineCapLintsMismatch("base".into()))
+        );
+        let err = EditExperiment {
+            lockfile: Some(LockfilePolicy::Update),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ExperimentError::BaselineLockfileMismatch("base".into()))
+        );
+
+        // The rejected edits were not applied
+        let ex = Experiment::get(&db, "foo").unwrap().unwrap();
+        assert_eq!(ex.mode, Mode::BuildAndTest);
+        assert_eq!(ex.cap_lints, CapLints::Forbid);
+        assert_eq!(ex.lockfile, LockfilePolicy::AsPrepared);
+
+        EditExperiment {
+            priority: Some(10),
+            ..EditExperiment::dummy("foo")
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert_eq!(Experiment::get(&db, "foo").unwrap().unwrap().priority, 10);
     }
 
     #[test]
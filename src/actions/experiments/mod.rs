@@ -6,6 +6,76 @@ pub use self::create::CreateExperiment;
 pub use self::delete::DeleteExperiment;
 pub use self::edit::EditExperiment;
 
+use crate::experiments::{
+    CapLints, CargoProfile, Experiment, FeatureSet, LockfilePolicy, Mode, Query, Status,
+};
+use crate::prelude::*;
+use crate::toolchain::Toolchain;
+use std::collections::HashSet;
+
+/// Ensure the toolchains of an experiment can be compared against each other.
+fn check_toolchains(toolchains: &[Toolchain]) -> Fallible<()> {
+    if toolchains.len() < 2 {
+        return Err(ExperimentError::NotEnoughToolchains.into());
+    }
+
+    let mut seen = HashSet::new();
+    if !toolchains.iter().all(|tc| seen.insert(tc)) {
+        return Err(ExperimentError::DuplicateToolchains.into());
+    }
+
+    Ok(())
+}
+
+/// Ensure the results of the baseline experiment can be compared with the ones of an experiment
+/// run with these settings.
+fn check_baseline(
+    baseline: &Experiment,
+    mode: Mode,
+    cap_lints: CapLints,
+    features: &FeatureSet,
+    profile: &CargoProfile,
+    lockfile: LockfilePolicy,
+) -> Fallible<()> {
+    let name = baseline.name.clone();
+    if baseline.status != Status::Completed {
+        return Err(ExperimentError::BaselineNotCompleted(name).into());
+    }
+    if baseline.mode != mode {
+        return Err(ExperimentError::BaselineModeMismatch(name).into());
+    }
+    if baseline.cap_lints != cap_lints {
+        return Err(ExperimentError::BaselineCapLintsMismatch(name).into());
+    }
+    if baseline.features != *features {
+        return Err(ExperimentError::BaselineFeaturesMismatch(name).into());
+    }
+    if baseline.profile != *profile {
+        return Err(ExperimentError::BaselineProfileMismatch(name).into());
+    }
+    if baseline.lockfile != lockfile {
+        return Err(ExperimentError::BaselineLockfileMismatch(name).into());
+    }
+
+    Ok(())
+}
+
+/// Ensure the queries are provided to `query` experiments only, and have distinct names.
+fn check_queries(mode: Mode, queries: &[Query]) -> Fallible<()> {
+    if mode == Mode::Query && queries.is_empty() {
+        return Err(ExperimentError::MissingQueries.into());
+    } else if mode != Mode::Query && !queries.is_empty() {
+        return Err(ExperimentError::UnexpectedQueries.into());
+    }
+
+    let mut seen = HashSet::new();
+    if let Some(query) = queries.iter().find(|query| !seen.insert(&query.name)) {
+        return Err(ExperimentError::DuplicateQuery(query.name.clone()).into());
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, failure::Fail)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub enum ExperimentError {
@@ -15,6 +85,30 @@ pub enum ExperimentError {
     AlreadyExists(String),
     #[fail(display = "duplicate toolchains provided")]
     DuplicateToolchains,
+    #[fail(display = "at least two toolchains are required")]
+    NotEnoughToolchains,
     #[fail(display = "it's only possible to edit queued experiments")]
     CanOnlyEditQueuedExperiments,
+    #[fail(display = "baseline experiment '{}' is not completed", _0)]
+    BaselineNotCompleted(String),
+    #[fail(display = "baseline experiment '{}' uses a different mode", _0)]
+    BaselineModeMismatch(String),
+    #[fail(display = "baseline experiment '{}' caps the lints differently", _0)]
+    BaselineCapLintsMismatch(String),
+    #[fail(display = "baseline experiment '{}' uses a different feature set", _0)]
+    BaselineFeaturesMismatch(String),
+    #[fail(display = "baseline experiment '{}' uses a different profile", _0)]
+    BaselineProfileMismatch(String),
+    #[fail(display = "baseline experiment '{}' uses a different lockfile", _0)]
+    BaselineLockfileMismatch(String),
+    #[fail(display = "the first toolchain of an experiment with a baseline can't be changed")]
+    CantEditBaselineToolchain,
+    #[fail(display = "experiment '{}' is the baseline of other experiments", _0)]
+    UsedAsBaseline(String),
+    #[fail(display = "query experiments require at least one query")]
+    MissingQueries,
+    #[fail(display = "queries can only be provided to query experiments")]
+    UnexpectedQueries,
+    #[fail(display = "duplicate query name: {}", _0)]
+    DuplicateQuery(String),
 }
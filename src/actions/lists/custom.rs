@@ -0,0 +1,259 @@
+use crate::actions::lists::{check_list_name, format_names, ListError};
+use crate::actions::{Action, ActionsCtx};
+use crate::crates::lists::{crate_name, find_crates, CustomList};
+use crate::crates::Crate;
+use crate::db::Database;
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// Find the latest version of the named crates, failing if any of them is missing.
+fn resolve_crates(db: &Database, names: &HashSet<String>) -> Fallible<Vec<Crate>> {
+    let (crates, missing) = find_crates(db, names)?;
+    if !missing.is_empty() {
+        return Err(ListError::MissingCrates(format_names(&missing)).into());
+    }
+
+    Ok(crates)
+}
+
+fn get_list(db: &Database, name: &str) -> Fallible<CustomList> {
+    CustomList::get(db, name)?.ok_or_else(|| ListError::NotFound(name.to_string()).into())
+}
+
+pub struct CreateList {
+    pub name: String,
+    pub crates: HashSet<String>,
+}
+
+impl Action for CreateList {
+    fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
+        check_list_name(&self.name)?;
+        if CustomList::exists(&ctx.db, &self.name)? {
+            return Err(ListError::AlreadyExists(self.name).into());
+        }
+
+        let list = CustomList {
+            crates: resolve_crates(&ctx.db, &self.crates)?,
+            name: self.name,
+        };
+        list.store(&ctx.db)?;
+
+        info!(
+            "created the {} list with {} crates",
+            list.name,
+            list.crates.len()
+        );
+        Ok(())
+    }
+}
+
+pub struct AddToList {
+    pub name: String,
+    pub crates: HashSet<String>,
+}
+
+impl Action for AddToList {
+    fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
+        let mut list = get_list(&ctx.db, &self.name)?;
+
+        // Crates already in the list are replaced with their latest version
+        list.crates
+            .retain(|krate| crate_name(krate).map_or(true, |name| !self.crates.contains(&name)));
+        list.crates
+            .append(&mut resolve_crates(&ctx.db, &self.crates)?);
+        list.store(&ctx.db)?;
+
+        Ok(())
+    }
+}
+
+pub struct RemoveFromList {
+    pub name: String,
+    pub crates: HashSet<String>,
+}
+
+impl Action for RemoveFromList {
+    fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
+        let mut list = get_list(&ctx.db, &self.name)?;
+
+        let mut missing = self.crates.clone();
+        list.crates.retain(|krate| match crate_name(krate) {
+            Some(ref name) if self.crates.contains(name) => {
+                missing.remove(name);
+                false
+            }
+            _ => true,
+        });
+        if !missing.is_empty() {
+            return Err(ListError::NotInList(self.name, format_names(&missing)).into());
+        }
+        list.store(&ctx.db)?;
+
+        Ok(())
+    }
+}
+
+pub struct DeleteList {
+    pub name: String,
+}
+
+impl Action for DeleteList {
+    fn apply(self, ctx: &ActionsCtx) -> Fallible<()> {
+        if !CustomList::exists(&ctx.db, &self.name)? {
+            return Err(ListError::NotFound(self.name).into());
+        }
+
+        CustomList::delete(&ctx.db, &self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AddToList, CreateList, DeleteList, RemoveFromList};
+    use crate::actions::{Action, ActionsCtx, ListError};
+    use crate::config::Config;
+    use crate::crates::lists::get_crates;
+    use crate::crates::{Crate, CustomList, RegistryCrate};
+    use crate::db::{Database, QueryUtils};
+    use crate::experiments::CrateSelect;
+    use chrono::Utc;
+    use std::collections::HashSet;
+
+    fn registry_crate(name: &str, version: &str) -> Crate {
+        Crate::Registry(RegistryCrate {
+            name: name.into(),
+            version: version.into(),
+        })
+    }
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn list_crates(db: &Database, name: &str) -> Vec<Crate> {
+        let mut crates = CustomList::get(db, name).unwrap().unwrap().crates;
+        crates.sort();
+        crates
+    }
+
+    #[test]
+    fn test_custom_lists() {
+        let db = Database::temp().unwrap();
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        for krate in &[
+            registry_crate("cortex-m", "0.6.0"),
+            registry_crate("embedded-hal", "0.2.3"),
+            registry_crate("syn", "1.0.0"),
+        ] {
+            db.execute(
+                "INSERT INTO crates (crate, list, loaded_at) VALUES (?1, 'registry', ?2);",
+                &[&krate.id(), &Utc::now()],
+            )
+            .unwrap();
+        }
+
+        CreateList {
+            name: "embedded".into(),
+            crates: names(&["cortex-m"]),
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert_eq!(
+            list_crates(&db, "embedded"),
+            vec![registry_crate("cortex-m", "0.6.0")]
+        );
+
+        // Lists can't be created twice, or with the name of a built-in list
+        let err = CreateList {
+            name: "embedded".into(),
+            crates: HashSet::new(),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ListError::AlreadyExists("embedded".into()))
+        );
+        let err = CreateList {
+            name: "registry".into(),
+            crates: HashSet::new(),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ListError::ReservedName("registry".into()))
+        );
+
+        AddToList {
+            name: "embedded".into(),
+            crates: names(&["cortex-m", "embedded-hal"]),
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert_eq!(
+            list_crates(&db, "embedded"),
+            vec![
+                registry_crate("cortex-m", "0.6.0"),
+                registry_crate("embedded-hal", "0.2.3"),
+            ]
+        );
+
+        assert_eq!(
+            get_crates(&CrateSelect::Named("embedded".into()), &db, &config).unwrap(),
+            list_crates(&db, "embedded")
+        );
+
+        let err = AddToList {
+            name: "embedded".into(),
+            crates: names(&["missing"]),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ListError::MissingCrates("missing".into()))
+        );
+
+        let err = RemoveFromList {
+            name: "embedded".into(),
+            crates: names(&["cortex-m", "syn"]),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ListError::NotInList("embedded".into(), "syn".into()))
+        );
+        RemoveFromList {
+            name: "embedded".into(),
+            crates: names(&["cortex-m"]),
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert_eq!(
+            list_crates(&db, "embedded"),
+            vec![registry_crate("embedded-hal", "0.2.3")]
+        );
+
+        DeleteList {
+            name: "embedded".into(),
+        }
+        .apply(&ctx)
+        .unwrap();
+        assert!(CustomList::get(&db, "embedded").unwrap().is_none());
+        assert!(CustomList::all(&db).unwrap().is_empty());
+
+        let err = DeleteList {
+            name: "embedded".into(),
+        }
+        .apply(&ctx)
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&ListError::NotFound("embedded".into()))
+        );
+    }
+}
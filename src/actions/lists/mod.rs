@@ -1,3 +1,52 @@
+mod custom;
 mod update;
 
+pub use self::custom::{AddToList, CreateList, DeleteList, RemoveFromList};
 pub use self::update::UpdateLists;
+
+use crate::crates::lists::{GitHubList, List, LocalList, RegistryList};
+use crate::prelude::*;
+
+/// Ensure the name can be used for a custom list, without conflicting with the built-in lists.
+fn check_list_name(name: &str) -> Fallible<()> {
+    if [RegistryList::NAME, GitHubList::NAME, LocalList::NAME].contains(&name) {
+        return Err(ListError::ReservedName(name.to_string()).into());
+    }
+
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(valid_char) {
+        return Err(ListError::InvalidName(name.to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Format a set of crate names for the error messages, in a stable order.
+fn format_names<'a>(names: impl IntoIterator<Item = &'a String>) -> String {
+    let mut names = names
+        .into_iter()
+        .map(|name| name.as_str())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    names.join(", ")
+}
+
+#[derive(Debug, failure::Fail)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+pub enum ListError {
+    #[fail(display = "list '{}' not found", _0)]
+    NotFound(String),
+    #[fail(display = "list '{}' already exists", _0)]
+    AlreadyExists(String),
+    #[fail(display = "'{}' is the name of a built-in list", _0)]
+    ReservedName(String),
+    #[fail(
+        display = "invalid list name (only letters, digits, `-` and `_` are allowed): {}",
+        _0
+    )]
+    InvalidName(String),
+    #[fail(display = "crates not found: {}", _0)]
+    MissingCrates(String),
+    #[fail(display = "crates not in list '{}': {}", _0, _1)]
+    NotInList(String, String),
+}
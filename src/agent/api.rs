@@ -2,7 +2,7 @@ use crate::agent::Capabilities;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::TestResult;
+use crate::results::{ResultExtras, TestResult};
 use crate::server::api_types::{AgentConfig, ApiResponse, CraterToken};
 use crate::toolchain::Toolchain;
 use crate::utils;
@@ -149,8 +149,10 @@ impl AgentApi {
         ex: &Experiment,
         krate: &Crate,
         toolchain: &Toolchain,
+        compiler: Option<&str>,
         log: &[u8],
         result: &TestResult,
+        extras: &ResultExtras,
         version: Option<(&Crate, &Crate)>,
     ) -> Fallible<()> {
         self.retry(|this| {
@@ -162,8 +164,10 @@ impl AgentApi {
                         {
                             "crate": krate,
                             "toolchain": toolchain,
+                            "compiler": compiler,
                             "result": result,
                             "log": base64::encode(log),
+                            "extras": extras,
                         },
                     ],
                     "version": version
@@ -174,6 +178,26 @@ impl AgentApi {
         })
     }
 
+    pub fn reuse_cached_result(
+        &self,
+        ex: &Experiment,
+        krate: &Crate,
+        toolchain: &Toolchain,
+        compiler: &str,
+    ) -> Fallible<Option<TestResult>> {
+        self.retry(|this| {
+            this.build_request(Method::POST, "reuse-cached-result")
+                .json(&json!({
+                    "experiment-name": ex.name,
+                    "crate": krate,
+                    "toolchain": toolchain,
+                    "compiler": compiler,
+                }))
+                .send()?
+                .to_api_response()
+        })
+    }
+
     pub fn heartbeat(&self) -> Fallible<()> {
         self.retry(|this| {
             let _: bool = this
@@ -3,16 +3,19 @@ use crate::config::Config;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::{EncodingType, TestResult, WriteResults};
+use crate::results::{EncodingType, ResultExtras, TestResult, WriteResults};
 use crate::toolchain::Toolchain;
 use rustwide::logging::{self, LogStorage};
-use std::collections::{hash_map::Entry::Occupied, HashMap};
+use std::collections::{hash_map::Entry::Occupied, HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 #[derive(Clone)]
 pub struct ResultsUploader<'a> {
     api: &'a AgentApi,
     versions: Arc<Mutex<HashMap<Crate, (Crate, bool)>>>,
+    cache_checked: Arc<Mutex<HashSet<(Crate, Toolchain)>>>,
+    // The extras are sent to the server along with the result of the crate
+    extras: Arc<Mutex<HashMap<(Crate, Toolchain), ResultExtras>>>,
 }
 
 impl<'a> ResultsUploader<'a> {
@@ -20,6 +23,8 @@ impl<'a> ResultsUploader<'a> {
         ResultsUploader {
             api,
             versions: Arc::new(Mutex::new(HashMap::new())),
+            cache_checked: Arc::new(Mutex::new(HashSet::new())),
+            extras: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -35,6 +40,28 @@ impl<'a> WriteResults for ResultsUploader<'a> {
         Ok(None)
     }
 
+    fn reuse_cached_result(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        compiler: &str,
+        krate: &Crate,
+        _config: &Config,
+    ) -> Fallible<Option<TestResult>> {
+        // The server is asked only once for each task, as the tasks graph checks whether a task
+        // needs to be executed every time it's walked.
+        if !self
+            .cache_checked
+            .lock()
+            .unwrap()
+            .insert((krate.clone(), toolchain.clone()))
+        {
+            return Ok(None);
+        }
+
+        self.api.reuse_cached_result(ex, krate, toolchain, compiler)
+    }
+
     fn update_crate_version(&self, _ex: &Experiment, old: &Crate, new: &Crate) -> Fallible<()> {
         self.versions
             .lock()
@@ -43,10 +70,31 @@ impl<'a> WriteResults for ResultsUploader<'a> {
         Ok(())
     }
 
+    fn record_extras<F>(
+        &self,
+        _ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        update: F,
+    ) -> Fallible<()>
+    where
+        F: FnOnce(&mut ResultExtras),
+    {
+        update(
+            self.extras
+                .lock()
+                .unwrap()
+                .entry((krate.clone(), toolchain.clone()))
+                .or_default(),
+        );
+        Ok(())
+    }
+
     fn record_result<F>(
         &self,
         ex: &Experiment,
         toolchain: &Toolchain,
+        compiler: Option<&str>,
         krate: &Crate,
         existing_logs: Option<LogStorage>,
         config: &Config,
@@ -59,6 +107,12 @@ impl<'a> WriteResults for ResultsUploader<'a> {
         let storage = existing_logs.unwrap_or_else(|| LogStorage::from(config));
         let result = logging::capture(&storage, f)?;
         let output = storage.to_string();
+        let extras = self
+            .extras
+            .lock()
+            .unwrap()
+            .remove(&(krate.clone(), toolchain.clone()))
+            .unwrap_or_default();
 
         let mut updated = None;
         let mut new_version = None;
@@ -85,8 +139,10 @@ impl<'a> WriteResults for ResultsUploader<'a> {
             ex,
             updated.as_ref().unwrap_or(krate),
             toolchain,
+            compiler,
             output.as_bytes(),
             &result,
+            &extras,
             new_version.map(|new| (krate, new)),
         )?;
 
@@ -71,6 +71,12 @@ load_files! {
         "report/layout.html",
         "report/downloads.html",
         "report/results.html",
+        "report/compile-time.html",
+        "report/lints.html",
+        "report/future-incompat.html",
+        "report/formatting.html",
+        "report/unstable-features.html",
+        "report/targets.html",
     ],
     assets: [
         "ui.css" => mime::TEXT_CSS,
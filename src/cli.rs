@@ -12,14 +12,17 @@
 use crater::actions::{self, Action, ActionsCtx};
 use crater::agent::{self, Capabilities};
 use crater::config::Config;
-use crater::crates::Crate;
+use crater::crates::{Crate, CustomList};
 use crater::db::Database;
-use crater::experiments::{Assignee, CapLints, DeferredCrateSelect, Experiment, Mode, Status};
+use crater::experiments::{
+    Assignee, CapLints, CargoProfile, DeferredCrateSelect, Experiment, FeatureSet, LockfilePolicy,
+    Mode, Query, Status,
+};
 use crater::report;
 use crater::results::{DatabaseDB, DeleteResults};
 use crater::runner;
 use crater::server;
-use crater::toolchain::Toolchain;
+use crater::toolchain::{Toolchain, ToolchainList};
 use failure::{bail, Error, Fallible};
 use rustwide::{cmd::SandboxImage, Workspace, WorkspaceBuilder};
 use std::collections::HashSet;
@@ -98,6 +101,12 @@ pub enum Crater {
         lists: Vec<String>,
     },
 
+    #[structopt(name = "list", about = "manage the custom lists of crates")]
+    List {
+        #[structopt(subcommand)]
+        cmd: ListCommand,
+    },
+
     #[structopt(name = "define-ex", about = "define an experiment")]
     DefineEx {
         #[structopt(name = "experiment", long = "ex", default_value = "default")]
@@ -105,7 +114,12 @@ pub enum Crater {
         #[structopt(name = "tc-1")]
         tc1: Toolchain,
         #[structopt(name = "tc-2")]
-        tc2: Toolchain,
+        tc2: Option<Toolchain>,
+        #[structopt(
+            name = "tc-extra",
+            help = "Additional toolchains to compare against tc-1, in the same experiment."
+        )]
+        extra_toolchains: Vec<Toolchain>,
         #[structopt(
             name = "mode",
             long = "mode",
@@ -121,8 +135,20 @@ pub enum Crater {
             help = "The set of crates on which the experiment will run.",
             long_help = "The set of crates on which the experiment will run.\n\n\
                          This can be one of (full, demo, random-{d}, top-{d}, local) \
-                         where {d} is a positive integer, or \"list:\" followed \
-                         by a comma-separated list of crates.",
+                         where {d} is a positive integer, \"sample-{d}\" optionally \
+                         followed by \":{seed}\" and \":{strata}\" (e.g. \
+                         sample-500:42:1000=50,10000=30,rest=20), \"list:\" followed \
+                         by a comma-separated list of crates, \"named:\" followed \
+                         by the name of a custom list, \"rdeps:\" followed \
+                         by the name of a crate and optionally \":{d}\" to select the \
+                         crates depending on it up to {d} levels away, \"from-ex:\" \
+                         followed by the name of an experiment, a colon and a \
+                         comma-separated list of comparisons (e.g. regressed) to select \
+                         the crates with those results in that experiment, or \
+                         \"versions:{crate}:{requirement}\" to select the versions of \
+                         a crate matching a semver requirement. Any selection followed \
+                         by \":last-{d}\" selects the last {d} versions of each of \
+                         its crates.",
             raw(default_value = "\"demo\"",)
         )]
         crates: DeferredCrateSelect,
@@ -143,6 +169,59 @@ pub enum Crater {
         assign: Option<Assignee>,
         #[structopt(name = "requirement", long = "requirement")]
         requirement: Option<String>,
+        #[structopt(
+            name = "verify-runs",
+            long = "verify-runs",
+            default_value = "0",
+            help = "How many times regressed and fixed crates are run again to detect spurious results."
+        )]
+        verify_runs: u32,
+        #[structopt(
+            name = "baseline",
+            long = "baseline",
+            help = "Completed experiment whose last toolchain replaces the start toolchain: its \
+                    results are reused, and tc-1 is compared against them."
+        )]
+        baseline: Option<String>,
+        #[structopt(
+            name = "features",
+            long = "features",
+            default_value = "default",
+            help = "The combinations of Cargo features each crate is built with.",
+            long_help = "The combinations of Cargo features each crate is built with.\n\n\
+                         This can be one of (default, all-features, no-default-features, \
+                         powerset-{d}) where {d} is the maximum number of combinations."
+        )]
+        features: FeatureSet,
+        #[structopt(
+            name = "profile",
+            long = "profile",
+            default_value = "dev",
+            help = "The Cargo profile the crates are built with.",
+            long_help = "The Cargo profile the crates are built with.\n\n\
+                         This can be dev, release or a custom profile defined by \
+                         the crates, optionally followed by +opt-level={l} and \
+                         +debug-assertions={on,off} to override the settings of \
+                         the profile."
+        )]
+        profile: CargoProfile,
+        #[structopt(
+            name = "lockfile",
+            long = "lockfile",
+            default_value = "as-prepared",
+            raw(possible_values = "LockfilePolicy::possible_values()"),
+            help = "How the lockfile of each crate is updated before building it."
+        )]
+        lockfile: LockfilePolicy,
+        #[structopt(
+            name = "query",
+            long = "query",
+            help = "A regex searched in the source code of the crates by query experiments.",
+            long_help = "A regex searched in the source code of the crates by query experiments.\n\n\
+                         The query must be written as {name}={regex}, and can be repeated to \
+                         search multiple regexes."
+        )]
+        queries: Vec<Query>,
     },
 
     #[structopt(name = "edit", about = "edit an experiment configuration")]
@@ -153,6 +232,12 @@ pub enum Crater {
         tc1: Option<Toolchain>,
         #[structopt(name = "toolchain-end", long = "end")]
         tc2: Option<Toolchain>,
+        #[structopt(
+            name = "toolchains-extra",
+            long = "extra",
+            help = "Comma-separated list of additional toolchains to compare against the start one."
+        )]
+        extra_toolchains: Option<ToolchainList>,
         #[structopt(
             name = "mode",
             long = "mode",
@@ -193,6 +278,20 @@ pub enum Crater {
         assign: Option<Assignee>,
         #[structopt(name = "requirement", long = "requirement")]
         requirement: Option<String>,
+        #[structopt(name = "verify-runs", long = "verify-runs")]
+        verify_runs: Option<u32>,
+        #[structopt(name = "features", long = "features")]
+        features: Option<FeatureSet>,
+        #[structopt(name = "profile", long = "profile")]
+        profile: Option<CargoProfile>,
+        #[structopt(
+            name = "lockfile",
+            long = "lockfile",
+            raw(possible_values = "LockfilePolicy::possible_values()")
+        )]
+        lockfile: Option<LockfilePolicy>,
+        #[structopt(name = "query", long = "query")]
+        queries: Vec<Query>,
     },
 
     #[structopt(name = "delete-ex", about = "delete shared data for experiment")]
@@ -318,9 +417,108 @@ pub enum Crater {
     },
 }
 
+#[derive(structopt_derive::StructOpt)]
+pub enum ListCommand {
+    #[structopt(name = "create", about = "create a custom list of crates")]
+    Create {
+        #[structopt(name = "list")]
+        name: String,
+        #[structopt(name = "crates")]
+        crates: Vec<String>,
+    },
+
+    #[structopt(name = "add", about = "add crates to a custom list")]
+    Add {
+        #[structopt(name = "list")]
+        name: String,
+        #[structopt(name = "crates", raw(required = "true"))]
+        crates: Vec<String>,
+    },
+
+    #[structopt(name = "remove", about = "remove crates from a custom list")]
+    Remove {
+        #[structopt(name = "list")]
+        name: String,
+        #[structopt(name = "crates", raw(required = "true"))]
+        crates: Vec<String>,
+    },
+
+    #[structopt(name = "delete", about = "delete a custom list")]
+    Delete {
+        #[structopt(name = "list")]
+        name: String,
+    },
+
+    #[structopt(
+        name = "show",
+        about = "show the custom lists, or the crates of one of them"
+    )]
+    Show {
+        #[structopt(name = "list")]
+        name: Option<String>,
+    },
+}
+
+impl ListCommand {
+    fn run(&self) -> Fallible<()> {
+        let config = Config::load()?;
+        let db = Database::open()?;
+        let ctx = ActionsCtx::new(&db, &config);
+
+        match *self {
+            ListCommand::Create {
+                ref name,
+                ref crates,
+            } => actions::CreateList {
+                name: name.clone(),
+                crates: crates.iter().cloned().collect(),
+            }
+            .apply(&ctx)?,
+            ListCommand::Add {
+                ref name,
+                ref crates,
+            } => actions::AddToList {
+                name: name.clone(),
+                crates: crates.iter().cloned().collect(),
+            }
+            .apply(&ctx)?,
+            ListCommand::Remove {
+                ref name,
+                ref crates,
+            } => actions::RemoveFromList {
+                name: name.clone(),
+                crates: crates.iter().cloned().collect(),
+            }
+            .apply(&ctx)?,
+            ListCommand::Delete { ref name } => {
+                actions::DeleteList { name: name.clone() }.apply(&ctx)?
+            }
+            ListCommand::Show { name: None } => {
+                for list in CustomList::all(&db)? {
+                    println!("{} ({} crates)", list.name, list.crates.len());
+                }
+            }
+            ListCommand::Show {
+                name: Some(ref name),
+            } => {
+                if let Some(list) = CustomList::get(&db, name)? {
+                    for krate in &list.crates {
+                        println!("{}", krate);
+                    }
+                } else {
+                    bail!("missing list {}", name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Crater {
     pub fn run(&self) -> Fallible<()> {
         match *self {
+            Crater::List { ref cmd } => cmd.run()?,
             Crater::CreateLists { ref lists } => {
                 let mut lists: HashSet<_> = lists.iter().map(|s| s.as_str()).collect();
 
@@ -354,6 +552,7 @@ impl Crater {
                 ref ex,
                 ref tc1,
                 ref tc2,
+                ref extra_toolchains,
                 ref mode,
                 ref crates,
                 ref cap_lints,
@@ -361,14 +560,24 @@ impl Crater {
                 ref ignore_blacklist,
                 ref assign,
                 ref requirement,
+                ref verify_runs,
+                ref baseline,
+                ref features,
+                ref profile,
+                ref lockfile,
+                ref queries,
             } => {
                 let config = Config::load()?;
                 let db = Database::open()?;
                 let ctx = ActionsCtx::new(&db, &config);
 
+                let mut toolchains = vec![tc1.clone()];
+                toolchains.extend(tc2.iter().cloned());
+                toolchains.extend(extra_toolchains.iter().cloned());
+
                 actions::CreateExperiment {
                     name: ex.0.clone(),
-                    toolchains: [tc1.clone(), tc2.clone()],
+                    toolchains,
                     mode: *mode,
                     crates: crates.clone().resolve()?,
                     cap_lints: *cap_lints,
@@ -377,6 +586,12 @@ impl Crater {
                     ignore_blacklist: *ignore_blacklist,
                     assign: assign.clone(),
                     requirement: requirement.clone(),
+                    verify_runs: *verify_runs,
+                    baseline: baseline.clone(),
+                    features: *features,
+                    profile: profile.clone(),
+                    lockfile: *lockfile,
+                    queries: queries.clone(),
                 }
                 .apply(&ctx)?;
             }
@@ -384,6 +599,7 @@ impl Crater {
                 ref name,
                 ref tc1,
                 ref tc2,
+                ref extra_toolchains,
                 ref mode,
                 ref crates,
                 ref cap_lints,
@@ -392,6 +608,11 @@ impl Crater {
                 ref no_ignore_blacklist,
                 ref assign,
                 ref requirement,
+                ref verify_runs,
+                ref features,
+                ref profile,
+                ref lockfile,
+                ref queries,
             } => {
                 let config = Config::load()?;
                 let db = Database::open()?;
@@ -408,6 +629,7 @@ impl Crater {
                 actions::EditExperiment {
                     name: name.clone(),
                     toolchains: [tc1.clone(), tc2.clone()],
+                    extra_toolchains: extra_toolchains.clone().map(|list| list.0),
                     mode: *mode,
                     crates: crates.clone().map(|cs| cs.resolve()).transpose()?,
                     cap_lints: *cap_lints,
@@ -415,6 +637,15 @@ impl Crater {
                     ignore_blacklist,
                     assign: assign.clone(),
                     requirement: requirement.clone(),
+                    verify_runs: *verify_runs,
+                    features: *features,
+                    profile: profile.clone(),
+                    lockfile: *lockfile,
+                    queries: if queries.is_empty() {
+                        None
+                    } else {
+                        Some(queries.clone())
+                    },
                 }
                 .apply(&ctx)?;
             }
@@ -44,6 +44,8 @@ pub struct ServerConfig {
     pub bot_acl: BotACL,
     pub labels: ServerLabels,
     pub distributed: ChunkConfig,
+    #[serde(default)]
+    pub results_cache: ResultsCacheConfig,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -84,6 +86,45 @@ pub struct ChunkConfig {
     pub chunk_size: i32,
 }
 
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResultsCacheConfig {
+    /// How many days a result can be reused by other experiments, or 0 to disable the cache.
+    pub max_age_days: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct CompileTimeConfig {
+    /// How many times each crate is built from scratch, to reduce the noise of the measurements.
+    pub samples: u32,
+    /// Minimum change in percent of the build time or the peak memory usage to report a crate
+    /// as slower or faster.
+    pub threshold: f64,
+}
+
+impl Default for CompileTimeConfig {
+    fn default() -> Self {
+        CompileTimeConfig {
+            samples: 3,
+            threshold: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct MiriConfig {
+    /// Maximum time in seconds the tests of a crate can run under Miri before being killed.
+    pub timeout: u64,
+}
+
+impl Default for MiriConfig {
+    fn default() -> Self {
+        MiriConfig { timeout: 60 * 60 }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
@@ -93,6 +134,10 @@ pub struct Config {
     pub local_crates: HashMap<String, CrateConfig>,
     pub server: ServerConfig,
     pub sandbox: SandboxConfig,
+    #[serde(default)]
+    pub compile_time: CompileTimeConfig,
+    #[serde(default)]
+    pub miri: MiriConfig,
 }
 
 impl Config {
@@ -269,7 +314,10 @@ impl Default for Config {
                     experiment_completed: "".into(),
                 },
                 distributed: ChunkConfig { chunk_size: 1 },
+                results_cache: ResultsCacheConfig { max_age_days: 0 },
             },
+            compile_time: CompileTimeConfig::default(),
+            miri: MiriConfig::default(),
         }
     }
 }
@@ -301,6 +349,8 @@ mod tests {
             "experiment-completed = \"\"\n",
             "[server.distributed]\n",
             "chunk-size = 32\n",
+            "[server.results-cache]\n",
+            "max-age-days = 7\n",
             "[demo-crates]\n",
             "crates = []\n",
             "github-repos = []\n",
@@ -339,5 +389,6 @@ mod tests {
         })));
 
         assert_eq!(list.chunk_size(), 32);
+        assert_eq!(list.server.results_cache.max_age_days, 7);
     }
 }
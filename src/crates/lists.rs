@@ -1,11 +1,17 @@
 use crate::config::Config;
 use crate::crates::sources::github::GitHubRepo;
+use crate::crates::sources::registry::{
+    matching_versions, published_versions, reverse_dependencies,
+};
 use crate::crates::{Crate, RegistryCrate};
 use crate::db::{Database, QueryUtils};
-use crate::experiments::CrateSelect;
+use crate::experiments::{CrateSelect, Experiment, Sample};
 use crate::prelude::*;
+use crate::report;
+use crate::results::DatabaseDB;
 use chrono::Utc;
-use rand::{thread_rng, Rng};
+use rand::prng::ChaChaRng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::collections::HashSet;
 
 pub(crate) use crate::crates::sources::{
@@ -19,45 +25,181 @@ pub(crate) trait List {
 
     fn update(&self, db: &Database) -> Fallible<()> {
         let crates = self.fetch()?;
-
-        let now = Utc::now();
-        db.transaction(|t| {
-            // Replace the existing list in the database
-            t.execute("DELETE FROM crates WHERE list = ?1;", &[&Self::NAME])?;
-            for krate in &crates {
-                t.execute(
-                    "INSERT INTO crates (crate, list, loaded_at) VALUES (?1, ?2, ?3);",
-                    &[&krate.id(), &Self::NAME, &now],
-                )
-                .with_context(|_| {
-                    format!(
-                        "failed to insert crate {} into the {} list",
-                        krate,
-                        Self::NAME
-                    )
-                })?;
-            }
-
-            Ok(())
-        })?;
+        store_list(db, Self::NAME, &crates)?;
 
         info!("loaded {} crates in the {} list", crates.len(), Self::NAME);
         Ok(())
     }
 
     fn get(db: &Database) -> Fallible<Vec<Crate>> {
-        let crates_results = db.query(
-            "SELECT crate FROM crates WHERE list = ?1 ORDER BY rowid;",
-            &[&Self::NAME],
-            |r| {
-                let raw: String = r.get("crate");
-                Ok(raw.parse()?)
-            },
+        load_list(db, Self::NAME)
+    }
+}
+
+/// Replace the crates of the named list in the database.
+fn store_list(db: &Database, name: &str, crates: &[Crate]) -> Fallible<()> {
+    let now = Utc::now();
+    db.transaction(|t| {
+        t.execute("DELETE FROM crates WHERE list = ?1;", &[&name])?;
+        for krate in crates {
+            t.execute(
+                "INSERT INTO crates (crate, list, loaded_at) VALUES (?1, ?2, ?3);",
+                &[&krate.id(), &name, &now],
+            )
+            .with_context(|_| format!("failed to insert crate {} into the {} list", krate, name))?;
+        }
+
+        Ok(())
+    })
+}
+
+fn load_list(db: &Database, name: &str) -> Fallible<Vec<Crate>> {
+    let crates_results = db.query(
+        "SELECT crate FROM crates WHERE list = ?1 ORDER BY rowid;",
+        &[&name],
+        |r| {
+            let raw: String = r.get("crate");
+            Ok(raw.parse()?)
+        },
+    )?;
+
+    // Turns Vec<Fallible<Crate>> into Fallible<Vec<Crate>>
+    crates_results.into_iter().collect()
+}
+
+/// A list of crates created by the users, stored alongside the lists built by Crater.
+pub struct CustomList {
+    pub name: String,
+    pub crates: Vec<Crate>,
+}
+
+impl CustomList {
+    pub(crate) fn exists(db: &Database, name: &str) -> Fallible<bool> {
+        db.exists("SELECT rowid FROM custom_lists WHERE name = ?1;", &[&name])
+    }
+
+    pub fn get(db: &Database, name: &str) -> Fallible<Option<CustomList>> {
+        if !CustomList::exists(db, name)? {
+            return Ok(None);
+        }
+
+        Ok(Some(CustomList {
+            name: name.to_string(),
+            crates: load_list(db, name)?,
+        }))
+    }
+
+    pub fn all(db: &Database) -> Fallible<Vec<CustomList>> {
+        let names = db.query(
+            "SELECT name FROM custom_lists ORDER BY name;",
+            &[],
+            |r| -> String { r.get("name") },
+        )?;
+
+        names
+            .into_iter()
+            .map(|name| {
+                Ok(CustomList {
+                    crates: load_list(db, &name)?,
+                    name,
+                })
+            })
+            .collect()
+    }
+
+    /// Store the list in the database, replacing its existing crates.
+    pub(crate) fn store(&self, db: &Database) -> Fallible<()> {
+        db.execute(
+            "INSERT OR IGNORE INTO custom_lists (name, created_at) VALUES (?1, ?2);",
+            &[&self.name, &Utc::now()],
         )?;
+        store_list(db, &self.name, &self.crates)
+    }
+
+    pub(crate) fn delete(db: &Database, name: &str) -> Fallible<()> {
+        db.transaction(|t| {
+            t.execute("DELETE FROM crates WHERE list = ?1;", &[&name])?;
+            t.execute("DELETE FROM custom_lists WHERE name = ?1;", &[&name])?;
+            Ok(())
+        })
+    }
+}
+
+/// The name crates are selected by: the name of crates.io crates and the slug of GitHub repos.
+pub(crate) fn crate_name(krate: &Crate) -> Option<String> {
+    match krate {
+        Crate::Registry(RegistryCrate { ref name, .. }) => Some(name.clone()),
+        Crate::GitHub(ref repo) => Some(repo.slug()),
+        _ => None,
+    }
+}
+
+/// Find the latest crates.io crates and GitHub repos with the given names, returning the names
+/// that couldn't be found too.
+pub(crate) fn find_crates(
+    db: &Database,
+    names: &HashSet<String>,
+) -> Fallible<(Vec<Crate>, HashSet<String>)> {
+    let mut missing = names.clone();
+
+    let mut all_crates = Vec::new();
+    all_crates.append(&mut RegistryList::get(db)?);
+    all_crates.append(&mut GitHubList::get(db)?);
 
-        // Turns Vec<Fallible<Crate>> into Fallible<Vec<Crate>>
-        crates_results.into_iter().collect()
+    let found = all_crates
+        .into_iter()
+        .filter(|krate| match crate_name(krate) {
+            Some(name) => missing.remove(&name),
+            None => unreachable!(),
+        })
+        .collect();
+
+    Ok((found, missing))
+}
+
+/// Draw the sample out of the crates ranked by popularity. The seed fully determines which crates
+/// are sampled, as long as the ranking doesn't change.
+fn sample_crates(ranked: &[Crate], sample: &Sample, seed: u64) -> Vec<Crate> {
+    // ChaCha is used instead of StdRng as its output is guaranteed not to change
+    let mut rng_seed = [0; 32];
+    rng_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    let mut rng = ChaChaRng::from_seed(rng_seed);
+
+    // Whatever is left after rounding down the share of each stratum goes to the first ones
+    let mut counts = sample
+        .strata
+        .iter()
+        .map(|stratum| sample.count * stratum.percent / 100)
+        .collect::<Vec<_>>();
+    let remaining = sample.count - counts.iter().sum::<u32>();
+    for count in counts.iter_mut().take(remaining as usize) {
+        *count += 1;
     }
+
+    let mut sampled = Vec::new();
+    let mut start = 0;
+    for (stratum, count) in sample.strata.iter().zip(counts) {
+        let end = match stratum.size {
+            Some(size) => (start + size as usize).min(ranked.len()),
+            None => ranked.len(),
+        };
+
+        let mut bucket = ranked[start..end].to_vec();
+        if bucket.len() < count as usize {
+            warn!(
+                "only {} crates can be sampled out of a stratum instead of {}",
+                bucket.len(),
+                count
+            );
+        }
+        rng.shuffle(&mut bucket);
+        bucket.truncate(count as usize);
+        sampled.append(&mut bucket);
+
+        start = end;
+    }
+
+    sampled
 }
 
 pub(crate) fn get_crates(
@@ -116,27 +258,28 @@ pub(crate) fn get_crates(
             }
         }
         CrateSelect::List(list) => {
-            let mut desired = list.clone();
-
-            let mut all_crates = Vec::new();
-            all_crates.append(&mut RegistryList::get(db)?);
-            all_crates.append(&mut GitHubList::get(db)?);
+            let (mut found, missing) = find_crates(db, list)?;
+            if !missing.is_empty() {
+                bail!("missing desired crates: {:?}", missing);
+            }
 
-            for krate in all_crates {
-                let is_desired = match krate {
-                    Crate::Registry(RegistryCrate { ref name, .. }) => desired.remove(name),
-                    Crate::GitHub(ref repo) => desired.remove(&repo.slug()),
-                    _ => unreachable!(),
-                };
+            crates.append(&mut found);
+        }
+        CrateSelect::Named(name) => {
+            let list = CustomList::get(db, name)?
+                .ok_or_else(|| err_msg(format!("list {} doesn't exist", name)))?;
 
-                if is_desired {
-                    crates.push(krate);
-                }
+            // The list is resolved again to run the latest version of each crate
+            let names = list.crates.iter().filter_map(crate_name).collect();
+            let (mut found, missing) = find_crates(db, &names)?;
+            if !missing.is_empty() {
+                warn!(
+                    "crates of the {} list not available anymore: {:?}",
+                    name, missing
+                );
             }
 
-            if !desired.is_empty() {
-                bail!("missing desired crates: {:?}", desired);
-            }
+            crates.append(&mut found);
         }
 
         CrateSelect::Random(n) => {
@@ -147,6 +290,87 @@ pub(crate) fn get_crates(
             rng.shuffle(&mut crates);
             crates.truncate(*n as usize);
         }
+        CrateSelect::ReverseDeps { name, depth } => {
+            let dependents = reverse_dependencies(name, *depth)?;
+            crates.extend(
+                RegistryList::get(db)?
+                    .into_iter()
+                    .filter(|krate| match krate {
+                        Crate::Registry(krate) => dependents.contains(&krate.name),
+                        _ => false,
+                    }),
+            );
+        }
+        CrateSelect::FromExperiment { name, comparisons } => {
+            let ex = Experiment::get(db, name)?
+                .ok_or_else(|| err_msg(format!("experiment {} doesn't exist", name)))?;
+            let ex_crates = ex.get_crates(db)?;
+            crates.append(&mut report::crates_with_comparisons(
+                &DatabaseDB::new(db),
+                config,
+                &ex,
+                &ex_crates,
+                comparisons,
+            )?);
+        }
+        CrateSelect::Versions { name, req } => {
+            let mut names = HashSet::new();
+            names.insert(name.clone());
+            let versions = published_versions(&names)?;
+            let versions = versions.get(name).map(Vec::as_slice).unwrap_or_default();
+
+            let matching = matching_versions(versions, &req.parse()?);
+            if matching.is_empty() {
+                bail!("no version of {} matches {}", name, req);
+            }
+            crates.extend(matching.into_iter().map(|version| {
+                Crate::Registry(RegistryCrate {
+                    name: name.clone(),
+                    version: version.to_string(),
+                })
+            }));
+        }
+        CrateSelect::LastVersions { select, count } => {
+            let selected = get_crates(select, db, config)?;
+            let names = selected
+                .iter()
+                .filter_map(|krate| match krate {
+                    Crate::Registry(krate) => Some(krate.name.clone()),
+                    _ => None,
+                })
+                .collect();
+            let versions = published_versions(&names)?;
+
+            for krate in selected {
+                match krate {
+                    Crate::Registry(RegistryCrate { ref name, .. })
+                        if versions.contains_key(name) =>
+                    {
+                        let versions = &versions[name];
+                        let skip = versions.len().saturating_sub(*count as usize);
+                        crates.extend(versions[skip..].iter().map(|version| {
+                            Crate::Registry(RegistryCrate {
+                                name: name.clone(),
+                                version: version.clone(),
+                            })
+                        }));
+                    }
+                    // Crates not on crates.io only have one version to test
+                    krate => crates.push(krate),
+                }
+            }
+        }
+        CrateSelect::Sample(sample) => {
+            let seed = match sample.seed {
+                Some(seed) => seed,
+                None => {
+                    let seed = thread_rng().gen();
+                    warn!("no seed provided for {}, the sample uses {}", sample, seed);
+                    seed
+                }
+            };
+            crates.append(&mut sample_crates(&RegistryList::get(db)?, sample, seed));
+        }
         CrateSelect::Top(n) => {
             crates.append(&mut RegistryList::get(db)?);
             crates.truncate(*n as usize);
@@ -172,3 +396,49 @@ pub(crate) fn setup_test_lists(db: &Database, config: &Config) -> Fallible<()> {
     }
     .apply(&ActionsCtx::new(db, config))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::sample_crates;
+    use crate::crates::{Crate, RegistryCrate};
+    use crate::experiments::Sample;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_sample_crates() {
+        let ranked = (0..100)
+            .map(|i| {
+                Crate::Registry(RegistryCrate {
+                    name: format!("crate{}", i),
+                    version: "1.0.0".into(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let rank = |krate: &Crate| ranked.iter().position(|c| c == krate).unwrap();
+
+        let sample: Sample = "sample-11:42:10=50,40=30,rest=20".parse().unwrap();
+        let sampled = sample_crates(&ranked, &sample, 42);
+        assert_eq!(sampled.len(), 11);
+        assert_eq!(sampled.iter().collect::<HashSet<_>>().len(), 11);
+        // 5.5 crates from the first stratum rounded up, 3.3 and 2.2 from the other ones rounded down
+        assert_eq!(sampled.iter().filter(|&c| rank(c) < 10).count(), 6);
+        assert_eq!(
+            sampled
+                .iter()
+                .filter(|&c| rank(c) >= 10 && rank(c) < 50)
+                .count(),
+            3
+        );
+        assert_eq!(sampled.iter().filter(|&c| rank(c) >= 50).count(), 2);
+
+        // The same seed always samples the same crates, and different seeds don't
+        assert_eq!(sample_crates(&ranked, &sample, 42), sampled);
+        assert_ne!(sample_crates(&ranked, &sample, 43), sampled);
+
+        // Strata with too few crates are sampled entirely
+        let sample: Sample = "sample-50:1:5=50,rest=50".parse().unwrap();
+        let sampled = sample_crates(&ranked, &sample, 1);
+        assert_eq!(sampled.iter().filter(|&c| rank(c) < 5).count(), 5);
+        assert_eq!(sampled.len(), 30);
+    }
+}
@@ -11,8 +11,11 @@ use std::fmt;
 use std::path::Path;
 use std::str::FromStr;
 
+pub use crate::crates::lists::CustomList;
 pub(crate) use crate::crates::sources::github::GitHubRepo;
-pub(crate) use crate::crates::sources::registry::RegistryCrate;
+pub(crate) use crate::crates::sources::registry::{
+    published_versions, update_index, RegistryCrate,
+};
 
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Clone)]
 pub struct GitRepo {
@@ -1,10 +1,118 @@
 use crate::crates::{lists::List, Crate};
 use crate::dirs::WORK_DIR;
 use crate::prelude::*;
-use crates_index::Index;
-use std::collections::HashMap;
+use crates_index::{Index, Version};
+use semver::{Version as SemverVersion, VersionReq};
+use std::collections::{HashMap, HashSet};
 use std::fs::{self};
 
+/// The latest version of the crate that wasn't yanked, if any.
+fn latest_version(krate: &crates_index::Crate) -> Option<&Version> {
+    // The versions() method returns the list of published versions starting from the first one,
+    // so its output is reversed to check the latest first
+    krate
+        .versions()
+        .iter()
+        .rev()
+        .find(|version| !version.is_yanked())
+}
+
+/// Download the crates.io index, or update it if it was already downloaded.
+pub(crate) fn update_index() -> Fallible<()> {
+    fs::create_dir_all(&*WORK_DIR)?;
+    let index = Index::new(WORK_DIR.join("crates.io-index"));
+    index.retrieve_or_update().to_failure()?;
+    Ok(())
+}
+
+/// The crates.io index downloaded by `update_index`.
+fn downloaded_index() -> Fallible<Index> {
+    let index = Index::new(WORK_DIR.join("crates.io-index"));
+    if !index.exists() {
+        bail!("the crates.io index is missing, update the registry list first");
+    }
+
+    Ok(index)
+}
+
+/// The versions of the named crates that weren't yanked, in the order they were published.
+pub(crate) fn published_versions(
+    names: &HashSet<String>,
+) -> Fallible<HashMap<String, Vec<String>>> {
+    Ok(downloaded_index()?
+        .crates()
+        .filter(|krate| names.contains(krate.name()))
+        .map(|krate| {
+            let versions = krate
+                .versions()
+                .iter()
+                .filter(|version| !version.is_yanked())
+                .map(|version| version.version().to_string())
+                .collect();
+            (krate.name().to_string(), versions)
+        })
+        .collect())
+}
+
+/// The versions matching the requirement, ignoring the ones that aren't valid semver.
+pub(crate) fn matching_versions<'a>(versions: &'a [String], req: &VersionReq) -> Vec<&'a str> {
+    versions
+        .iter()
+        .filter(|version| {
+            SemverVersion::parse(version)
+                .map(|version| req.matches(&version))
+                .unwrap_or(false)
+        })
+        .map(|version| version.as_str())
+        .collect()
+}
+
+/// The names of the crates depending on `name`, either directly or through up to `depth` levels
+/// of dependencies (or any number of levels without a depth), according to the latest version
+/// of every crate in the index downloaded by `RegistryList`.
+pub(crate) fn reverse_dependencies(name: &str, depth: Option<u32>) -> Fallible<HashSet<String>> {
+    let index = downloaded_index()?;
+
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+    for krate in index.crates() {
+        if let Some(version) = latest_version(&krate) {
+            for dependency in version.dependencies() {
+                // Renamed dependencies are recorded under the name of the crate they point to
+                dependents
+                    .entry(dependency.crate_name().to_string())
+                    .or_insert_with(Vec::new)
+                    .push(krate.name().to_string());
+            }
+        }
+    }
+
+    Ok(find_reverse_dependencies(&dependents, name, depth))
+}
+
+fn find_reverse_dependencies(
+    dependents: &HashMap<String, Vec<String>>,
+    name: &str,
+    depth: Option<u32>,
+) -> HashSet<String> {
+    let mut found = HashSet::new();
+    let mut current = vec![name.to_string()];
+    let mut level = 0;
+    while !current.is_empty() && depth.map(|depth| level < depth).unwrap_or(true) {
+        let mut next = Vec::new();
+        for krate in &current {
+            for dependent in dependents.get(krate).into_iter().flatten() {
+                // Crates can depend on their own dependents through dev-dependencies
+                if dependent != name && found.insert(dependent.clone()) {
+                    next.push(dependent.clone());
+                }
+            }
+        }
+        current = next;
+        level += 1;
+    }
+    found
+}
+
 pub(crate) struct RegistryList;
 
 impl List for RegistryList {
@@ -14,29 +122,22 @@ impl List for RegistryList {
         let mut list = Vec::new();
         let mut counts = HashMap::new();
 
-        fs::create_dir_all(&*WORK_DIR)?;
-        let index = Index::new(WORK_DIR.join("crates.io-index"));
-        index.retrieve_or_update().to_failure()?;
+        update_index()?;
+        let index = downloaded_index()?;
 
         for krate in index.crates() {
-            // The versions() method returns the list of published versions starting from the
-            // first one, so its output is reversed to check the latest first
-            for version in krate.versions().iter().rev() {
-                // Try every version until we find a non-yanked one. If all the versions are
-                // yanked the crate is automatically skipped
-                if !version.is_yanked() {
-                    // Increment the counters of this crate's dependencies
-                    for dependency in version.dependencies() {
-                        let count = counts.entry(dependency.name().to_string()).or_insert(0);
-                        *count += 1;
-                    }
-
-                    list.push(Crate::Registry(RegistryCrate {
-                        name: krate.name().to_string(),
-                        version: version.version().to_string(),
-                    }));
-                    break;
+            // If all the versions are yanked the crate is automatically skipped
+            if let Some(version) = latest_version(&krate) {
+                // Increment the counters of this crate's dependencies
+                for dependency in version.dependencies() {
+                    let count = counts.entry(dependency.name().to_string()).or_insert(0);
+                    *count += 1;
                 }
+
+                list.push(Crate::Registry(RegistryCrate {
+                    name: krate.name().to_string(),
+                    version: version.version().to_string(),
+                }));
             }
         }
 
@@ -60,3 +161,51 @@ pub struct RegistryCrate {
     pub name: String,
     pub version: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{find_reverse_dependencies, matching_versions};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_matching_versions() {
+        let versions = ["0.9.0", "1.0.0", "1.3.9", "1.4.0", "1.3.10", "invalid"]
+            .iter()
+            .map(|version| version.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            matching_versions(&versions, &">=1.0, <1.4".parse().unwrap()),
+            vec!["1.0.0", "1.3.9", "1.3.10"]
+        );
+        assert!(matching_versions(&versions, &">=2".parse().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_find_reverse_dependencies() {
+        let mut dependents = HashMap::new();
+        dependents.insert(
+            "syn".to_string(),
+            vec!["quote".into(), "serde_derive".into()],
+        );
+        dependents.insert("serde_derive".to_string(), vec!["serde".into()]);
+        dependents.insert("serde".to_string(), vec!["serde_json".into(), "syn".into()]);
+
+        let find = |name: &str, depth: Option<u32>| {
+            let mut found = find_reverse_dependencies(&dependents, name, depth)
+                .into_iter()
+                .collect::<Vec<_>>();
+            found.sort();
+            found
+        };
+
+        assert_eq!(find("syn", Some(1)), vec!["quote", "serde_derive"]);
+        assert_eq!(find("syn", Some(2)), vec!["quote", "serde", "serde_derive"]);
+        assert_eq!(
+            find("syn", None),
+            vec!["quote", "serde", "serde_derive", "serde_json"]
+        );
+        assert_eq!(find("serde_json", None), Vec::<String>::new());
+        assert_eq!(find("unknown", None), Vec::<String>::new());
+    }
+}
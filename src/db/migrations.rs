@@ -363,6 +363,492 @@ fn migrations() -> Vec<(&'static str, MigrationKind)> {
         })),
     ));
 
+    migrations.push((
+        "create_experiment_toolchains_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE experiment_toolchains (
+                experiment TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                toolchain TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, position),
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+
+            INSERT INTO experiment_toolchains (experiment, position, toolchain)
+                SELECT name, 0, toolchain_start FROM experiments;
+            INSERT INTO experiment_toolchains (experiment, position, toolchain)
+                SELECT name, 1, toolchain_end FROM experiments;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_verify_runs",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN verify_runs INTEGER NOT NULL DEFAULT 0;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_results_field_retry",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE results_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                result TEXT NOT NULL,
+                log BLOB NOT NULL,
+                encoding TEXT NOT NULL DEFAULT 'plain',
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+
+            INSERT INTO results_new (experiment, crate, toolchain, result, log, encoding)
+                SELECT experiment, crate, toolchain, result, log, encoding FROM results;
+
+            DROP TABLE results;
+            ALTER TABLE results_new RENAME TO results;
+
+            CREATE INDEX results__experiment
+            ON results (experiment);
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_results_cache_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE results_cache (
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                cap_lints TEXT NOT NULL,
+                experiment TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+
+                PRIMARY KEY (crate, toolchain, mode, cap_lints) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+
+            ALTER TABLE results ADD COLUMN reused_from TEXT;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_baseline",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN baseline TEXT;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_test_outcomes_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE test_outcomes (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                test TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, test) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_ices_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE ices (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                message TEXT NOT NULL,
+                location TEXT,
+                query_stack TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_compile_times_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE compile_times (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                samples INTEGER NOT NULL,
+                wall_time_ms INTEGER NOT NULL,
+                cpu_time_ms INTEGER,
+                peak_rss_kb INTEGER,
+
+                PRIMARY KEY (experiment, crate, toolchain) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_warnings_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE warnings (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                lint TEXT NOT NULL,
+                span TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, lint, span) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_future_incompat_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE future_incompat (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                lint TEXT NOT NULL,
+                dependency TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, lint, dependency) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_rustfmt_diffs_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE rustfmt_diffs (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                files_changed INTEGER NOT NULL,
+                hunks TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_features",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN features TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE results_cache ADD COLUMN features TEXT NOT NULL DEFAULT 'default';
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_feature_results_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE feature_results (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                features TEXT NOT NULL,
+                result TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, features) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_profile",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN profile TEXT NOT NULL DEFAULT 'dev';
+            ALTER TABLE results_cache ADD COLUMN profile TEXT NOT NULL DEFAULT 'dev';
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_lockfile",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN lockfile TEXT NOT NULL DEFAULT 'as-prepared';
+            ALTER TABLE results_cache ADD COLUMN lockfile TEXT NOT NULL DEFAULT 'as-prepared';
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_lockfiles_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE lockfiles (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                policy TEXT NOT NULL,
+                content TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_queries",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN queries TEXT NOT NULL DEFAULT '[]';
+            ALTER TABLE results_cache ADD COLUMN queries TEXT NOT NULL DEFAULT '[]';
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_query_results_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE query_results (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                query TEXT NOT NULL,
+                matches INTEGER NOT NULL,
+                lines TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, query) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_unstable_features_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE unstable_features (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                feature TEXT NOT NULL,
+
+                PRIMARY KEY (experiment, crate, toolchain, feature) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "create_custom_lists_table",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE custom_lists (
+                name TEXT PRIMARY KEY,
+                created_at DATETIME NOT NULL
+            );
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_experiment_field_sample",
+        MigrationKind::SQL(
+            "
+            ALTER TABLE experiments ADD COLUMN sample TEXT;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "add_retry_to_compared_results",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE compile_times_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                samples INTEGER NOT NULL,
+                wall_time_ms INTEGER NOT NULL,
+                cpu_time_ms INTEGER,
+                peak_rss_kb INTEGER,
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO compile_times_new
+                (experiment, crate, toolchain, samples, wall_time_ms, cpu_time_ms, peak_rss_kb)
+                SELECT experiment, crate, toolchain, samples, wall_time_ms, cpu_time_ms,
+                peak_rss_kb FROM compile_times;
+            DROP TABLE compile_times;
+            ALTER TABLE compile_times_new RENAME TO compile_times;
+
+            CREATE TABLE warnings_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                lint TEXT NOT NULL,
+                span TEXT NOT NULL,
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry, lint, span) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO warnings_new (experiment, crate, toolchain, lint, span)
+                SELECT experiment, crate, toolchain, lint, span FROM warnings;
+            DROP TABLE warnings;
+            ALTER TABLE warnings_new RENAME TO warnings;
+
+            CREATE TABLE future_incompat_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                lint TEXT NOT NULL,
+                dependency TEXT NOT NULL,
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry, lint, dependency)
+                    ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO future_incompat_new (experiment, crate, toolchain, lint, dependency)
+                SELECT experiment, crate, toolchain, lint, dependency FROM future_incompat;
+            DROP TABLE future_incompat;
+            ALTER TABLE future_incompat_new RENAME TO future_incompat;
+
+            CREATE TABLE rustfmt_diffs_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                files_changed INTEGER NOT NULL,
+                hunks TEXT NOT NULL,
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO rustfmt_diffs_new (experiment, crate, toolchain, files_changed, hunks)
+                SELECT experiment, crate, toolchain, files_changed, hunks FROM rustfmt_diffs;
+            DROP TABLE rustfmt_diffs;
+            ALTER TABLE rustfmt_diffs_new RENAME TO rustfmt_diffs;
+
+            CREATE TABLE feature_results_new (
+                experiment TEXT NOT NULL,
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                features TEXT NOT NULL,
+                result TEXT NOT NULL,
+                retry INTEGER NOT NULL DEFAULT 0,
+
+                PRIMARY KEY (experiment, crate, toolchain, retry, features) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO feature_results_new (experiment, crate, toolchain, features, result)
+                SELECT experiment, crate, toolchain, features, result FROM feature_results;
+            DROP TABLE feature_results;
+            ALTER TABLE feature_results_new RENAME TO feature_results;
+            ",
+        ),
+    ));
+
+    migrations.push((
+        "widen_results_cache_key",
+        MigrationKind::SQL(
+            "
+            CREATE TABLE results_cache_new (
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                cap_lints TEXT NOT NULL,
+                features TEXT NOT NULL,
+                profile TEXT NOT NULL,
+                lockfile TEXT NOT NULL,
+                queries TEXT NOT NULL,
+                experiment TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+
+                PRIMARY KEY (crate, toolchain, mode, cap_lints, features, profile, lockfile, queries)
+                    ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            INSERT INTO results_cache_new
+                (crate, toolchain, mode, cap_lints, features, profile, lockfile, queries,
+                experiment, created_at)
+                SELECT crate, toolchain, mode, cap_lints, features, profile, lockfile, queries,
+                experiment, created_at FROM results_cache;
+            DROP TABLE results_cache;
+            ALTER TABLE results_cache_new RENAME TO results_cache;
+            ",
+        ),
+    ));
+
+    // The compiler the cached results were built with wasn't recorded before, so they can't be
+    // reused anymore
+    migrations.push((
+        "key_results_cache_on_compiler",
+        MigrationKind::SQL(
+            "
+            DROP TABLE results_cache;
+            CREATE TABLE results_cache (
+                crate TEXT NOT NULL,
+                toolchain TEXT NOT NULL,
+                compiler TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                cap_lints TEXT NOT NULL,
+                features TEXT NOT NULL,
+                profile TEXT NOT NULL,
+                lockfile TEXT NOT NULL,
+                queries TEXT NOT NULL,
+                experiment TEXT NOT NULL,
+                created_at DATETIME NOT NULL,
+
+                PRIMARY KEY (
+                    crate, toolchain, compiler, mode, cap_lints, features, profile, lockfile,
+                    queries
+                ) ON CONFLICT REPLACE,
+                FOREIGN KEY (experiment) REFERENCES experiments(name) ON DELETE CASCADE
+            );
+            ",
+        ),
+    ));
+
     migrations
 }
 
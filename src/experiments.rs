@@ -2,12 +2,15 @@ use crate::config::Config;
 use crate::crates::Crate;
 use crate::db::{Database, QueryUtils};
 use crate::prelude::*;
-use crate::results::TestResult;
+use crate::report::Comparison;
+use crate::results::{DatabaseDB, TestResult};
 use crate::toolchain::Toolchain;
 use crate::utils;
 use chrono::{DateTime, Utc};
+use regex::Regex;
 use rusqlite::Row;
-use std::collections::HashSet;
+use semver::VersionReq;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::str::FromStr;
 use url::Url;
@@ -19,6 +22,7 @@ static SQL_VARIABLE_LIMIT: usize = 500;
 string_enum!(pub enum Status {
     Queued => "queued",
     Running => "running",
+    Verifying => "verifying",
     NeedsReport => "needs-report",
     Failed => "failed",
     GeneratingReport => "generating-report",
@@ -33,6 +37,14 @@ string_enum!(pub enum Mode {
     Clippy => "clippy",
     Rustdoc => "rustdoc",
     UnstableFeatures => "unstable-features",
+    CompileTime => "compile-time",
+    LintDiff => "lint-diff",
+    FutureIncompat => "future-incompat",
+    Miri => "miri",
+    Rustfmt => "rustfmt",
+    EditionMigration => "edition-migration",
+    SemverCheck => "semver-check",
+    Query => "query",
 });
 
 string_enum!(pub enum CapLints {
@@ -53,12 +65,66 @@ pub enum CrateSelect {
     Dummy,
     Random(u32),
     List(HashSet<String>),
+    /// The crates of a custom list stored in the database.
+    Named(String),
+    /// The crates depending on the named crate, up to `depth` levels of dependencies away (or at
+    /// any level without a depth).
+    ReverseDeps {
+        name: String,
+        depth: Option<u32>,
+    },
+    /// The crates of a previous experiment whose results compared in one of the listed ways.
+    FromExperiment {
+        name: String,
+        comparisons: Vec<Comparison>,
+    },
+    /// The versions of a crates.io crate matching the requirement, excluding the yanked ones. The
+    /// requirement is kept as written, and parsed when the crates are selected.
+    Versions {
+        name: String,
+        req: String,
+    },
+    /// The last `count` versions published on crates.io of each crates.io crate in the selection,
+    /// excluding the yanked ones.
+    LastVersions {
+        select: Box<CrateSelect>,
+        count: u32,
+    },
+    Sample(Sample),
+}
+
+impl CrateSelect {
+    /// The sample of crates.io crates this selection is based on, if any.
+    pub fn sample_mut(&mut self) -> Option<&mut Sample> {
+        match self {
+            CrateSelect::Sample(sample) => Some(sample),
+            CrateSelect::LastVersions { select, .. } => select.sample_mut(),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for CrateSelect {
     type Err = failure::Error;
 
     fn from_str(s: &str) -> failure::Fallible<Self> {
+        if let Some(pos) = s.rfind(":last-") {
+            let select: CrateSelect = s[..pos].parse()?;
+            let count: u32 = s[pos + ":last-".len()..].parse()?;
+            match select {
+                CrateSelect::Versions { .. } | CrateSelect::LastVersions { .. } => {
+                    bail!("`last-` can't be used to select versions again: {}", s)
+                }
+                _ if count == 0 => bail!("at least one version must be selected: {}", s),
+                select => {
+                    return Ok(CrateSelect::LastVersions {
+                        select: Box::new(select),
+                        count,
+                    })
+                }
+            }
+        }
+
         let ret = match s {
             s if s.starts_with("top-") => {
                 let n: u32 = s["top-".len()..].parse()?;
@@ -66,6 +132,7 @@ impl FromStr for CrateSelect {
             }
 
             "small-random" => CrateSelect::Random(SMALL_RANDOM_COUNT),
+            s if s.starts_with("sample-") => CrateSelect::Sample(s.parse()?),
             s if s.starts_with("random-") => {
                 let n: u32 = s["random-".len()..].parse()?;
                 CrateSelect::Random(n)
@@ -80,6 +147,74 @@ impl FromStr for CrateSelect {
                 CrateSelect::List(list)
             }
 
+            s if s.starts_with("named:") => {
+                let name = &s["named:".len()..];
+                if name.is_empty() {
+                    bail!("missing list name in CrateSelect: {}", s);
+                }
+
+                CrateSelect::Named(name.to_string())
+            }
+
+            s if s.starts_with("rdeps:") => {
+                let mut parts = s["rdeps:".len()..].splitn(2, ':');
+                let name = parts.next().unwrap_or_default();
+                if name.is_empty() {
+                    bail!("missing crate name in CrateSelect: {}", s);
+                }
+                let depth = match parts.next() {
+                    Some(depth) => match depth.parse()? {
+                        0 => bail!("the depth of reverse dependencies must be at least 1"),
+                        depth => Some(depth),
+                    },
+                    None => None,
+                };
+
+                CrateSelect::ReverseDeps {
+                    name: name.to_string(),
+                    depth,
+                }
+            }
+
+            s if s.starts_with("from-ex:") => {
+                // Experiment names can contain colons, while the comparisons can't
+                let mut parts = s["from-ex:".len()..].rsplitn(2, ':');
+                let comparisons = parts.next().unwrap_or_default();
+                let name = parts.next().unwrap_or_default();
+                if name.is_empty() || comparisons.is_empty() {
+                    bail!(
+                        "expected `from-ex:{{experiment}}:{{comparisons}}`, found: {}",
+                        s
+                    );
+                }
+
+                CrateSelect::FromExperiment {
+                    name: name.to_string(),
+                    comparisons: comparisons
+                        .split(',')
+                        .map(|comparison| comparison.parse())
+                        .collect::<Fallible<_>>()?,
+                }
+            }
+
+            s if s.starts_with("versions:") => {
+                let mut parts = s["versions:".len()..].splitn(2, ':');
+                let name = parts.next().unwrap_or_default();
+                let req = parts.next().unwrap_or_default();
+                if name.is_empty() || req.is_empty() {
+                    bail!(
+                        "expected `versions:{{crate}}:{{requirement}}`, found: {}",
+                        s
+                    );
+                }
+                req.parse::<VersionReq>()?;
+
+                CrateSelect::Versions {
+                    name: name.to_string(),
+                    req: req.to_string(),
+                }
+            }
+
             "full" => CrateSelect::Full,
             "demo" => CrateSelect::Demo,
             "local" => CrateSelect::Local,
@@ -100,6 +235,28 @@ impl fmt::Display for CrateSelect {
             CrateSelect::Top(n) => write!(f, "top-{}", n),
             CrateSelect::Local => write!(f, "local"),
             CrateSelect::Random(n) => write!(f, "random-{}", n),
+            CrateSelect::Sample(sample) => write!(f, "{}", sample),
+            CrateSelect::Named(name) => write!(f, "named:{}", name),
+            CrateSelect::Versions { name, req } => write!(f, "versions:{}:{}", name, req),
+            CrateSelect::LastVersions { select, count } => write!(f, "{}:last-{}", select, count),
+            CrateSelect::ReverseDeps { name, depth: None } => write!(f, "rdeps:{}", name),
+            CrateSelect::ReverseDeps {
+                name,
+                depth: Some(depth),
+            } => write!(f, "rdeps:{}:{}", name, depth),
+            CrateSelect::FromExperiment { name, comparisons } => {
+                write!(f, "from-ex:{}:", name)?;
+
+                for (i, comparison) in comparisons.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+
+                    write!(f, "{}", comparison)?;
+                }
+
+                Ok(())
+            }
             CrateSelect::List(list) => {
                 let mut first = true;
                 write!(f, "list:")?;
@@ -169,6 +326,333 @@ impl FromStr for DeferredCrateSelect {
 
 impl_serde_from_parse!(CrateSelect, expecting = "A valid value of `CrateSelect`");
 
+/// A bucket of crates.io crates ranked by popularity, and the share of the sample drawn from it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Stratum {
+    /// How many crates follow the ones of the previous buckets in this bucket, or `None` for all
+    /// the remaining crates.
+    pub size: Option<u32>,
+    /// The percentage of the sample drawn from the bucket.
+    pub percent: u32,
+}
+
+/// A random sample of the crates.io crates, drawn from buckets of crates ranked by popularity so
+/// the most used crates aren't drowned out by abandoned ones.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Sample {
+    pub count: u32,
+    /// The seed of the random number generator, picked when the experiment is created if it's
+    /// missing. The same seed samples the same crates out of the same list of crates.
+    pub seed: Option<u64>,
+    pub strata: Vec<Stratum>,
+}
+
+impl Sample {
+    /// Half of the sample from the top 1,000 crates, 30% from the next 10,000 crates and the rest
+    /// from all the other crates.
+    fn default_strata() -> Vec<Stratum> {
+        vec![
+            Stratum {
+                size: Some(1_000),
+                percent: 50,
+            },
+            Stratum {
+                size: Some(10_000),
+                percent: 30,
+            },
+            Stratum {
+                size: None,
+                percent: 20,
+            },
+        ]
+    }
+}
+
+impl FromStr for Sample {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> failure::Fallible<Self> {
+        if !s.starts_with("sample-") {
+            bail!("invalid Sample (expected `sample-{{n}}`): {}", s);
+        }
+
+        let mut parts = s["sample-".len()..].splitn(3, ':');
+        let count = parts.next().unwrap_or_default().parse()?;
+        let seed = match parts.next() {
+            Some("") | None => None,
+            Some(seed) => Some(seed.parse()?),
+        };
+        let strata = match parts.next() {
+            Some(strata) => strata
+                .split(',')
+                .map(|stratum| {
+                    let mut parts = stratum.splitn(2, '=');
+                    match (parts.next(), parts.next()) {
+                        (Some(size), Some(percent)) => Ok(Stratum {
+                            size: if size == "rest" {
+                                None
+                            } else {
+                                Some(size.parse()?)
+                            },
+                            percent: percent.parse()?,
+                        }),
+                        _ => bail!("invalid stratum (expected `size=percent`): {}", stratum),
+                    }
+                })
+                .collect::<Fallible<Vec<_>>>()?,
+            None => Sample::default_strata(),
+        };
+
+        if strata.iter().map(|stratum| stratum.percent).sum::<u32>() != 100 {
+            bail!("the percentages of the strata must add up to 100: {}", s);
+        }
+        if strata
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|stratum| stratum.size.is_none())
+        {
+            bail!(
+                "only the last stratum can contain the rest of the crates: {}",
+                s
+            );
+        }
+        if strata.iter().any(|stratum| stratum.size == Some(0)) {
+            bail!("the strata can't be empty: {}", s);
+        }
+
+        Ok(Sample {
+            count,
+            seed,
+            strata,
+        })
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sample-{}", self.count)?;
+        if self.seed.is_none() && self.strata == Sample::default_strata() {
+            return Ok(());
+        }
+
+        write!(f, ":")?;
+        if let Some(seed) = self.seed {
+            write!(f, "{}", seed)?;
+        }
+        write!(f, ":")?;
+        for (i, stratum) in self.strata.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            match stratum.size {
+                Some(size) => write!(f, "{}={}", size, stratum.percent)?,
+                None => write!(f, "rest={}", stratum.percent)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl_serde_from_parse!(Sample, expecting = "A valid value of `Sample`");
+
+/// Which combinations of the Cargo features of each crate are built.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FeatureSet {
+    Default,
+    AllFeatures,
+    NoDefaultFeatures,
+    /// The smallest combinations of features, each built without the default ones, up to the
+    /// given number of combinations.
+    Powerset(u32),
+}
+
+impl FromStr for FeatureSet {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> failure::Fallible<Self> {
+        let ret = match s {
+            "default" => FeatureSet::Default,
+            "all-features" => FeatureSet::AllFeatures,
+            "no-default-features" => FeatureSet::NoDefaultFeatures,
+            s if s.starts_with("powerset-") => {
+                let n: u32 = s["powerset-".len()..].parse()?;
+                if n == 0 {
+                    bail!("the powerset must contain at least one combination");
+                }
+                FeatureSet::Powerset(n)
+            }
+            s => bail!("invalid FeatureSet: {}", s),
+        };
+
+        Ok(ret)
+    }
+}
+
+impl fmt::Display for FeatureSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeatureSet::Default => write!(f, "default"),
+            FeatureSet::AllFeatures => write!(f, "all-features"),
+            FeatureSet::NoDefaultFeatures => write!(f, "no-default-features"),
+            FeatureSet::Powerset(n) => write!(f, "powerset-{}", n),
+        }
+    }
+}
+
+impl_serde_from_parse!(FeatureSet, expecting = "A valid value of `FeatureSet`");
+
+const OPT_LEVELS: &[&str] = &["0", "1", "2", "3", "s", "z"];
+
+/// The Cargo profile the crates are built with, optionally overriding some of its settings with
+/// the compiler flags.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CargoProfile {
+    /// The name of the profile: `dev`, `release` or a custom one defined by the crates.
+    pub name: String,
+    pub opt_level: Option<String>,
+    pub debug_assertions: Option<bool>,
+}
+
+impl Default for CargoProfile {
+    fn default() -> Self {
+        CargoProfile {
+            name: "dev".into(),
+            opt_level: None,
+            debug_assertions: None,
+        }
+    }
+}
+
+impl CargoProfile {
+    /// The Cargo flags selecting the profile.
+    pub fn cargo_args(&self) -> Vec<&str> {
+        match self.name.as_str() {
+            "dev" => Vec::new(),
+            // Unlike `--profile`, `--release` is understood by toolchains of any age
+            "release" => vec!["--release"],
+            name => vec!["--profile", name],
+        }
+    }
+
+    /// The compiler flags overriding the settings of the profile.
+    pub fn rustflags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(ref opt_level) = self.opt_level {
+            flags.push(format!("-Copt-level={}", opt_level));
+        }
+        if let Some(debug_assertions) = self.debug_assertions {
+            let value = if debug_assertions { "on" } else { "off" };
+            flags.push(format!("-Cdebug-assertions={}", value));
+        }
+        flags
+    }
+}
+
+impl FromStr for CargoProfile {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> failure::Fallible<Self> {
+        let mut parts = s.split('+');
+        let mut profile = match parts.next() {
+            Some(name)
+                if !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                CargoProfile {
+                    name: name.to_string(),
+                    ..CargoProfile::default()
+                }
+            }
+            _ => bail!("invalid CargoProfile: {}", s),
+        };
+
+        for part in parts {
+            let mut flag = part.splitn(2, '=');
+            match (flag.next(), flag.next()) {
+                (Some("opt-level"), Some(level)) if OPT_LEVELS.contains(&level) => {
+                    profile.opt_level = Some(level.to_string());
+                }
+                (Some("debug-assertions"), Some("on")) => profile.debug_assertions = Some(true),
+                (Some("debug-assertions"), Some("off")) => profile.debug_assertions = Some(false),
+                _ => bail!("invalid override of the Cargo profile: {}", part),
+            }
+        }
+
+        Ok(profile)
+    }
+}
+
+impl fmt::Display for CargoProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(ref opt_level) = self.opt_level {
+            write!(f, "+opt-level={}", opt_level)?;
+        }
+        if let Some(debug_assertions) = self.debug_assertions {
+            let value = if debug_assertions { "on" } else { "off" };
+            write!(f, "+debug-assertions={}", value)?;
+        }
+        Ok(())
+    }
+}
+
+impl_serde_from_parse!(CargoProfile, expecting = "A valid value of `CargoProfile`");
+
+string_enum!(pub enum LockfilePolicy {
+    AsPrepared => "as-prepared",
+    Update => "update",
+    MinimalVersions => "minimal-versions",
+});
+
+/// A named regex searched in the source code of the crates by `query` experiments.
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub name: String,
+    pub regex: Regex,
+}
+
+impl PartialEq for Query {
+    fn eq(&self, other: &Query) -> bool {
+        self.name == other.name && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Eq for Query {}
+
+impl FromStr for Query {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> failure::Fallible<Self> {
+        let mut parts = s.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(regex))
+                if !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+            {
+                Ok(Query {
+                    name: name.to_string(),
+                    regex: Regex::new(regex)?,
+                })
+            }
+            _ => bail!("invalid Query (expected `name=regex`): {}", s),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.regex.as_str())
+    }
+}
+
+impl_serde_from_parse!(Query, expecting = "A valid value of `Query`");
+
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Assignee {
@@ -247,7 +731,9 @@ pub struct GitHubIssue {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Experiment {
     pub name: String,
-    pub toolchains: [Toolchain; 2],
+    /// The toolchains of the experiment. The first one is the baseline every other toolchain is
+    /// compared against.
+    pub toolchains: Vec<Toolchain>,
     pub mode: Mode,
     pub cap_lints: CapLints,
     pub priority: i32,
@@ -260,9 +746,54 @@ pub struct Experiment {
     pub report_url: Option<String>,
     pub ignore_blacklist: bool,
     pub requirement: Option<String>,
+    /// How many times crates that regressed or were fixed are run again before generating the
+    /// report, to detect spurious results.
+    pub verify_runs: u32,
+    /// The experiment the results of the first toolchain are read from, instead of building the
+    /// crates with it again.
+    pub baseline: Option<String>,
+    /// Which combinations of Cargo features the crates are built with.
+    pub features: FeatureSet,
+    /// The Cargo profile the crates are built with.
+    pub profile: CargoProfile,
+    /// How the lockfile generated while preparing the crates is updated before building them.
+    pub lockfile: LockfilePolicy,
+    /// The regexes searched in the source code of the crates, by `query` experiments only.
+    pub queries: Vec<Query>,
+    /// The sample the crates were selected with, to select the same crates again.
+    pub sample: Option<Sample>,
 }
 
 impl Experiment {
+    #[cfg(test)]
+    pub fn dummy(name: &str) -> Self {
+        use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+
+        Experiment {
+            name: name.to_string(),
+            toolchains: vec![MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
+            mode: Mode::BuildAndTest,
+            cap_lints: CapLints::Forbid,
+            priority: 0,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            github_issue: None,
+            status: Status::GeneratingReport,
+            assigned_to: None,
+            report_url: None,
+            ignore_blacklist: false,
+            requirement: None,
+            verify_runs: 0,
+            baseline: None,
+            features: FeatureSet::Default,
+            profile: CargoProfile::default(),
+            lockfile: LockfilePolicy::AsPrepared,
+            queries: Vec::new(),
+            sample: None,
+        }
+    }
+
     pub fn exists(db: &Database, name: &str) -> Fallible<bool> {
         Ok(db.exists("SELECT rowid FROM experiments WHERE name = ?1;", &[&name])?)
     }
@@ -275,7 +806,7 @@ impl Experiment {
         )?;
         records
             .into_iter()
-            .map(|record| record.into_experiment())
+            .map(|record| record.into_experiment(db))
             .collect::<Fallible<_>>()
     }
 
@@ -284,14 +815,18 @@ impl Experiment {
             "SELECT * FROM experiments \
              INNER JOIN experiment_crates ON experiment_crates.experiment \
              = experiments.name WHERE experiment_crates.assigned_to = ?1 \
-             AND experiment_crates.status = ?2 AND experiments.status = ?2 \
+             AND experiment_crates.status = ?2 AND experiments.status IN (?2, ?3) \
              AND experiment_crates.skipped = 0 LIMIT 1",
-            &[&assignee.to_string(), Status::Running.to_str()],
+            &[
+                &assignee.to_string(),
+                Status::Running.to_str(),
+                Status::Verifying.to_str(),
+            ],
             |r| ExperimentDBRecord::from_row(r),
         )?;
 
         if let Some(record) = record {
-            Ok(Some(record.into_experiment()?))
+            Ok(Some(record.into_experiment(db)?))
         } else {
             Ok(None)
         }
@@ -307,7 +842,7 @@ impl Experiment {
         )?;
 
         if let Some(record) = record {
-            Ok(Some(record.into_experiment()?))
+            Ok(Some(record.into_experiment(db)?))
         } else {
             Ok(None)
         }
@@ -352,7 +887,7 @@ impl Experiment {
         ex: Option<Experiment>,
     ) -> Fallible<Option<(bool, Experiment)>> {
         if let Some(mut experiment) = ex {
-            let new_ex = experiment.status != Status::Running;
+            let new_ex = experiment.status == Status::Queued;
             if new_ex {
                 experiment.set_status(&db, Status::Running)?;
                 // If this experiment was not assigned to a specific agent make it distributed
@@ -390,7 +925,7 @@ impl Experiment {
                         SELECT *
                         FROM   experiments ex
                         WHERE  ( ex.status = "queued" 
-                                OR ( status = "running"
+                                OR ( status IN ("running", "verifying")
                                             AND ( SELECT COUNT (*)
                                                   FROM  experiment_crates ex_crates
                                                   WHERE ex_crates.experiment = ex.name
@@ -419,7 +954,7 @@ impl Experiment {
                         SELECT     *
                         FROM       experiments ex
                         WHERE      ( ex.status = "queued" 
-                                        OR ( status = "running"
+                                        OR ( status IN ("running", "verifying")
                                             AND ( SELECT COUNT (*)
                                                   FROM  experiment_crates ex_crates
                                                   WHERE ex_crates.experiment = ex.name
@@ -441,7 +976,7 @@ impl Experiment {
                 SELECT *
                 FROM   experiments ex
                 WHERE  ( ex.status = "queued"
-                        OR ( status = "running"
+                        OR ( status IN ("running", "verifying")
                                             AND ( SELECT COUNT (*)
                                                   FROM  experiment_crates ex_crates
                                                   WHERE ex_crates.experiment = ex.name
@@ -464,7 +999,7 @@ impl Experiment {
         if let Some(record) = db.get_row(query, params.as_slice(), |r| {
             ExperimentDBRecord::from_row(r)
         })? {
-            Ok(Some(record.into_experiment()?))
+            Ok(Some(record.into_experiment(db)?))
         } else {
             Ok(None)
         }
@@ -478,7 +1013,7 @@ impl Experiment {
         )?;
 
         if let Some(record) = record {
-            Ok(Some(record.into_experiment()?))
+            Ok(Some(record.into_experiment(db)?))
         } else {
             Ok(None)
         }
@@ -521,9 +1056,11 @@ impl Experiment {
                 )?;
                 self.started_at = Some(now);
             }
-            // Check if the old status was "running" and there is no completed date
-            (Status::Running, new_status)
-                if self.completed_at.is_none() && new_status != Status::Failed =>
+            // Check if the old status was "running" or "verifying" and there is no completed date
+            (Status::Running, new_status) | (Status::Verifying, new_status)
+                if self.completed_at.is_none()
+                    && new_status != Status::Failed
+                    && new_status != Status::Verifying =>
             {
                 db.execute(
                     "UPDATE experiments SET completed_at = ?1 WHERE name = ?2;",
@@ -577,7 +1114,7 @@ impl Experiment {
     pub fn raw_progress(&self, db: &Database) -> Fallible<(u32, u32)> {
         let results_len: u32 = db
             .get_row(
-                "SELECT COUNT(*) AS count FROM results WHERE experiment = ?1;",
+                "SELECT COUNT(*) AS count FROM results WHERE experiment = ?1 AND retry = 0;",
                 &[&self.name.as_str()],
                 |r| r.get("count"),
             )?
@@ -592,13 +1129,76 @@ impl Experiment {
             )?
             .unwrap();
 
-        Ok((results_len, crates_len * 2))
+        Ok((
+            results_len,
+            crates_len * self.built_toolchains().len() as u32,
+        ))
+    }
+
+    /// The toolchains the crates are built with, which don't include the first one if its
+    /// results come from a baseline experiment.
+    pub fn built_toolchains(&self) -> &[Toolchain] {
+        if self.baseline.is_some() {
+            &self.toolchains[1..]
+        } else {
+            &self.toolchains
+        }
+    }
+
+    /// Whether the toolchains of the experiment build the crates for more than one target.
+    pub fn tests_multiple_targets(&self) -> bool {
+        self.toolchains
+            .iter()
+            .map(|tc| tc.target.as_ref())
+            .collect::<HashSet<_>>()
+            .len()
+            > 1
+    }
+
+    /// The name of the experiment storing the results of this experiment for `toolchain`.
+    pub fn results_experiment(&self, toolchain: &Toolchain) -> &str {
+        match self.baseline {
+            Some(ref baseline) if *toolchain == self.toolchains[0] => baseline,
+            _ => &self.name,
+        }
+    }
+
+    /// Replace the toolchains of the experiment `name` with the provided ones.
+    ///
+    /// The legacy `toolchain_start` and `toolchain_end` columns are kept in sync with the baseline
+    /// and the first compared toolchain.
+    pub(crate) fn store_toolchains<Q: QueryUtils>(
+        db: &Q,
+        name: &str,
+        toolchains: &[Toolchain],
+    ) -> Fallible<()> {
+        db.execute(
+            "UPDATE experiments SET toolchain_start = ?1, toolchain_end = ?2 WHERE name = ?3;",
+            &[
+                &toolchains[0].to_string(),
+                &toolchains[1].to_string(),
+                &name,
+            ],
+        )?;
+        db.execute(
+            "DELETE FROM experiment_toolchains WHERE experiment = ?1;",
+            &[&name],
+        )?;
+        for (position, tc) in toolchains.iter().enumerate() {
+            db.execute(
+                "INSERT INTO experiment_toolchains (experiment, position, toolchain) \
+                 VALUES (?1, ?2, ?3);",
+                &[&name, &(position as i64), &tc.to_string()],
+            )?;
+        }
+
+        Ok(())
     }
 
     pub fn get_result_counts(&self, db: &Database) -> Fallible<Vec<(TestResult, u32)>> {
         let results: Vec<Fallible<(TestResult, u32)>> = db.query(
             "SELECT result, COUNT(*) FROM results \
-             WHERE experiment = ?1 GROUP BY result;",
+             WHERE experiment = ?1 AND retry = 0 GROUP BY result;",
             &[&self.name.as_str()],
             |r| Ok((TestResult::from_str(&r.get::<_, String>(0))?, r.get(1))),
         )?;
@@ -616,6 +1216,56 @@ impl Experiment {
         }
     }
 
+    /// Queue again the crates whose results changed, so they can be re-run while the experiment
+    /// is verifying. Returns the number of queued crates.
+    pub fn requeue_for_verification(&self, db: &Database, config: &Config) -> Fallible<usize> {
+        let results_counts: Vec<(String, u32)> = db.query(
+            "SELECT crate, COUNT(*) AS count FROM results WHERE experiment = ?1 GROUP BY crate;",
+            &[&self.name],
+            |r| (r.get("crate"), r.get("count")),
+        )?;
+        let results_counts = results_counts.into_iter().collect::<HashMap<_, _>>();
+        let wanted_results = (self.verify_runs + 1) * self.built_toolchains().len() as u32;
+
+        let results = DatabaseDB::new(db);
+        let mut queued = 0;
+        for krate in self.get_crates(db)? {
+            // The crates are compared as in the report, so the changes found in the data recorded
+            // by the mode are verified as well
+            let needs_verification =
+                crate::report::compare_results(&results, config, self, &krate)?
+                    .into_iter()
+                    .any(Comparison::needs_verification);
+
+            // Crates that were already verified enough times are not queued again
+            let id = krate.id();
+            if needs_verification && results_counts.get(&id).cloned().unwrap_or(0) < wanted_results
+            {
+                db.execute(
+                    "UPDATE experiment_crates SET status = ?1, assigned_to = NULL \
+                     WHERE experiment = ?2 AND crate = ?3;",
+                    &[&Status::Queued.to_string(), &self.name, &id],
+                )?;
+                queued += 1;
+            }
+        }
+
+        Ok(queued)
+    }
+
+    /// Check whether there are crates still waiting to be run or being run.
+    pub fn has_pending_crates(&self, db: &Database) -> Fallible<bool> {
+        Ok(db.exists(
+            "SELECT rowid FROM experiment_crates WHERE experiment = ?1 \
+             AND status IN (?2, ?3) AND skipped = 0;",
+            &[
+                &self.name,
+                &Status::Queued.to_string(),
+                &Status::Running.to_string(),
+            ],
+        )?)
+    }
+
     pub fn get_crates(&self, db: &Database) -> Fallible<Vec<Crate>> {
         db.query(
             "SELECT crate FROM experiment_crates WHERE experiment = ?1;",
@@ -714,8 +1364,6 @@ struct ExperimentDBRecord {
     name: String,
     mode: String,
     cap_lints: String,
-    toolchain_start: String,
-    toolchain_end: String,
     priority: i32,
     created_at: DateTime<Utc>,
     started_at: Option<DateTime<Utc>>,
@@ -728,6 +1376,13 @@ struct ExperimentDBRecord {
     report_url: Option<String>,
     ignore_blacklist: bool,
     requirement: Option<String>,
+    verify_runs: u32,
+    baseline: Option<String>,
+    features: String,
+    profile: String,
+    lockfile: String,
+    queries: String,
+    sample: Option<String>,
 }
 
 impl ExperimentDBRecord {
@@ -736,8 +1391,6 @@ impl ExperimentDBRecord {
             name: row.get("name"),
             mode: row.get("mode"),
             cap_lints: row.get("cap_lints"),
-            toolchain_start: row.get("toolchain_start"),
-            toolchain_end: row.get("toolchain_end"),
             priority: row.get("priority"),
             created_at: row.get("created_at"),
             started_at: row.get("started_at"),
@@ -750,13 +1403,31 @@ impl ExperimentDBRecord {
             report_url: row.get("report_url"),
             ignore_blacklist: row.get("ignore_blacklist"),
             requirement: row.get("requirement"),
+            verify_runs: row.get("verify_runs"),
+            baseline: row.get("baseline"),
+            features: row.get("features"),
+            profile: row.get("profile"),
+            lockfile: row.get("lockfile"),
+            queries: row.get("queries"),
+            sample: row.get("sample"),
         }
     }
 
-    fn into_experiment(self) -> Fallible<Experiment> {
+    fn into_experiment(self, db: &Database) -> Fallible<Experiment> {
+        let toolchains = db
+            .query(
+                "SELECT toolchain FROM experiment_toolchains WHERE experiment = ?1 \
+                 ORDER BY position;",
+                &[&self.name],
+                |r| r.get::<_, String>("toolchain"),
+            )?
+            .into_iter()
+            .map(|tc| Ok(tc.parse()?))
+            .collect::<Fallible<Vec<Toolchain>>>()?;
+
         Ok(Experiment {
             name: self.name,
-            toolchains: [self.toolchain_start.parse()?, self.toolchain_end.parse()?],
+            toolchains,
             cap_lints: self.cap_lints.parse()?,
             mode: self.mode.parse()?,
             priority: self.priority,
@@ -785,6 +1456,13 @@ impl ExperimentDBRecord {
             report_url: self.report_url,
             ignore_blacklist: self.ignore_blacklist,
             requirement: self.requirement,
+            verify_runs: self.verify_runs,
+            baseline: self.baseline,
+            features: self.features.parse()?,
+            profile: self.profile.parse()?,
+            lockfile: self.lockfile.parse()?,
+            queries: serde_json::from_str(&self.queries)?,
+            sample: self.sample.map(|sample| sample.parse()).transpose()?,
         })
     }
 }
@@ -792,17 +1470,65 @@ impl ExperimentDBRecord {
 #[cfg(test)]
 mod tests {
     use super::{
-        Assignee, AssigneeParseError, CrateSelect, DeferredCrateSelect, Experiment, Status,
+        Assignee, AssigneeParseError, CargoProfile, CrateSelect, DeferredCrateSelect, Experiment,
+        FeatureSet, Query, Sample, Status, Stratum,
     };
     use crate::actions::{Action, ActionsCtx, CreateExperiment};
     use crate::agent::Capabilities;
     use crate::config::Config;
     use crate::db::Database;
+    use crate::report::Comparison;
     use crate::server::agents::Agents;
     use crate::server::tokens::Tokens;
     use std::collections::HashSet;
     use std::str::FromStr;
 
+    #[test]
+    fn test_sample_parsing() {
+        let stratum = |size, percent| Stratum { size, percent };
+
+        let sample = Sample::from_str("sample-500").unwrap();
+        assert_eq!(sample.count, 500);
+        assert_eq!(sample.seed, None);
+        assert_eq!(
+            sample.strata,
+            vec![
+                stratum(Some(1000), 50),
+                stratum(Some(10000), 30),
+                stratum(None, 20)
+            ]
+        );
+        assert_eq!(sample.to_string(), "sample-500");
+
+        let sample = Sample::from_str("sample-500:42").unwrap();
+        assert_eq!(sample.seed, Some(42));
+        assert_eq!(sample.to_string(), "sample-500:42:1000=50,10000=30,rest=20");
+
+        let sample = Sample::from_str("sample-20::100=75,rest=25").unwrap();
+        assert_eq!(sample.seed, None);
+        assert_eq!(
+            sample.strata,
+            vec![stratum(Some(100), 75), stratum(None, 25)]
+        );
+        assert_eq!(sample.to_string(), "sample-20::100=75,rest=25");
+
+        assert_eq!(
+            CrateSelect::from_str("sample-500:42").unwrap(),
+            CrateSelect::Sample(Sample::from_str("sample-500:42").unwrap())
+        );
+
+        for invalid in &[
+            "sample-",
+            "sample-500:seed",
+            "sample-500:42:100=50,rest=40",
+            "sample-500:42:rest=50,100=50",
+            "sample-500:42:0=50,rest=50",
+            "sample-500:42:100",
+        ] {
+            assert!(Sample::from_str(invalid).is_err(), "{}", invalid);
+        }
+    }
+
     #[test]
     fn test_crate_select_parsing() {
         let demo_crates: HashSet<_> = ["brson/hello-rs", "lazy_static"]
@@ -819,6 +1545,56 @@ mod tests {
                 "list:brson/hello-rs,lazy_static",
                 CrateSelect::List(demo_crates.clone()),
             ),
+            ("named:embedded", CrateSelect::Named("embedded".into())),
+            (
+                "rdeps:serde",
+                CrateSelect::ReverseDeps {
+                    name: "serde".into(),
+                    depth: None,
+                },
+            ),
+            (
+                "rdeps:syn:2",
+                CrateSelect::ReverseDeps {
+                    name: "syn".into(),
+                    depth: Some(2),
+                },
+            ),
+            (
+                "from-ex:pr-12345:regressed,spurious-regressed",
+                CrateSelect::FromExperiment {
+                    name: "pr-12345".into(),
+                    comparisons: vec![Comparison::Regressed, Comparison::SpuriousRegressed],
+                },
+            ),
+            (
+                "from-ex:pr-12345:1:fixed",
+                CrateSelect::FromExperiment {
+                    name: "pr-12345:1".into(),
+                    comparisons: vec![Comparison::Fixed],
+                },
+            ),
+            (
+                "versions:regex:>=1.0,<1.4",
+                CrateSelect::Versions {
+                    name: "regex".into(),
+                    req: ">=1.0,<1.4".into(),
+                },
+            ),
+            (
+                "top-100:last-3",
+                CrateSelect::LastVersions {
+                    select: Box::new(CrateSelect::Top(100)),
+                    count: 3,
+                },
+            ),
+            (
+                "list:brson/hello-rs,lazy_static:last-2",
+                CrateSelect::LastVersions {
+                    select: Box::new(CrateSelect::List(demo_crates.clone())),
+                    count: 2,
+                },
+            ),
         ];
 
         for (s, output) in suite.into_iter() {
@@ -829,6 +1605,34 @@ mod tests {
             );
         }
 
+        for s in &[
+            "named:embedded",
+            "rdeps:serde",
+            "rdeps:syn:2",
+            "from-ex:pr-12345:regressed,spurious-regressed",
+            "versions:regex:>=1.0,<1.4",
+            "top-100:last-3",
+        ] {
+            assert_eq!(CrateSelect::from_str(s).unwrap().to_string(), *s);
+        }
+        assert!(CrateSelect::from_str("named:").is_err());
+        assert!(CrateSelect::from_str("versions:regex").is_err());
+        assert!(CrateSelect::from_str("versions:regex:latest").is_err());
+        assert!(CrateSelect::from_str("top-100:last-0").is_err());
+        assert!(CrateSelect::from_str("top-100:last-3:last-2").is_err());
+        assert!(CrateSelect::from_str("versions:regex:1.0:last-2").is_err());
+
+        // The requirement is normalized when it's displayed
+        let select = CrateSelect::from_str("versions:regex:>=1.0,<1.4").unwrap();
+        assert_eq!(CrateSelect::from_str(&select.to_string()).unwrap(), select);
+        assert!(CrateSelect::from_str("rdeps:").is_err());
+        assert!(CrateSelect::from_str("rdeps:syn:0").is_err());
+        assert!(CrateSelect::from_str("rdeps:syn:all").is_err());
+        assert!(CrateSelect::from_str("from-ex:pr-12345").is_err());
+        assert!(CrateSelect::from_str("from-ex::regressed").is_err());
+        assert!(CrateSelect::from_str("from-ex:pr-12345:").is_err());
+        assert!(CrateSelect::from_str("from-ex:pr-12345:regresed").is_err());
+
         assert_eq!(
             DeferredCrateSelect::from_str("http://git.io/Jes7o").unwrap(),
             DeferredCrateSelect::Indirect("http://git.io/Jes7o".parse().unwrap()),
@@ -850,6 +1654,111 @@ mod tests {
         assert_eq!(list, CrateSelect::List(demo_crates));
     }
 
+    #[test]
+    fn test_feature_set_parsing() {
+        let suite = vec![
+            ("default", FeatureSet::Default),
+            ("all-features", FeatureSet::AllFeatures),
+            ("no-default-features", FeatureSet::NoDefaultFeatures),
+            ("powerset-16", FeatureSet::Powerset(16)),
+        ];
+
+        for (s, output) in suite.into_iter() {
+            assert_eq!(FeatureSet::from_str(s).unwrap(), output);
+            assert_eq!(output.to_string(), s);
+        }
+
+        assert!(FeatureSet::from_str("powerset-0").is_err());
+        assert!(FeatureSet::from_str("powerset").is_err());
+        assert!(FeatureSet::from_str("features").is_err());
+    }
+
+    #[test]
+    fn test_cargo_profile_parsing() {
+        let suite = vec![
+            ("dev", CargoProfile::default()),
+            (
+                "release",
+                CargoProfile {
+                    name: "release".into(),
+                    ..CargoProfile::default()
+                },
+            ),
+            (
+                "ci_fast-2",
+                CargoProfile {
+                    name: "ci_fast-2".into(),
+                    ..CargoProfile::default()
+                },
+            ),
+            (
+                "dev+opt-level=3",
+                CargoProfile {
+                    opt_level: Some("3".into()),
+                    ..CargoProfile::default()
+                },
+            ),
+            (
+                "release+opt-level=s+debug-assertions=on",
+                CargoProfile {
+                    name: "release".into(),
+                    opt_level: Some("s".into()),
+                    debug_assertions: Some(true),
+                },
+            ),
+        ];
+
+        for (s, output) in suite.into_iter() {
+            assert_eq!(CargoProfile::from_str(s).unwrap(), output);
+            assert_eq!(output.to_string(), s);
+        }
+
+        assert_eq!(
+            CargoProfile::from_str("release+debug-assertions=off+opt-level=1")
+                .unwrap()
+                .rustflags(),
+            vec!["-Copt-level=1", "-Cdebug-assertions=off"]
+        );
+
+        assert_eq!(
+            CargoProfile::from_str("dev").unwrap().cargo_args(),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            CargoProfile::from_str("release").unwrap().cargo_args(),
+            vec!["--release"]
+        );
+        assert_eq!(
+            CargoProfile::from_str("bench").unwrap().cargo_args(),
+            vec!["--profile", "bench"]
+        );
+
+        assert!(CargoProfile::from_str("").is_err());
+        assert!(CargoProfile::from_str("+opt-level=3").is_err());
+        assert!(CargoProfile::from_str("ci fast").is_err());
+        assert!(CargoProfile::from_str("dev+opt-level=4").is_err());
+        assert!(CargoProfile::from_str("dev+debug-assertions").is_err());
+        assert!(CargoProfile::from_str("dev+lto=fat").is_err());
+    }
+
+    #[test]
+    fn test_query_parsing() {
+        let query = Query::from_str("phantom-data=PhantomData<[^>]*>").unwrap();
+        assert_eq!(query.name, "phantom-data");
+        assert_eq!(query.regex.as_str(), "PhantomData<[^>]*>");
+        assert_eq!(query.to_string(), "phantom-data=PhantomData<[^>]*>");
+
+        // The regex can contain `=` as well
+        let query = Query::from_str("eq=a == b").unwrap();
+        assert_eq!(query.name, "eq");
+        assert_eq!(query.regex.as_str(), "a == b");
+
+        assert!(Query::from_str("unsafe").is_err());
+        assert!(Query::from_str("=unsafe").is_err());
+        assert!(Query::from_str("has space=unsafe").is_err());
+        assert!(Query::from_str("unclosed=(unsafe").is_err());
+    }
+
     #[test]
     fn test_assignee_parsing() {
         assert_eq!(
@@ -2,16 +2,50 @@ use super::{Comparison, CrateResult, RawTestResults};
 use crate::crates::Crate;
 use crate::results::{
     FailureReason,
-    TestResult::{self, BuildFail},
+    TestResult::{self, BuildFail, TestFail},
 };
-use indexmap::IndexMap;
-use std::collections::BTreeSet;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
+#[derive(Clone, Copy)]
 pub enum ToolchainSelect {
     Start,
     End,
 }
 
+impl ToolchainSelect {
+    /// Index of the selected run of a crate: the baseline for `Start`, and the run compared
+    /// against it for `End`.
+    pub(super) fn run_index(self, krate: &CrateResult) -> usize {
+        match self {
+            ToolchainSelect::Start => 0,
+            ToolchainSelect::End => krate.deciding_run(),
+        }
+    }
+
+    /// Index of the run the selected one is compared with.
+    fn other_run_index(self, krate: &CrateResult) -> usize {
+        match self {
+            ToolchainSelect::Start => krate.deciding_run(),
+            ToolchainSelect::End => 0,
+        }
+    }
+}
+
+/// Tests failing in the selected run of a crate but not in the one it's compared with.
+fn changed_failed_tests(toolchain: ToolchainSelect, krate: &CrateResult) -> BTreeSet<String> {
+    let failed_tests = |run: usize| match krate.runs[run].as_ref().map(|r| &r.res) {
+        Some(TestFail(FailureReason::FailedTests(tests))) => tests.clone(),
+        _ => BTreeSet::new(),
+    };
+
+    let other = failed_tests(toolchain.other_run_index(krate));
+    failed_tests(toolchain.run_index(krate))
+        .into_iter()
+        .filter(|test| !other.contains(test))
+        .collect()
+}
+
 pub enum ReportConfig {
     Simple,
     Complete(ToolchainSelect),
@@ -24,6 +58,8 @@ pub enum ReportCrates {
     Complete {
         tree: IndexMap<Crate, Vec<CrateResult>>,
         results: IndexMap<TestResult, Vec<CrateResult>>,
+        ices: IndexMap<String, Vec<CrateResult>>,
+        lints: IndexMap<String, Vec<CrateResult>>,
     },
 }
 
@@ -33,29 +69,136 @@ pub struct TestResults {
     pub info: IndexMap<Comparison, u32>,
 }
 
-fn analyze_detailed(toolchain: usize, crates: Vec<CrateResult>) -> ReportCrates {
+impl ReportCrates {
+    /// Every crate in the category, listed once even if it's listed under several causes.
+    pub fn crates(&self) -> Vec<&CrateResult> {
+        match self {
+            ReportCrates::Plain(crates) => crates.iter().collect(),
+            ReportCrates::Complete {
+                tree,
+                results,
+                ices,
+                lints,
+            } => results
+                .values()
+                .chain(ices.values())
+                .chain(lints.values())
+                .chain(tree.values())
+                .flatten()
+                .collect::<IndexSet<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// The crates in the category grouped by name, listing the versions of each crate together.
+    pub fn group_versions(&self) -> IndexMap<String, Vec<CrateResult>> {
+        let mut versions = IndexMap::new();
+        for result in self.crates() {
+            let name = match result.krate {
+                Crate::Registry(ref details) => details.name.clone(),
+                _ => result.name.clone(),
+            };
+            versions
+                .entry(name)
+                .or_insert_with(Vec::new)
+                .push(result.clone());
+        }
+        versions
+    }
+}
+
+impl TestResults {
+    /// Whether several versions of the same crates.io crate are part of the results.
+    pub fn has_multiple_versions(&self) -> bool {
+        let mut versions: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for result in self.categories.values().flat_map(ReportCrates::crates) {
+            if let Crate::Registry(ref details) = result.krate {
+                let versions = versions
+                    .entry(details.name.as_str())
+                    .or_insert_with(HashSet::new);
+                versions.insert(details.version.as_str());
+                if versions.len() > 1 {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+fn analyze_detailed(toolchain: ToolchainSelect, crates: Vec<CrateResult>) -> ReportCrates {
     let mut tree = IndexMap::new();
     let mut results = IndexMap::new();
+    let mut ices = IndexMap::new();
+    let mut lints = IndexMap::new();
 
     let mut root = Vec::new();
     for krate in crates {
+        let run = toolchain.run_index(&krate);
+        let future_incompat = &krate.runs[run].as_ref().unwrap().future_incompat;
         if let BuildFail(FailureReason::DependsOn(ref deps)) =
-            (&krate.runs[toolchain]).as_ref().unwrap().res
+            (&krate.runs[run]).as_ref().unwrap().res
         {
             for dep in deps {
                 tree.entry(dep.clone())
                     .or_insert_with(Vec::new)
                     .push(krate.clone())
             }
+        } else if !future_incompat.is_empty() {
+            // crates are affected by future-incompat lints of their dependencies the same way
+            // they're affected by their build failures
+            let deps = future_incompat
+                .iter()
+                .filter_map(|lint| lint.dependency.as_ref())
+                .collect::<BTreeSet<_>>();
+            for dep in deps {
+                tree.entry(dep.clone())
+                    .or_insert_with(Vec::new)
+                    .push(krate.clone())
+            }
+            if future_incompat.iter().any(|lint| lint.dependency.is_none()) {
+                root.push(krate);
+            }
         } else {
             root.push(krate);
         }
     }
 
+    // list first the crates whose fix would unblock the most dependents
+    tree.sort_by(|_, a, _, b| b.len().cmp(&a.len()));
+
     for krate in root {
+        let run = toolchain.run_index(&krate);
+        let changed_tests = changed_failed_tests(toolchain, &krate);
+        let ice = krate.runs[run].as_ref().and_then(|run| match run.res {
+            BuildFail(FailureReason::ICE) => run.ice.as_ref().map(|ice| ice.signature()),
+            _ => None,
+        });
         // record results only for root crates
-        if let BuildFail(FailureReason::CompilerError(codes)) =
-            krate.runs[toolchain].clone().unwrap().res
+        let direct_lints = krate.runs[run]
+            .as_ref()
+            .map(|run| {
+                run.future_incompat
+                    .iter()
+                    .filter(|lint| lint.dependency.is_none())
+                    .map(|lint| lint.lint.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if let Some(signature) = ice {
+            // group ICEs by signature, so the same compiler bug is only listed once
+            ices.entry(signature).or_insert_with(Vec::new).push(krate)
+        } else if !direct_lints.is_empty() {
+            for lint in direct_lints {
+                lints
+                    .entry(lint)
+                    .or_insert_with(Vec::new)
+                    .push(krate.clone())
+            }
+        } else if let BuildFail(FailureReason::CompilerError(codes)) =
+            krate.runs[run].clone().unwrap().res
         {
             for code in codes {
                 results
@@ -63,15 +206,37 @@ fn analyze_detailed(toolchain: usize, crates: Vec<CrateResult>) -> ReportCrates
                     .or_insert_with(Vec::new)
                     .push(krate.clone())
             }
+        } else if let BuildFail(FailureReason::MissingSystemLibs(libs)) =
+            krate.runs[run].clone().unwrap().res
+        {
+            for lib in libs {
+                results
+                    .entry(BuildFail(FailureReason::MissingSystemLibs(btreeset![lib])))
+                    .or_insert_with(Vec::new)
+                    .push(krate.clone())
+            }
+        } else if !changed_tests.is_empty() {
+            // only list the tests whose outcome changed
+            for test in changed_tests {
+                results
+                    .entry(TestFail(FailureReason::FailedTests(btreeset![test])))
+                    .or_insert_with(Vec::new)
+                    .push(krate.clone())
+            }
         } else {
             results
-                .entry(krate.runs[toolchain].as_ref().unwrap().res.clone())
+                .entry(krate.runs[run].as_ref().unwrap().res.clone())
                 .or_insert_with(Vec::new)
                 .push(krate)
         }
     }
 
-    ReportCrates::Complete { tree, results }
+    ReportCrates::Complete {
+        tree,
+        results,
+        ices,
+        lints,
+    }
 }
 
 pub fn analyze_report(test: RawTestResults) -> TestResults {
@@ -91,9 +256,7 @@ pub fn analyze_report(test: RawTestResults) -> TestResults {
     let mut categories = IndexMap::new();
     for (cat, crates) in comparison {
         if let ReportConfig::Complete(toolchain) = cat.report_config() {
-            // variants in an enum are numbered following an
-            // increasing sequence starting from 0
-            categories.insert(cat, analyze_detailed(toolchain as usize, crates));
+            categories.insert(cat, analyze_detailed(toolchain, crates));
         } else {
             categories.insert(cat, ReportCrates::Plain(crates));
         }
@@ -107,9 +270,9 @@ mod tests {
     use super::*;
     use crate::config::Config;
     use crate::crates::{Crate, RegistryCrate};
-    use crate::experiments::{CapLints, Experiment, Mode, Status};
+    use crate::experiments::{Experiment, Mode};
     use crate::report::{generate_report, Comparison};
-    use crate::results::{DummyDB, FailureReason::*};
+    use crate::results::{DummyDB, FailureReason::*, FutureIncompat, Ice};
     use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
     use failure::Fallible;
 
@@ -151,33 +314,31 @@ mod tests {
 
         let config = Config::default();
         let mut db = DummyDB::default();
-        let ex = Experiment {
-            name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
-            mode: Mode::BuildAndTest,
-            cap_lints: CapLints::Forbid,
-            priority: 0,
-            created_at: ::chrono::Utc::now(),
-            started_at: None,
-            completed_at: None,
-            github_issue: None,
-            status: Status::GeneratingReport,
-            assigned_to: None,
-            report_url: None,
-            ignore_blacklist: false,
-            requirement: None,
-        };
+        let ex = Experiment::dummy("foo");
 
         let crates = record_crates! {db, ex,
             "test-pass" => (TestResult::TestPass, TestResult::TestPass),
             "ce-1" => (TestResult::TestPass, TestResult::BuildFail(CompilerError(btreeset!["001".parse()?, "002".parse()?]))),
             "ce-2" => (TestResult::TestPass, TestResult::BuildFail(CompilerError(btreeset!["002".parse()?]))),
             "unknown" => (TestResult::TestPass, TestResult::BuildFail(Unknown)),
+            "libs" => (TestResult::TestPass, TestResult::BuildFail(MissingSystemLibs(btreeset!["ssl".into(), "z".into()]))),
             "dep-1" => (TestResult::TestPass, TestResult::BuildFail(DependsOn(btreeset![reg!("ce-1"), reg!("unknown")]))),
             "dep-2" => (TestResult::TestPass, TestResult::BuildFail(DependsOn(btreeset![reg!("ce-1"), reg!("ce-2")]))),
             "fix-1" => (TestResult::BuildFail(DependsOn(btreeset![reg!("ce-1"), reg!("ce-2")])), TestResult::TestPass),
-            "fix-2" => (TestResult::BuildFail(Unknown), TestResult::TestPass)
+            "fix-2" => (TestResult::BuildFail(Unknown), TestResult::TestPass),
+            "tests-1" => (TestResult::TestFail(FailedTests(btreeset!["lib: a".into()])), TestResult::TestFail(FailedTests(btreeset!["lib: a".into(), "lib: b".into()]))),
+            "tests-2" => (TestResult::TestFail(FailedTests(btreeset!["lib: a".into()])), TestResult::TestPass),
+            "ice-1" => (TestResult::TestPass, TestResult::BuildFail(ICE)),
+            "ice-2" => (TestResult::TestPass, TestResult::BuildFail(ICE))
         };
+        for (name, line) in &[("ice-1", 12), ("ice-2", 34)] {
+            let ice = Ice {
+                message: format!("no entry found for key `{}`", name),
+                location: Some(format!("src/librustc/hir/map/mod.rs:{}:5", line)),
+                query_stack: vec!["#0 [type_of] processing `foo`".into()],
+            };
+            db.add_dummy_ice(&ex, reg!(*name), TEST_TOOLCHAIN.clone(), ice);
+        }
 
         let raw = generate_report(&db, &config, &ex, &crates)?;
         let mut crates = raw
@@ -195,8 +356,8 @@ mod tests {
         let analyzed = analyze_report(raw);
 
         let mut info = IndexMap::new();
-        info.insert(Comparison::Regressed, 5);
-        info.insert(Comparison::Fixed, 2);
+        info.insert(Comparison::Regressed, 9);
+        info.insert(Comparison::Fixed, 3);
         info.insert(Comparison::SameTestPass, 1);
 
         macro_rules! create_results {
@@ -224,12 +385,21 @@ mod tests {
         let regr_root = create_results! {crates,
             TestResult::BuildFail(CompilerError(btreeset!["001".parse()?])) => ("ce-1"),
             TestResult::BuildFail(CompilerError(btreeset!["002".parse()?])) => ("ce-1", "ce-2"),
-            TestResult::BuildFail(Unknown) => ("unknown")
+            TestResult::BuildFail(Unknown) => ("unknown"),
+            TestResult::BuildFail(MissingSystemLibs(btreeset!["ssl".into()])) => ("libs"),
+            TestResult::BuildFail(MissingSystemLibs(btreeset!["z".into()])) => ("libs"),
+            TestResult::TestFail(FailedTests(btreeset!["lib: b".into()])) => ("tests-1")
+        };
+
+        let regr_ices = create_results! {crates,
+            "no entry found for key `_` at src/librustc/hir/map/mod.rs in type_of".to_string() => ("ice-1", "ice-2")
         };
 
         let regressed = ReportCrates::Complete {
             tree: regr_tree,
             results: regr_root,
+            ices: regr_ices,
+            lints: IndexMap::new(),
         };
 
         let fix_tree = create_results! {crates,
@@ -238,12 +408,15 @@ mod tests {
         };
 
         let fix_root = create_results! {crates,
-            TestResult::BuildFail(Unknown) => ("fix-2")
+            TestResult::BuildFail(Unknown) => ("fix-2"),
+            TestResult::TestFail(FailedTests(btreeset!["lib: a".into()])) => ("tests-2")
         };
 
         let fixed = ReportCrates::Complete {
             tree: fix_tree,
             results: fix_root,
+            ices: IndexMap::new(),
+            lints: IndexMap::new(),
         };
 
         let test_pass = ReportCrates::Plain(vec![crates.remove("test-pass").unwrap()]);
@@ -258,4 +431,86 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_future_incompat_analysis() -> Fallible<()> {
+        let reg = |name: &str| {
+            Crate::Registry(RegistryCrate {
+                name: name.into(),
+                version: "0.0.1".into(),
+            })
+        };
+
+        let config = Config::default();
+        let mut db = DummyDB::default();
+        let ex = Experiment {
+            mode: Mode::FutureIncompat,
+            ..Experiment::dummy("foo")
+        };
+
+        let lints = vec![
+            ("clean", vec![]),
+            ("dep-1", vec![Some("syn")]),
+            ("dep-2", vec![Some("nom")]),
+            ("dep-3", vec![Some("nom")]),
+            ("direct", vec![None, Some("nom")]),
+        ];
+        let mut crates = Vec::new();
+        for (name, deps) in lints {
+            let krate = reg(name);
+            for tc in &ex.toolchains {
+                db.add_dummy_result(&ex, krate.clone(), tc.clone(), TestResult::TestPass);
+            }
+            db.add_dummy_future_incompat(
+                &ex,
+                krate.clone(),
+                TEST_TOOLCHAIN.clone(),
+                deps.into_iter()
+                    .map(|dep| FutureIncompat {
+                        lint: "unaligned_references".into(),
+                        dependency: dep.map(reg),
+                    })
+                    .collect(),
+            );
+            crates.push(krate);
+        }
+
+        let raw = generate_report(&db, &config, &ex, &crates)?;
+        let analyzed = analyze_report(raw);
+        assert_eq!(analyzed.info[&Comparison::FutureIncompat], 4);
+        assert_eq!(analyzed.info[&Comparison::SameTestPass], 1);
+
+        let names = |crates: &Vec<CrateResult>| {
+            crates
+                .iter()
+                .map(|krate| krate.name.clone())
+                .collect::<Vec<_>>()
+        };
+        if let ReportCrates::Complete {
+            tree,
+            results,
+            ices,
+            lints,
+        } = &analyzed.categories[&Comparison::FutureIncompat]
+        {
+            // the dependency with the most dependents is listed first
+            assert_eq!(
+                tree.keys().cloned().collect::<Vec<_>>(),
+                vec![reg("nom"), reg("syn")]
+            );
+            assert_eq!(
+                names(&tree[&reg("nom")]),
+                vec!["dep-2-0.0.1", "dep-3-0.0.1", "direct-0.0.1"]
+            );
+            assert_eq!(names(&tree[&reg("syn")]), vec!["dep-1-0.0.1"]);
+            assert_eq!(lints.len(), 1);
+            assert_eq!(names(&lints["unaligned_references"]), vec!["direct-0.0.1"]);
+            assert!(results.is_empty());
+            assert!(ices.is_empty());
+        } else {
+            panic!("future-incompat crates should be analyzed in detail");
+        }
+
+        Ok(())
+    }
 }
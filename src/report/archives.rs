@@ -2,7 +2,7 @@ use crate::config::Config;
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::report::{compare, ReportWriter};
+use crate::report::{compare_results, summarize, ReportWriter};
 use crate::results::{EncodedLog, EncodingType, ReadResults};
 use flate2::{write::GzEncoder, Compression};
 use indexmap::IndexMap;
@@ -30,9 +30,7 @@ pub fn write_logs_archives<DB: ReadResults, W: ReportWriter>(
             continue;
         }
 
-        let res1 = db.load_test_result(ex, &ex.toolchains[0], krate)?;
-        let res2 = db.load_test_result(ex, &ex.toolchains[1], krate)?;
-        let comparison = compare(config, krate, res1.as_ref(), res2.as_ref());
+        let comparison = summarize(&compare_results(db, config, ex, krate)?);
 
         for tc in &ex.toolchains {
             let log = db
@@ -110,10 +108,12 @@ mod tests {
     use crate::actions::{Action, ActionsCtx, CreateExperiment};
     use crate::config::Config;
     use crate::db::Database;
-    use crate::experiments::Experiment;
+    use crate::experiments::{Experiment, Mode};
     use crate::prelude::*;
-    use crate::report::DummyWriter;
-    use crate::results::{DatabaseDB, EncodingType, FailureReason, TestResult, WriteResults};
+    use crate::report::{generate_report, DummyWriter};
+    use crate::results::{
+        CompileTime, DatabaseDB, EncodingType, FailureReason, TestResult, WriteResults,
+    };
     use flate2::read::GzDecoder;
     use mime::Mime;
     use std::io::Read;
@@ -142,6 +142,7 @@ mod tests {
             .record_result(
                 &ex,
                 &ex.toolchains[0],
+                None,
                 &crate1,
                 None,
                 &config,
@@ -156,6 +157,7 @@ mod tests {
             .record_result(
                 &ex,
                 &ex.toolchains[1],
+                None,
                 &crate1,
                 None,
                 &config,
@@ -170,6 +172,7 @@ mod tests {
             .record_result(
                 &ex,
                 &ex.toolchains[0],
+                None,
                 &crate2,
                 None,
                 &config,
@@ -184,6 +187,7 @@ mod tests {
             .record_result(
                 &ex,
                 &ex.toolchains[1],
+                None,
                 &crate2,
                 None,
                 &config,
@@ -277,4 +281,94 @@ mod tests {
             format!("test-pass/{}/{}.txt", crate2.id(), ex.toolchains[1]) => "tc2 crate2",
         });
     }
+
+    #[test]
+    fn test_logs_archives_refined_comparisons() {
+        rustwide::logging::init();
+
+        let config = Config::default();
+        let db = Database::temp().unwrap();
+        let writer = DummyWriter::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment {
+            mode: Mode::CompileTime,
+            ..CreateExperiment::dummy("dummy")
+        }
+        .apply(&ctx)
+        .unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+        let crates = &ex.get_crates(&db).unwrap()[..2];
+
+        // The first crate takes twice as long to build with the second toolchain
+        let results = DatabaseDB::new(&db);
+        for (krate, cpu_times) in crates.iter().zip(&[[1_000, 2_000], [1_000, 1_000]]) {
+            for (tc, cpu_time_ms) in ex.toolchains.iter().zip(cpu_times) {
+                results
+                    .record_result(
+                        &ex,
+                        tc,
+                        None,
+                        krate,
+                        None,
+                        &config,
+                        EncodingType::Plain,
+                        || {
+                            info!("{} {}", tc, krate);
+                            Ok(TestResult::TestSkipped)
+                        },
+                    )
+                    .unwrap();
+                results
+                    .record_extras(&ex, tc, krate, |extras| {
+                        extras.compile_time = Some(CompileTime {
+                            samples: 1,
+                            wall_time_ms: *cpu_time_ms,
+                            cpu_time_ms: Some(*cpu_time_ms),
+                            peak_rss_kb: None,
+                        })
+                    })
+                    .unwrap();
+            }
+        }
+
+        let archives = write_logs_archives(&results, &ex, crates, &writer, &config).unwrap();
+
+        // The crates are archived with the refined comparisons of the report
+        let mut archives_paths = archives.into_iter().map(|a| a.path).collect::<Vec<_>>();
+        archives_paths.sort();
+        assert_eq!(
+            &archives_paths,
+            &[
+                "logs-archives/all.tar.gz",
+                "logs-archives/same-compile-time.tar.gz",
+                "logs-archives/slower.tar.gz",
+            ]
+        );
+
+        let report = generate_report(&results, &config, &ex, crates).unwrap();
+        let mime: Mime = "application/gzip".parse().unwrap();
+        for krate in &report.crates {
+            let content = writer.get(&format!("logs-archives/{}.tar.gz", krate.res), &mime);
+            let mut archive = Archive::new(GzDecoder::new(content.as_slice()));
+            let paths = archive
+                .entries()
+                .unwrap()
+                .map(|entry| {
+                    entry
+                        .unwrap()
+                        .path()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(paths.len(), ex.toolchains.len());
+            for path in paths {
+                assert!(path.starts_with(&format!("{}/{}/", krate.res, krate.krate.id())));
+            }
+        }
+    }
 }
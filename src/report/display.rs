@@ -16,13 +16,40 @@ impl ResultName for FailureReason {
             FailureReason::ICE => "ICE".into(),
             FailureReason::CompilerError(_) => "compiler error".into(),
             FailureReason::DependsOn(_) => "faulty deps".into(),
+            FailureReason::FailedTests(_) => "failed".into(),
+            FailureReason::LinkerError => "linker error".into(),
+            FailureReason::MissingSystemLibs(_) => "missing system libs".into(),
+            FailureReason::BuildScriptPanic => "build script panic".into(),
+            FailureReason::ProcMacroPanic => "proc-macro panic".into(),
+            FailureReason::NetworkAccess => "network access".into(),
+            FailureReason::NoSpace => "out of disk space".into(),
+            FailureReason::Crash => "crashed".into(),
+            FailureReason::UndefinedBehavior => "UB detected".into(),
+            FailureReason::MiriUnsupported => "unsupported by Miri".into(),
+            FailureReason::Unformatted => "formatting diff".into(),
+            FailureReason::MigrationFailed => "migration failed".into(),
+            FailureReason::BreakingChanges(_) => "breaking changes".into(),
         }
     }
 
     fn long_name(&self) -> String {
         match self {
-            FailureReason::CompilerError(_) | FailureReason::DependsOn(_) => self.to_string(),
+            FailureReason::CompilerError(_)
+            | FailureReason::DependsOn(_)
+            | FailureReason::FailedTests(_)
+            | FailureReason::MissingSystemLibs(_)
+            | FailureReason::BreakingChanges(_) => self.to_string(),
             FailureReason::Unknown
+            | FailureReason::LinkerError
+            | FailureReason::BuildScriptPanic
+            | FailureReason::ProcMacroPanic
+            | FailureReason::NetworkAccess
+            | FailureReason::NoSpace
+            | FailureReason::Crash
+            | FailureReason::UndefinedBehavior
+            | FailureReason::MiriUnsupported
+            | FailureReason::Unformatted
+            | FailureReason::MigrationFailed
             | FailureReason::Timeout
             | FailureReason::OOM
             | FailureReason::ICE => self.name(),
@@ -37,6 +64,7 @@ impl ResultName for BrokenReason {
             BrokenReason::CargoToml => "broken Cargo.toml".into(),
             BrokenReason::Yanked => "deps yanked".into(),
             BrokenReason::MissingGitRepository => "missing repo".into(),
+            BrokenReason::Unresolvable => "deps unresolvable".into(),
         }
     }
 
@@ -96,6 +124,16 @@ impl ResultColor for Comparison {
             Comparison::Broken => Color::Single("#44176e"),
             Comparison::SpuriousRegressed => Color::Striped("#db3026", "#d5433b"),
             Comparison::SpuriousFixed => Color::Striped("#5630db", "#5d3dcf"),
+            Comparison::UnknownBaseline => Color::Striped("#494b4a", "#5c5e5d"),
+            Comparison::Slower => Color::Single("#db7826"),
+            Comparison::Faster => Color::Single("#2683db"),
+            Comparison::SameCompileTime => Color::Single("#72a156"),
+            Comparison::WarningsChanged => Color::Single("#c4a000"),
+            Comparison::FutureIncompat => Color::Single("#a07a1c"),
+            Comparison::FormattingChanged => Color::Single("#8e5ea2"),
+            Comparison::MigrationFailed => Color::Single("#b8336a"),
+            Comparison::MigratedBroken => Color::Single("#65461e"),
+            Comparison::MigratedClean => Color::Single("#72a156"),
         }
     }
 }
@@ -1,11 +1,14 @@
 use crate::assets;
-use crate::experiments::Experiment;
+use crate::crates::Crate;
+use crate::experiments::{Experiment, Mode};
 use crate::prelude::*;
 use crate::report::{
-    analyzer::ReportCrates, archives::Archive, Color, Comparison, CrateResult, ReportWriter,
-    ResultColor, ResultName, TestResults,
+    analyzer::{ReportCrates, ToolchainSelect},
+    archives::Archive,
+    crate_to_url, delta_percent, Color, Comparison, CrateResult, ReportWriter, ResultColor,
+    ResultName, TestResults,
 };
-use crate::results::EncodingType;
+use crate::results::{EncodingType, RustfmtDiff};
 use indexmap::IndexMap;
 
 #[derive(Serialize)]
@@ -19,12 +22,23 @@ struct NavbarItem {
 enum CurrentPage {
     Summary,
     Full,
+    CompileTime,
+    Lints,
+    FutureIncompat,
+    Formatting,
+    UnstableFeatures,
+    Targets,
     Downloads,
 }
 
 #[derive(Serialize)]
 enum ReportCratesHTML {
     Plain(Vec<CrateResultHTML>),
+    /// The crates grouped by name, used when several versions of the same crate are tested.
+    Versions {
+        count: u32,
+        versions: IndexMap<String, Vec<CrateResultHTML>>,
+    },
     Tree {
         count: u32,
         tree: IndexMap<String, Vec<CrateResultHTML>>,
@@ -36,8 +50,8 @@ enum ReportCratesHTML {
 }
 
 impl CurrentPage {
-    fn navbar(&self) -> Vec<NavbarItem> {
-        vec![
+    fn navbar(&self, ex: &Experiment) -> Vec<NavbarItem> {
+        let mut items = vec![
             NavbarItem {
                 label: "Summary",
                 url: "index.html",
@@ -48,12 +62,55 @@ impl CurrentPage {
                 url: "full.html",
                 active: *self == CurrentPage::Full,
             },
-            NavbarItem {
-                label: "Downloads",
-                url: "downloads.html",
-                active: *self == CurrentPage::Downloads,
-            },
-        ]
+        ];
+        if ex.mode == Mode::CompileTime {
+            items.push(NavbarItem {
+                label: "Compile time",
+                url: "compile-time.html",
+                active: *self == CurrentPage::CompileTime,
+            });
+        }
+        if ex.mode == Mode::LintDiff {
+            items.push(NavbarItem {
+                label: "Lints",
+                url: "lints.html",
+                active: *self == CurrentPage::Lints,
+            });
+        }
+        if ex.mode == Mode::FutureIncompat {
+            items.push(NavbarItem {
+                label: "Future incompat",
+                url: "future-incompat.html",
+                active: *self == CurrentPage::FutureIncompat,
+            });
+        }
+        if ex.mode == Mode::Rustfmt {
+            items.push(NavbarItem {
+                label: "Formatting",
+                url: "formatting.html",
+                active: *self == CurrentPage::Formatting,
+            });
+        }
+        if ex.mode == Mode::UnstableFeatures {
+            items.push(NavbarItem {
+                label: "Unstable features",
+                url: "unstable-features.html",
+                active: *self == CurrentPage::UnstableFeatures,
+            });
+        }
+        if ex.tests_multiple_targets() {
+            items.push(NavbarItem {
+                label: "Targets",
+                url: "targets.html",
+                active: *self == CurrentPage::Targets,
+            });
+        }
+        items.push(NavbarItem {
+            label: "Downloads",
+            url: "downloads.html",
+            active: *self == CurrentPage::Downloads,
+        });
+        items
     }
 }
 
@@ -79,12 +136,163 @@ struct DownloadsContext<'a> {
     available_archives: Vec<Archive>,
 }
 
+#[derive(Serialize)]
+struct CompileTimeContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    comparison_colors: IndexMap<Comparison, Color>,
+    rows: Vec<CompileTimeRow>,
+}
+
+#[derive(Serialize)]
+struct CompileTimeRow {
+    name: String,
+    url: String,
+    toolchain: String,
+    res: Comparison,
+    wall_time: Delta,
+    cpu_time: Option<Delta>,
+    peak_rss: Option<Delta>,
+}
+
+#[derive(Serialize)]
+struct Delta {
+    before: f64,
+    after: f64,
+    percent: f64,
+}
+
+impl Delta {
+    fn new(before: u64, after: u64, unit: f64) -> Self {
+        Delta {
+            before: before as f64 / unit,
+            after: after as f64 / unit,
+            percent: delta_percent(before, after),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LintsContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    lints: Vec<LintChanges>,
+}
+
+/// The crates where a lint started or stopped firing.
+#[derive(Serialize)]
+struct LintChanges {
+    lint: String,
+    appeared: Vec<CrateLink>,
+    disappeared: Vec<CrateLink>,
+}
+
+#[derive(Serialize)]
+struct FutureIncompatContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    lints: Vec<FutureIncompatLint>,
+    dependencies: Vec<FutureIncompatDependency>,
+}
+
+/// The crates triggering a future-incompatibility lint, in their own code or in a dependency.
+#[derive(Serialize)]
+struct FutureIncompatLint {
+    lint: String,
+    direct: Vec<CrateLink>,
+    through_dependency: Vec<CrateLink>,
+}
+
+/// A dependency triggering future-incompatibility lints, with the crates depending on it.
+#[derive(Serialize)]
+struct FutureIncompatDependency {
+    krate: CrateLink,
+    lints: Vec<String>,
+    dependents: Vec<CrateLink>,
+}
+
+#[derive(Serialize)]
+struct FormattingContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    comparison_colors: IndexMap<Comparison, Color>,
+    crates: Vec<FormattingChange>,
+}
+
+/// A crate whose formatting changed between the toolchains, with the diff of each toolchain.
+#[derive(Serialize)]
+struct FormattingChange {
+    name: String,
+    url: String,
+    res: Comparison,
+    diffs: Vec<ToolchainDiff>,
+}
+
+#[derive(Serialize)]
+struct ToolchainDiff {
+    toolchain: String,
+    diff: Option<RustfmtDiff>,
+}
+
+#[derive(Serialize)]
+struct UnstableFeaturesContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    features: Vec<UnstableFeatureUsage>,
+}
+
+/// The crates enabling an unstable feature, in their own code or only in a dependency.
+#[derive(Serialize)]
+struct UnstableFeatureUsage {
+    feature: String,
+    direct: Vec<CrateLink>,
+    through_dependency: Vec<CrateLink>,
+}
+
+#[derive(Serialize)]
+struct TargetsContext<'a> {
+    ex: &'a Experiment,
+    nav: Vec<NavbarItem>,
+    crates_count: usize,
+    toolchains: Vec<String>,
+    result_colors: Vec<Color>,
+    rows: Vec<TargetsRow>,
+}
+
+/// The result of a crate on each of the toolchains, and so on each of the targets.
+#[derive(Serialize)]
+struct TargetsRow {
+    name: String,
+    url: String,
+    results: Vec<Option<TargetResult>>,
+}
+
+#[derive(Serialize)]
+struct TargetResult {
+    res: usize,
+    name: String,
+    log: String,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+struct CrateLink {
+    name: String,
+    url: String,
+}
+
 #[derive(Serialize)]
 struct CrateResultHTML {
     name: String,
     url: String,
     res: Comparison,
-    runs: [Option<BuildTestResultHTML>; 2],
+    runs: Vec<Option<BuildTestResultHTML>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    regressed_features: Vec<String>,
 }
 
 // Map TestResult to usize to avoid the presence of special characters in html
@@ -92,6 +300,13 @@ struct CrateResultHTML {
 struct BuildTestResultHTML {
     res: usize,
     log: String,
+    // Comparison against the baseline, only present with more than two toolchains
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<Comparison>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reused_from: Option<String>,
+    // Whether the lockfile the crate was built with is available next to the log
+    lockfile: bool,
 }
 
 fn write_report<W: ReportWriter>(
@@ -107,9 +322,10 @@ fn write_report<W: ReportWriter>(
     let mut test_results_to_int = IndexMap::new();
     let mut result_colors = Vec::new();
     let mut result_names = Vec::new();
+    let group_versions = res.has_multiple_versions();
 
     let mut to_html_crate_result = |result: CrateResult| {
-        let mut runs = [None, None];
+        let mut runs = Vec::with_capacity(result.runs.len());
 
         for (pos, run) in result.runs.iter().enumerate() {
             if let Some(ref run) = run {
@@ -120,10 +336,17 @@ fn write_report<W: ReportWriter>(
                         result_names.push(run.res.name());
                         result_names.len() - 1
                     });
-                runs[pos] = Some(BuildTestResultHTML {
+                runs.push(Some(BuildTestResultHTML {
                     res: *idx as usize,
                     log: run.log.clone(),
-                });
+                    comparison: pos
+                        .checked_sub(1)
+                        .and_then(|pos| result.comparisons.get(pos).cloned()),
+                    reused_from: run.reused_from.clone(),
+                    lockfile: run.lockfile.is_some(),
+                }));
+            } else {
+                runs.push(None);
             }
         }
 
@@ -132,6 +355,7 @@ fn write_report<W: ReportWriter>(
             url: result.url.clone(),
             res: result.res,
             runs,
+            regressed_features: result.regressed_features.clone(),
         }
     };
 
@@ -143,6 +367,33 @@ fn write_report<W: ReportWriter>(
         .flat_map(|(category, crates)| {
             comparison_colors.insert(category, category.color());
 
+            // The versions of each crate are listed together, instead of by the cause of their
+            // failure
+            if group_versions {
+                let versions = crates
+                    .group_versions()
+                    .into_iter()
+                    .map(|(name, results)| {
+                        (
+                            name,
+                            results
+                                .into_iter()
+                                .map(|result| to_html_crate_result(result))
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                    .collect::<IndexMap<_, _>>();
+
+                return vec![(
+                    category,
+                    ReportCratesHTML::Versions {
+                        count: versions.len() as u32,
+                        versions,
+                    },
+                )]
+                .into_iter();
+            }
+
             match crates {
                 ReportCrates::Plain(crates) => vec![(
                     category,
@@ -154,7 +405,12 @@ fn write_report<W: ReportWriter>(
                     ),
                 )]
                 .into_iter(),
-                ReportCrates::Complete { tree, results } => {
+                ReportCrates::Complete {
+                    tree,
+                    results,
+                    ices,
+                    lints,
+                } => {
                     let tree = tree
                         .into_iter()
                         .map(|(root, deps)| {
@@ -168,9 +424,16 @@ fn write_report<W: ReportWriter>(
                         .collect::<IndexMap<_, _>>();
                     let results = results
                         .into_iter()
+                        .map(|(res, krates)| (res.long_name(), krates))
+                        .chain(ices.into_iter().map(|(signature, krates)| {
+                            (format!("build ICE: {}", signature), krates)
+                        }))
+                        .chain(lints.into_iter().map(|(lint, krates)| {
+                            (format!("future-incompat lint: {}", lint), krates)
+                        }))
                         .map(|(res, krates)| {
                             (
-                                res.long_name(),
+                                res,
                                 krates
                                     .into_iter()
                                     .map(|result| to_html_crate_result(result))
@@ -208,7 +471,7 @@ fn write_report<W: ReportWriter>(
         } else {
             CurrentPage::Summary
         }
-        .navbar(),
+        .navbar(ex),
         categories,
         info: res.info.clone(),
         full,
@@ -242,7 +505,7 @@ fn write_downloads<W: ReportWriter>(
 ) -> Fallible<()> {
     let context = DownloadsContext {
         ex,
-        nav: CurrentPage::Downloads.navbar(),
+        nav: CurrentPage::Downloads.navbar(ex),
         crates_count,
         available_archives,
     };
@@ -262,6 +525,530 @@ fn write_downloads<W: ReportWriter>(
     Ok(())
 }
 
+fn write_compile_time<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut comparison_colors = IndexMap::new();
+    let mut rows = Vec::new();
+
+    for crates in res.categories.values() {
+        let crates: Vec<&CrateResult> = match crates {
+            ReportCrates::Plain(crates) => crates.iter().collect(),
+            ReportCrates::Complete { results, .. } => results.values().flatten().collect(),
+        };
+
+        for krate in crates {
+            let compile_times = krate
+                .runs
+                .iter()
+                .map(|run| run.as_ref().and_then(|run| run.compile_time.as_ref()))
+                .collect::<Vec<_>>();
+            let baseline = match compile_times[0] {
+                Some(baseline) => baseline,
+                None => continue,
+            };
+
+            for (pos, compile_time) in compile_times.iter().enumerate().skip(1) {
+                let compile_time = match compile_time {
+                    Some(compile_time) => compile_time,
+                    None => continue,
+                };
+                let res = krate.comparisons.get(pos - 1).cloned().unwrap_or(krate.res);
+                comparison_colors.insert(res, res.color());
+
+                rows.push(CompileTimeRow {
+                    name: krate.name.clone(),
+                    url: krate.url.clone(),
+                    toolchain: ex.toolchains[pos].to_string(),
+                    res,
+                    wall_time: Delta::new(baseline.wall_time_ms, compile_time.wall_time_ms, 1000.0),
+                    cpu_time: match (baseline.cpu_time_ms, compile_time.cpu_time_ms) {
+                        (Some(before), Some(after)) => Some(Delta::new(before, after, 1000.0)),
+                        _ => None,
+                    },
+                    peak_rss: match (baseline.peak_rss_kb, compile_time.peak_rss_kb) {
+                        (Some(before), Some(after)) => Some(Delta::new(before, after, 1024.0)),
+                        _ => None,
+                    },
+                });
+            }
+        }
+    }
+
+    // Show the biggest slowdowns first
+    let time_delta = |row: &CompileTimeRow| row.cpu_time.as_ref().unwrap_or(&row.wall_time).percent;
+    rows.sort_by(|a, b| {
+        time_delta(b)
+            .partial_cmp(&time_delta(a))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let context = CompileTimeContext {
+        ex,
+        nav: CurrentPage::CompileTime.navbar(ex),
+        crates_count,
+        comparison_colors,
+        rows,
+    };
+
+    info!("generating compile-time.html");
+    let html = minifier::html::minify(&assets::render_template(
+        "report/compile-time.html",
+        &context,
+    )?);
+    dest.write_string("compile-time.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "compile-time.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_lints<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut lints: IndexMap<String, LintChanges> = IndexMap::new();
+    let mut record = |lint: &str, krate: &CrateResult, appeared: bool| {
+        let changes = lints
+            .entry(lint.to_string())
+            .or_insert_with(|| LintChanges {
+                lint: lint.to_string(),
+                appeared: Vec::new(),
+                disappeared: Vec::new(),
+            });
+        let crates = if appeared {
+            &mut changes.appeared
+        } else {
+            &mut changes.disappeared
+        };
+        let link = CrateLink {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+        };
+        // A crate is listed once even if the lint changed in multiple places
+        if !crates.contains(&link) {
+            crates.push(link);
+        }
+    };
+
+    for crates in res.categories.values() {
+        let crates: Vec<&CrateResult> = match crates {
+            ReportCrates::Plain(crates) => crates.iter().collect(),
+            ReportCrates::Complete { results, .. } => results.values().flatten().collect(),
+        };
+
+        for krate in crates {
+            let baseline = match &krate.runs[0] {
+                Some(baseline) => baseline,
+                None => continue,
+            };
+            for run in krate.runs[1..].iter().flatten() {
+                for warning in run.warnings.difference(&baseline.warnings) {
+                    record(&warning.lint, krate, true);
+                }
+                for warning in baseline.warnings.difference(&run.warnings) {
+                    record(&warning.lint, krate, false);
+                }
+            }
+        }
+    }
+
+    // Show the lints affecting the most crates first
+    let mut lints = lints.into_iter().map(|(_, lint)| lint).collect::<Vec<_>>();
+    lints.sort_by(|a, b| {
+        (b.appeared.len() + b.disappeared.len()).cmp(&(a.appeared.len() + a.disappeared.len()))
+    });
+
+    let context = LintsContext {
+        ex,
+        nav: CurrentPage::Lints.navbar(ex),
+        crates_count,
+        lints,
+    };
+
+    info!("generating lints.html");
+    let html = minifier::html::minify(&assets::render_template("report/lints.html", &context)?);
+    dest.write_string("lints.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "lints.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_future_incompat<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut lints: IndexMap<String, FutureIncompatLint> = IndexMap::new();
+    let mut dependencies: IndexMap<Crate, FutureIncompatDependency> = IndexMap::new();
+    let push = |crates: &mut Vec<CrateLink>, link: CrateLink| {
+        // A crate is listed once even if it triggers the lint in multiple packages
+        if !crates.contains(&link) {
+            crates.push(link);
+        }
+    };
+
+    if let Some(ReportCrates::Complete {
+        tree,
+        lints: direct,
+        ..
+    }) = res.categories.get(&Comparison::FutureIncompat)
+    {
+        let mut crates = tree
+            .values()
+            .chain(direct.values())
+            .flatten()
+            .collect::<Vec<&CrateResult>>();
+        crates.sort_by(|a, b| a.name.cmp(&b.name));
+        crates.dedup_by(|a, b| a.name == b.name);
+
+        for krate in crates {
+            let run = match &krate.runs[ToolchainSelect::End.run_index(krate)] {
+                Some(run) => run,
+                None => continue,
+            };
+            let link = CrateLink {
+                name: krate.name.clone(),
+                url: krate.url.clone(),
+            };
+            for future_incompat in &run.future_incompat {
+                let lint = lints
+                    .entry(future_incompat.lint.clone())
+                    .or_insert_with(|| FutureIncompatLint {
+                        lint: future_incompat.lint.clone(),
+                        direct: Vec::new(),
+                        through_dependency: Vec::new(),
+                    });
+                if let Some(dep) = &future_incompat.dependency {
+                    push(&mut lint.through_dependency, link.clone());
+
+                    if !dependencies.contains_key(dep) {
+                        dependencies.insert(
+                            dep.clone(),
+                            FutureIncompatDependency {
+                                krate: CrateLink {
+                                    name: dep.to_string(),
+                                    url: crate_to_url(dep)?,
+                                },
+                                lints: Vec::new(),
+                                dependents: Vec::new(),
+                            },
+                        );
+                    }
+                    let dependency = &mut dependencies[dep];
+                    if !dependency.lints.contains(&future_incompat.lint) {
+                        dependency.lints.push(future_incompat.lint.clone());
+                    }
+                    push(&mut dependency.dependents, link.clone());
+                } else {
+                    push(&mut lint.direct, link.clone());
+                }
+            }
+        }
+    }
+
+    // Show the lints affecting the most crates, and the dependencies whose fix would unblock the
+    // most dependents first
+    let mut lints = lints.into_iter().map(|(_, lint)| lint).collect::<Vec<_>>();
+    lints.sort_by(|a, b| {
+        (b.direct.len() + b.through_dependency.len())
+            .cmp(&(a.direct.len() + a.through_dependency.len()))
+    });
+    let mut dependencies = dependencies
+        .into_iter()
+        .map(|(_, dependency)| dependency)
+        .collect::<Vec<_>>();
+    dependencies.sort_by(|a, b| b.dependents.len().cmp(&a.dependents.len()));
+
+    let context = FutureIncompatContext {
+        ex,
+        nav: CurrentPage::FutureIncompat.navbar(ex),
+        crates_count,
+        lints,
+        dependencies,
+    };
+
+    info!("generating future-incompat.html");
+    let html = minifier::html::minify(&assets::render_template(
+        "report/future-incompat.html",
+        &context,
+    )?);
+    dest.write_string("future-incompat.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "future-incompat.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_formatting<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut comparison_colors = IndexMap::new();
+    let mut crates = Vec::new();
+
+    for (&category, krates) in &res.categories {
+        match category {
+            Comparison::Regressed | Comparison::Fixed | Comparison::FormattingChanged => {}
+            _ => continue,
+        }
+        let krates: Vec<&CrateResult> = match krates {
+            ReportCrates::Plain(krates) => krates.iter().collect(),
+            ReportCrates::Complete { results, .. } => results.values().flatten().collect(),
+        };
+
+        comparison_colors.insert(category, category.color());
+        for krate in krates {
+            crates.push(FormattingChange {
+                name: krate.name.clone(),
+                url: krate.url.clone(),
+                res: category,
+                diffs: ex
+                    .toolchains
+                    .iter()
+                    .zip(&krate.runs)
+                    .map(|(tc, run)| ToolchainDiff {
+                        toolchain: tc.to_string(),
+                        diff: run.as_ref().and_then(|run| run.rustfmt_diff.clone()),
+                    })
+                    .collect(),
+            });
+        }
+    }
+
+    let context = FormattingContext {
+        ex,
+        nav: CurrentPage::Formatting.navbar(ex),
+        crates_count,
+        comparison_colors,
+        crates,
+    };
+
+    info!("generating formatting.html");
+    let html = minifier::html::minify(&assets::render_template(
+        "report/formatting.html",
+        &context,
+    )?);
+    dest.write_string("formatting.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "formatting.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_unstable_features<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut features: IndexMap<String, UnstableFeatureUsage> = IndexMap::new();
+    let mut record = |feature: &str, krate: &CrateResult, direct: bool| {
+        let usage = features
+            .entry(feature.to_string())
+            .or_insert_with(|| UnstableFeatureUsage {
+                feature: feature.to_string(),
+                direct: Vec::new(),
+                through_dependency: Vec::new(),
+            });
+        let crates = if direct {
+            &mut usage.direct
+        } else {
+            &mut usage.through_dependency
+        };
+        let link = CrateLink {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+        };
+        if !crates.contains(&link) {
+            crates.push(link);
+        }
+    };
+
+    for crates in res.categories.values() {
+        let crates: Vec<&CrateResult> = match crates {
+            ReportCrates::Plain(crates) => crates.iter().collect(),
+            ReportCrates::Complete { results, .. } => results.values().flatten().collect(),
+        };
+
+        for krate in crates {
+            // The source code is the same for all the toolchains
+            let run = match krate.runs.iter().flatten().next() {
+                Some(run) => run,
+                None => continue,
+            };
+            for feature in &run.unstable_features {
+                record(feature, krate, true);
+            }
+            for feature in run
+                .dependencies_unstable_features
+                .difference(&run.unstable_features)
+            {
+                record(feature, krate, false);
+            }
+        }
+    }
+
+    // Show the features the most crates depend on first
+    let mut features = features
+        .into_iter()
+        .map(|(_, feature)| feature)
+        .collect::<Vec<_>>();
+    features.sort_by(|a, b| {
+        (b.direct.len() + b.through_dependency.len())
+            .cmp(&(a.direct.len() + a.through_dependency.len()))
+            .then_with(|| a.feature.cmp(&b.feature))
+    });
+
+    let context = UnstableFeaturesContext {
+        ex,
+        nav: CurrentPage::UnstableFeatures.navbar(ex),
+        crates_count,
+        features,
+    };
+
+    info!("generating unstable-features.html");
+    let html = minifier::html::minify(&assets::render_template(
+        "report/unstable-features.html",
+        &context,
+    )?);
+    dest.write_string("unstable-features.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "unstable-features.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_targets<W: ReportWriter>(
+    ex: &Experiment,
+    crates_count: usize,
+    res: &TestResults,
+    dest: &W,
+    output_templates: bool,
+) -> Fallible<()> {
+    let mut test_results_to_int = IndexMap::new();
+    let mut result_colors = Vec::new();
+    let mut rows = Vec::new();
+
+    let mut crates = res
+        .categories
+        .values()
+        .flat_map(|crates| -> Vec<&CrateResult> {
+            match crates {
+                ReportCrates::Plain(crates) => crates.iter().collect(),
+                ReportCrates::Complete {
+                    tree,
+                    results,
+                    ices,
+                    lints,
+                } => tree
+                    .values()
+                    .chain(results.values())
+                    .chain(ices.values())
+                    .chain(lints.values())
+                    .flatten()
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    crates.sort_by(|a, b| a.name.cmp(&b.name));
+    crates.dedup_by(|a, b| a.name == b.name);
+
+    for krate in crates {
+        let results = krate
+            .runs
+            .iter()
+            .map(|run| {
+                run.as_ref().map(|run| {
+                    let idx = *test_results_to_int
+                        .entry(run.res.clone())
+                        .or_insert_with(|| {
+                            result_colors.push(run.res.color());
+                            result_colors.len() - 1
+                        });
+                    TargetResult {
+                        res: idx,
+                        name: run.res.name(),
+                        log: run.log.clone(),
+                    }
+                })
+            })
+            .collect();
+        rows.push(TargetsRow {
+            name: krate.name.clone(),
+            url: krate.url.clone(),
+            results,
+        });
+    }
+
+    let context = TargetsContext {
+        ex,
+        nav: CurrentPage::Targets.navbar(ex),
+        crates_count,
+        toolchains: ex.toolchains.iter().map(|tc| tc.to_string()).collect(),
+        result_colors,
+        rows,
+    };
+
+    info!("generating targets.html");
+    let html = minifier::html::minify(&assets::render_template("report/targets.html", &context)?);
+    dest.write_string("targets.html", html.into(), &mime::TEXT_HTML)?;
+
+    if output_templates {
+        dest.write_string(
+            "targets.html.context.json",
+            serde_json::to_string(&context)?.into(),
+            &mime::APPLICATION_JSON,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn write_html_report<W: ReportWriter>(
     ex: &Experiment,
     crates_count: usize,
@@ -290,6 +1077,24 @@ pub fn write_html_report<W: ReportWriter>(
         dest,
         output_templates,
     )?;
+    if ex.mode == Mode::CompileTime {
+        write_compile_time(ex, crates_count, res, dest, output_templates)?;
+    }
+    if ex.mode == Mode::LintDiff {
+        write_lints(ex, crates_count, res, dest, output_templates)?;
+    }
+    if ex.mode == Mode::FutureIncompat {
+        write_future_incompat(ex, crates_count, res, dest, output_templates)?;
+    }
+    if ex.mode == Mode::Rustfmt {
+        write_formatting(ex, crates_count, res, dest, output_templates)?;
+    }
+    if ex.mode == Mode::UnstableFeatures {
+        write_unstable_features(ex, crates_count, res, dest, output_templates)?;
+    }
+    if ex.tests_multiple_targets() {
+        write_targets(ex, crates_count, res, dest, output_templates)?;
+    }
     write_downloads(ex, crates_count, available_archives, dest, output_templates)?;
 
     info!("copying static assets");
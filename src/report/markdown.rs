@@ -19,6 +19,7 @@ enum ReportCratesMD {
         #[serde(serialize_with = "to_vec")]
         orphans: IndexMap<Crate, Vec<CrateResult>>,
     },
+    Versions(IndexMap<String, Vec<CrateResult>>),
 }
 
 #[derive(Serialize)]
@@ -35,6 +36,7 @@ fn write_crate(
     krate: &CrateResult,
     comparison: Comparison,
     is_child: bool,
+    labels: &[String],
 ) -> Fallible<()> {
     let get_run_name = |run: &BuildTestResult| {
         if !is_child {
@@ -44,55 +46,65 @@ fn write_crate(
         }
     };
 
-    let runs = [
-        krate.runs[0]
-            .as_ref()
-            .map(get_run_name)
-            .unwrap_or_else(|| "unavailable".into()),
-        krate.runs[0]
-            .as_ref()
-            .map(|run| run.log.to_owned())
-            .unwrap_or_else(|| "#".into()),
-        krate.runs[1]
-            .as_ref()
-            .map(get_run_name)
-            .unwrap_or_else(|| "unavailable".into()),
-        krate.runs[1]
-            .as_ref()
-            .map(|run| run.log.to_owned())
-            .unwrap_or_else(|| "#".into()),
-    ];
+    let logs = krate
+        .runs
+        .iter()
+        .zip(labels)
+        .map(|(run, label)| {
+            let reused = run.as_ref().map_or(false, |run| run.reused_from.is_some());
+            format!(
+                "[{}{}]({}/log.txt)",
+                label,
+                if reused { " (cached)" } else { "" },
+                run.as_ref().map(|run| run.log.as_str()).unwrap_or("#")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
 
     let prefix = if is_child { "  * " } else { "* " };
 
     if let ReportConfig::Complete(toolchain) = comparison.report_config() {
-        let (conj, run) = match toolchain {
-            ToolchainSelect::Start => ("from", 0),
-            ToolchainSelect::End => ("due to", 2),
+        let conj = match toolchain {
+            ToolchainSelect::Start => "from",
+            ToolchainSelect::End => "due to",
+        };
+        let run = krate.runs[toolchain.run_index(krate)]
+            .as_ref()
+            .map(get_run_name)
+            .unwrap_or_else(|| "unavailable".into());
+        let features = krate
+            .regressed_features
+            .iter()
+            .map(|features| format!(" `{}`", features))
+            .collect::<Vec<_>>();
+        let features = if features.is_empty() {
+            String::new()
+        } else {
+            format!(" with{}", features.join(","))
         };
 
         writeln!(
             &mut rendered,
-            "{}[{}]({}) {} {} **{}** [start]({}/log.txt) | [end]({}/log.txt)",
+            "{}[{}]({}) {}{} {} **{}** {}",
             prefix,
             krate.name,
             krate.url,
             comparison.to_string(),
+            features,
             conj,
-            runs[run],
-            runs[1],
-            runs[3]
+            run,
+            logs
         )?;
     } else {
         writeln!(
             &mut rendered,
-            "{}[{}]({}) {} [start]({}/log.txt) | [end]({}/log.txt)",
+            "{}[{}]({}) {} {}",
             prefix,
             krate.name,
             krate.url,
             comparison.to_string(),
-            runs[1],
-            runs[3]
+            logs
         )?;
     };
 
@@ -105,19 +117,31 @@ fn render_markdown(context: &ResultsContext) -> Fallible<String> {
     //add title
     writeln!(&mut rendered, "# Crater report for {}\n\n", context.ex.name)?;
 
+    // Logs are labeled by their position when there are only two toolchains
+    let labels = if context.ex.toolchains.len() == 2 {
+        vec!["start".to_string(), "end".to_string()]
+    } else {
+        context
+            .ex
+            .toolchains
+            .iter()
+            .map(|tc| tc.to_string())
+            .collect()
+    };
+
     for (comparison, results) in context.categories.iter() {
         writeln!(&mut rendered, "\n### {}", comparison.to_string())?;
         match results {
             ReportCratesMD::Plain(crates) => {
                 for krate in crates {
-                    write_crate(&mut rendered, krate, *comparison, false)?;
+                    write_crate(&mut rendered, krate, *comparison, false, &labels)?;
                 }
             }
             ReportCratesMD::Complete { res, orphans } => {
                 for (root, deps) in res {
-                    write_crate(&mut rendered, root, *comparison, false)?;
+                    write_crate(&mut rendered, root, *comparison, false, &labels)?;
                     for krate in deps {
-                        write_crate(&mut rendered, krate, *comparison, true)?;
+                        write_crate(&mut rendered, krate, *comparison, true, &labels)?;
                     }
                 }
 
@@ -129,7 +153,15 @@ fn render_markdown(context: &ResultsContext) -> Fallible<String> {
                         crate_to_url(&krate)?
                     )?;
                     for krate in deps {
-                        write_crate(&mut rendered, krate, *comparison, true)?;
+                        write_crate(&mut rendered, krate, *comparison, true, &labels)?;
+                    }
+                }
+            }
+            ReportCratesMD::Versions(versions) => {
+                for (name, crates) in versions {
+                    writeln!(&mut rendered, "* {}", name)?;
+                    for krate in crates {
+                        write_crate(&mut rendered, krate, *comparison, true, &labels)?;
                     }
                 }
             }
@@ -148,20 +180,38 @@ fn write_report<W: ReportWriter>(
     dest: &W,
     output_templates: bool,
 ) -> Fallible<()> {
+    let group_versions = res.has_multiple_versions();
     let categories = res
         .categories
         .iter()
         .filter(|(category, _)| full || category.show_in_summary())
         .map(|(&category, crates)| (category, crates.to_owned()))
         .map(|(category, crates)| match crates {
+            crates if group_versions => {
+                (category, ReportCratesMD::Versions(crates.group_versions()))
+            }
             ReportCrates::Plain(crates) => (
                 category,
                 ReportCratesMD::Plain(crates.into_iter().collect::<Vec<_>>()),
             ),
-            ReportCrates::Complete { mut tree, results } => {
+            ReportCrates::Complete {
+                mut tree,
+                results,
+                ices,
+                lints,
+            } => {
                 let res = results
                     .into_iter()
                     .flat_map(|(_key, values)| values.into_iter())
+                    .chain(
+                        ices.into_iter()
+                            .flat_map(|(_key, values)| values.into_iter()),
+                    )
+                    .chain(
+                        lints
+                            .into_iter()
+                            .flat_map(|(_key, values)| values.into_iter()),
+                    )
                     .collect::<IndexSet<_>>() // remove duplicates
                     .into_iter()
                     .map(|krate| {
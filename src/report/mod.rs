@@ -1,17 +1,20 @@
 use crate::config::Config;
-use crate::crates::Crate;
-use crate::experiments::Experiment;
+use crate::crates::{Crate, RegistryCrate};
+use crate::experiments::{Experiment, FeatureSet, LockfilePolicy, Mode, Status};
 use crate::prelude::*;
 use crate::report::analyzer::{analyze_report, ReportConfig, ToolchainSelect};
-use crate::results::{EncodedLog, EncodingType, FailureReason, ReadResults, TestResult};
+use crate::results::{
+    CompileTime, EncodedLog, EncodingType, FailureReason, FeatureResults, FutureIncompats, Ice,
+    QueryResults, ReadResults, RustfmtDiff, TestResult, UnstableFeatures, Warnings,
+};
 use crate::toolchain::Toolchain;
 use crate::utils;
+use indexmap::IndexMap;
 use mime::{self, Mime};
 use percent_encoding::{utf8_percent_encode, AsciiSet};
 use std::borrow::Cow;
 #[cfg(test)]
 use std::cell::RefCell;
-#[cfg(test)]
 use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fmt::{self, Display};
@@ -54,7 +57,26 @@ pub struct CrateResult {
     url: String,
     krate: Crate,
     pub res: Comparison,
-    runs: [Option<BuildTestResult>; 2],
+    runs: Vec<Option<BuildTestResult>>,
+    /// Comparisons of each toolchain against the baseline, only present when the experiment has
+    /// more than two toolchains (otherwise `res` is the only comparison).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    comparisons: Vec<Comparison>,
+    /// The combinations of features that regressed with any toolchain, when the experiment
+    /// builds other combinations than the default one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    regressed_features: Vec<String>,
+}
+
+impl CrateResult {
+    /// Index of the run that determined the comparison of this crate.
+    fn deciding_run(&self) -> usize {
+        self.comparisons
+            .iter()
+            .position(|&comparison| comparison == self.res)
+            .map(|pos| pos + 1)
+            .unwrap_or(self.runs.len() - 1)
+    }
 }
 
 string_enum!(pub enum Comparison {
@@ -70,6 +92,16 @@ string_enum!(pub enum Comparison {
     SameTestPass => "test-pass",
     SpuriousRegressed => "spurious-regressed",
     SpuriousFixed => "spurious-fixed",
+    UnknownBaseline => "unknown-baseline",
+    Slower => "slower",
+    Faster => "faster",
+    SameCompileTime => "same-compile-time",
+    WarningsChanged => "warnings-changed",
+    FutureIncompat => "future-incompat",
+    FormattingChanged => "formatting-changed",
+    MigrationFailed => "migration-failed",
+    MigratedBroken => "migrated-broken",
+    MigratedClean => "migrated-clean",
 });
 
 impl Comparison {
@@ -80,13 +112,64 @@ impl Comparison {
             | Comparison::Unknown
             | Comparison::Error
             | Comparison::SpuriousRegressed
-            | Comparison::SpuriousFixed => true,
+            | Comparison::SpuriousFixed
+            | Comparison::UnknownBaseline
+            | Comparison::Slower
+            | Comparison::Faster
+            | Comparison::WarningsChanged
+            | Comparison::FutureIncompat
+            | Comparison::FormattingChanged
+            | Comparison::MigrationFailed
+            | Comparison::MigratedBroken => true,
+            Comparison::Skipped
+            | Comparison::Broken
+            | Comparison::SameBuildFail
+            | Comparison::SameTestFail
+            | Comparison::SameTestSkipped
+            | Comparison::SameTestPass
+            | Comparison::SameCompileTime
+            | Comparison::MigratedClean => false,
+        }
+    }
+
+    /// Whether the comparison is a change that is verified by re-running the crate, when the
+    /// experiment asks for it.
+    pub(crate) fn needs_verification(self) -> bool {
+        match self {
+            Comparison::Regressed
+            | Comparison::Fixed
+            | Comparison::Slower
+            | Comparison::Faster
+            | Comparison::WarningsChanged
+            | Comparison::FutureIncompat
+            | Comparison::FormattingChanged => true,
             Comparison::Skipped
+            | Comparison::Unknown
+            | Comparison::Error
             | Comparison::Broken
             | Comparison::SameBuildFail
             | Comparison::SameTestFail
             | Comparison::SameTestSkipped
-            | Comparison::SameTestPass => false,
+            | Comparison::SameTestPass
+            | Comparison::SpuriousRegressed
+            | Comparison::SpuriousFixed
+            | Comparison::UnknownBaseline
+            | Comparison::SameCompileTime
+            | Comparison::MigrationFailed
+            | Comparison::MigratedBroken
+            | Comparison::MigratedClean => false,
+        }
+    }
+
+    /// The comparison to show for a change the re-runs of the crate didn't reproduce.
+    fn spurious(self) -> Comparison {
+        match self {
+            Comparison::Regressed => Comparison::SpuriousRegressed,
+            Comparison::Fixed => Comparison::SpuriousFixed,
+            Comparison::Slower | Comparison::Faster => Comparison::SameCompileTime,
+            Comparison::WarningsChanged | Comparison::FutureIncompat => Comparison::SameTestPass,
+            Comparison::FormattingChanged => Comparison::SameTestFail,
+            other => other,
         }
     }
 
@@ -94,16 +177,26 @@ impl Comparison {
         match self {
             Comparison::Regressed => ReportConfig::Complete(ToolchainSelect::End),
             Comparison::Fixed => ReportConfig::Complete(ToolchainSelect::Start),
+            Comparison::FutureIncompat => ReportConfig::Complete(ToolchainSelect::End),
             Comparison::Unknown
             | Comparison::Error
             | Comparison::SpuriousRegressed
             | Comparison::SpuriousFixed
+            | Comparison::UnknownBaseline
             | Comparison::Skipped
             | Comparison::Broken
             | Comparison::SameBuildFail
             | Comparison::SameTestFail
             | Comparison::SameTestSkipped
-            | Comparison::SameTestPass => ReportConfig::Simple,
+            | Comparison::SameTestPass
+            | Comparison::Slower
+            | Comparison::Faster
+            | Comparison::SameCompileTime
+            | Comparison::WarningsChanged
+            | Comparison::FormattingChanged
+            | Comparison::MigrationFailed
+            | Comparison::MigratedBroken
+            | Comparison::MigratedClean => ReportConfig::Simple,
         }
     }
 }
@@ -113,6 +206,32 @@ impl Comparison {
 struct BuildTestResult {
     res: TestResult,
     log: String,
+    /// The experiment this result was reused from, if the crate was not built again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reused_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ice: Option<Ice>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compile_time: Option<CompileTime>,
+    #[serde(default, skip_serializing_if = "Warnings::is_empty")]
+    warnings: Warnings,
+    #[serde(default, skip_serializing_if = "FutureIncompats::is_empty")]
+    future_incompat: FutureIncompats,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rustfmt_diff: Option<RustfmtDiff>,
+    #[serde(default, skip_serializing_if = "FeatureResults::is_empty")]
+    feature_results: FeatureResults,
+    /// The policy the recorded lockfile was updated with, if the lockfile was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lockfile: Option<LockfilePolicy>,
+    #[serde(default, skip_serializing_if = "QueryResults::is_empty")]
+    query_results: QueryResults,
+    #[serde(default, skip_serializing_if = "UnstableFeatures::is_empty")]
+    unstable_features: UnstableFeatures,
+    /// The unstable features enabled by the dependencies of the crate, as far as the dependencies
+    /// are part of the experiment as well.
+    #[serde(default, skip_serializing_if = "UnstableFeatures::is_empty")]
+    dependencies_unstable_features: UnstableFeatures,
 }
 
 /// The type of sanitization required for a string.
@@ -181,47 +300,145 @@ pub fn generate_report<DB: ReadResults>(
     let mut crates = crates.to_vec();
     //crate ids are unique so unstable sort is equivalent to stable sort but is generally faster
     crates.sort_unstable_by(|a, b| a.id().cmp(&b.id()));
-    let res = crates
+    let mut res = crates
         .iter()
         .map(|krate| {
             // Any errors here will turn into unknown results
-            let crate_results = ex.toolchains.iter().map(|tc| -> Fallible<BuildTestResult> {
-                let res = db
-                    .load_test_result(ex, tc, &krate)?
-                    .ok_or_else(|| err_msg("no result"))?;
-
-                Ok(BuildTestResult {
-                    res,
-                    log: crate_to_path_fragment(tc, &krate, SanitizationContext::Url)
-                        .to_str()
-                        .unwrap()
-                        .replace(r"\", "/"), // Normalize paths in reports generated on Windows
-                })
-            });
-            // Convert errors to Nones
-            let mut crate_results = crate_results.map(|r| r.ok()).collect::<Vec<_>>();
-            let crate2 = crate_results.pop().unwrap();
-            let crate1 = crate_results.pop().unwrap();
-            let comp = compare(
-                config,
-                &krate,
-                crate1.as_ref().map(|b| &b.res),
-                crate2.as_ref().map(|b| &b.res),
-            );
+            let crate_results = ex
+                .toolchains
+                .iter()
+                .map(|tc| load_run(db, ex, tc, &krate).ok())
+                .collect::<Vec<_>>();
+            let (comparisons, regressed_features) =
+                compare_and_verify(db, config, ex, &krate, &crate_results)?;
 
             Ok(CrateResult {
                 name: crate_to_name(&krate)?,
                 url: crate_to_url(&krate)?,
                 krate: krate.clone(),
-                res: comp,
-                runs: [crate1, crate2],
+                res: summarize(&comparisons),
+                runs: crate_results,
+                comparisons: if comparisons.len() > 1 {
+                    comparisons
+                } else {
+                    Vec::new()
+                },
+                regressed_features,
             })
         })
         .collect::<Fallible<Vec<_>>>()?;
 
+    if ex.mode == Mode::UnstableFeatures {
+        add_dependencies_unstable_features(db, ex, &mut res)?;
+    }
+
     Ok(RawTestResults { crates: res })
 }
 
+/// Load the results of a crate and compare them as the report does, returning the comparison of
+/// each toolchain against the baseline. Everything categorizing the crates of an experiment goes
+/// through this, so that it always agrees with the report.
+pub(crate) fn compare_results<DB: ReadResults>(
+    db: &DB,
+    config: &Config,
+    ex: &Experiment,
+    krate: &Crate,
+) -> Fallible<Vec<Comparison>> {
+    // Any errors here will turn into unknown results
+    let runs = ex
+        .toolchains
+        .iter()
+        .map(|tc| -> Fallible<BuildTestResult> {
+            let res = db
+                .load_test_result(ex, tc, krate)?
+                .ok_or_else(|| err_msg("no result"))?;
+            load_compared_run(db, ex, tc, krate, res, 0)
+        })
+        .map(|run| run.ok())
+        .collect::<Vec<_>>();
+
+    Ok(compare_and_verify(db, config, ex, krate, &runs)?.0)
+}
+
+/// Compare the results of a finished experiment as the report would, returning the crates whose
+/// comparison is one of the given ones. The report doesn't need to be generated already.
+pub(crate) fn crates_with_comparisons<DB: ReadResults>(
+    db: &DB,
+    config: &Config,
+    ex: &Experiment,
+    crates: &[Crate],
+    comparisons: &[Comparison],
+) -> Fallible<Vec<Crate>> {
+    match ex.status {
+        Status::NeedsReport
+        | Status::GeneratingReport
+        | Status::ReportFailed
+        | Status::Completed => {}
+        status => bail!("experiment {} is not finished yet ({})", ex.name, status),
+    }
+
+    let mut found = Vec::new();
+    for krate in crates {
+        if comparisons.contains(&summarize(&compare_results(db, config, ex, krate)?)) {
+            found.push(krate.clone());
+        }
+    }
+    Ok(found)
+}
+
+/// Add to the results of each crate the unstable features enabled by the crates locked in its
+/// lockfile. Only the dependencies that are part of the experiment too can be accounted for, as
+/// the features of the others were never searched.
+fn add_dependencies_unstable_features<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    crates: &mut [CrateResult],
+) -> Fallible<()> {
+    for (i, tc) in ex.toolchains.iter().enumerate() {
+        let features: HashMap<RegistryCrate, UnstableFeatures> = crates
+            .iter()
+            .filter_map(|krate| match (&krate.krate, &krate.runs[i]) {
+                (Crate::Registry(details), Some(run)) if !run.unstable_features.is_empty() => {
+                    Some((details.clone(), run.unstable_features.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for krate in crates.iter_mut() {
+            let run = match &mut krate.runs[i] {
+                Some(run) => run,
+                None => continue,
+            };
+            let packages = match db.load_lockfile(ex, tc, &krate.krate)? {
+                Some(lockfile) => lockfile
+                    .registry_packages()
+                    .with_context(|_| format!("failed to parse the lockfile of {}", krate.krate)),
+                None => continue,
+            };
+            let packages = match packages {
+                Ok(packages) => packages,
+                Err(err) => {
+                    utils::report_failure(&err);
+                    continue;
+                }
+            };
+
+            for package in packages {
+                // Registry crates are locked in their own lockfile
+                if krate.krate == Crate::Registry(package.clone()) {
+                    continue;
+                }
+                if let Some(features) = features.get(&package) {
+                    run.dependencies_unstable_features
+                        .extend(features.iter().cloned());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 const PROGRESS_FRACTION: usize = 10; // write progress every ~1/N crates
 
 fn write_logs<DB: ReadResults, W: ReportWriter>(
@@ -265,6 +482,16 @@ fn write_logs<DB: ReadResults, W: ReportWriter>(
                     dest.write_bytes(log_path, data, &mime::TEXT_PLAIN_UTF_8, EncodingType::Gzip)
                 }
             }?;
+
+            if let Some(lockfile) = db.load_lockfile(ex, tc, krate)? {
+                let lockfile_path =
+                    crate_to_path_fragment(tc, krate, SanitizationContext::Path).join("Cargo.lock");
+                dest.write_string(
+                    lockfile_path,
+                    lockfile.content.into(),
+                    &mime::TEXT_PLAIN_UTF_8,
+                )?;
+            }
         }
     }
     Ok(())
@@ -297,6 +524,19 @@ pub fn gen<DB: ReadResults, W: ReportWriter + Display>(
         gen_retry_list(&raw).into(),
         &mime::TEXT_PLAIN_UTF_8,
     )?;
+    dest.write_string(
+        "ices.json",
+        serde_json::to_string(&gen_ice_list(&raw))?.into(),
+        &mime::APPLICATION_JSON,
+    )?;
+    if ex.mode == Mode::Query {
+        dest.write_bytes(
+            "queries.csv",
+            gen_query_csv(&raw, ex)?,
+            &mime::TEXT_CSV_UTF_8,
+            EncodingType::Plain,
+        )?;
+    }
 
     let res = analyze_report(raw);
     info!("writing archives");
@@ -342,6 +582,86 @@ fn gen_retry_list(res: &RawTestResults) -> String {
     out
 }
 
+#[derive(Serialize)]
+struct IceReport<'a> {
+    signature: String,
+    #[serde(flatten)]
+    ice: &'a Ice,
+    crates: Vec<&'a str>,
+}
+
+/// Generates the list of the distinct ICEs found in the experiment and the crates hitting them,
+/// to search for the issues already reported for them.
+fn gen_ice_list(res: &RawTestResults) -> Vec<IceReport<'_>> {
+    let mut ices: IndexMap<String, IceReport> = IndexMap::new();
+
+    for krate in &res.crates {
+        for ice in krate
+            .runs
+            .iter()
+            .flatten()
+            .filter_map(|run| run.ice.as_ref())
+        {
+            let signature = ice.signature();
+            let report = ices.entry(signature.clone()).or_insert_with(|| IceReport {
+                signature,
+                ice,
+                crates: Vec::new(),
+            });
+            if !report.crates.contains(&krate.name.as_str()) {
+                report.crates.push(&krate.name);
+            }
+        }
+    }
+
+    ices.into_iter().map(|(_, report)| report).collect()
+}
+
+/// Generates a CSV file with the number of matches of each query in each crate, followed by the
+/// total number of matches of each query and the number of crates it matched.
+fn gen_query_csv(res: &RawTestResults, ex: &Experiment) -> Fallible<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header = vec!["crate"];
+    header.extend(ex.queries.iter().map(|query| query.name.as_str()));
+    writer.write_record(&header)?;
+
+    let mut total_matches = vec![0u64; ex.queries.len()];
+    let mut crates_matching = vec![0u64; ex.queries.len()];
+    for krate in &res.crates {
+        // The source code is the same for all the toolchains
+        let run = match krate.runs.iter().flatten().next() {
+            Some(run) => run,
+            None => continue,
+        };
+
+        let mut record = vec![krate.name.clone()];
+        for (i, query) in ex.queries.iter().enumerate() {
+            let matches = run
+                .query_results
+                .get(&query.name)
+                .map(|result| result.matches)
+                .unwrap_or(0);
+            total_matches[i] += u64::from(matches);
+            if matches > 0 {
+                crates_matching[i] += 1;
+            }
+            record.push(matches.to_string());
+        }
+        writer.write_record(&record)?;
+    }
+
+    for (label, counts) in &[
+        ("(total matches)", total_matches),
+        ("(crates matching)", crates_matching),
+    ] {
+        let mut record = vec![label.to_string()];
+        record.extend(counts.iter().map(|count| count.to_string()));
+        writer.write_record(&record)?;
+    }
+
+    Ok(writer.into_inner()?)
+}
+
 fn crate_to_name(c: &Crate) -> Fallible<String> {
     Ok(match *c {
         Crate::Registry(ref details) => format!("{}-{}", details.name, details.version),
@@ -391,6 +711,401 @@ fn crate_to_url(c: &Crate) -> Fallible<String> {
     })
 }
 
+/// Load the original run of a crate on a toolchain.
+fn load_run<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    tc: &Toolchain,
+    krate: &Crate,
+) -> Fallible<BuildTestResult> {
+    let res = db
+        .load_test_result(ex, tc, krate)?
+        .ok_or_else(|| err_msg("no result"))?;
+
+    Ok(BuildTestResult {
+        log: crate_to_path_fragment(tc, krate, SanitizationContext::Url)
+            .to_str()
+            .unwrap()
+            .replace(r"\", "/"), // Normalize paths in reports generated on Windows
+        reused_from: db.load_reused_from(ex, tc, krate)?,
+        ice: db.load_ice(ex, tc, krate)?,
+        lockfile: db.load_lockfile(ex, tc, krate)?.map(|l| l.policy),
+        query_results: db.load_query_results(ex, tc, krate)?,
+        unstable_features: db.load_unstable_features(ex, tc, krate)?,
+        ..load_compared_run(db, ex, tc, krate, res, 0)?
+    })
+}
+
+/// Load only the data compared between the runs of a crate, either for the original run (`retry`
+/// 0) or for one of the re-runs done while verifying the experiment.
+fn load_compared_run<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    tc: &Toolchain,
+    krate: &Crate,
+    res: TestResult,
+    retry: u32,
+) -> Fallible<BuildTestResult> {
+    Ok(BuildTestResult {
+        res,
+        log: String::new(),
+        reused_from: None,
+        ice: None,
+        compile_time: db.load_compile_time(ex, tc, krate, retry)?,
+        warnings: db.load_warnings(ex, tc, krate, retry)?,
+        future_incompat: db.load_future_incompat(ex, tc, krate, retry)?,
+        rustfmt_diff: db.load_rustfmt_diff(ex, tc, krate, retry)?,
+        feature_results: db.load_feature_results(ex, tc, krate, retry)?,
+        lockfile: None,
+        query_results: QueryResults::new(),
+        unstable_features: UnstableFeatures::new(),
+        dependencies_unstable_features: UnstableFeatures::new(),
+    })
+}
+
+/// Load the re-runs done while verifying the experiment, each of them with the run of every
+/// toolchain. The baseline toolchain isn't run again when its results come from another
+/// experiment, so its original run is compared against every re-run instead.
+fn load_retries<DB: ReadResults>(
+    db: &DB,
+    ex: &Experiment,
+    krate: &Crate,
+    runs: &[Option<BuildTestResult>],
+) -> Fallible<Vec<Vec<Option<BuildTestResult>>>> {
+    if ex.verify_runs == 0 {
+        return Ok(Vec::new());
+    }
+
+    let retries = ex
+        .toolchains
+        .iter()
+        .map(|tc| -> Fallible<Option<Vec<BuildTestResult>>> {
+            if !ex.built_toolchains().contains(tc) {
+                return Ok(None);
+            }
+            db.load_retried_test_results(ex, tc, krate)?
+                .into_iter()
+                .zip(1..)
+                .map(|(res, retry)| load_compared_run(db, ex, tc, krate, res, retry))
+                .collect::<Fallible<_>>()
+                .map(Some)
+        })
+        .collect::<Fallible<Vec<_>>>()?;
+
+    // Crates still being verified are only compared on the re-runs completed on every toolchain
+    let count = retries.iter().flatten().map(Vec::len).min().unwrap_or(0);
+    Ok((0..count)
+        .map(|retry| {
+            retries
+                .iter()
+                .zip(runs)
+                .map(|(tc_retries, run)| match tc_retries {
+                    Some(tc_retries) => Some(tc_retries[retry].clone()),
+                    None => run.clone(),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Compare the runs of a crate and mark the changes that the re-runs done while verifying the
+/// experiment didn't reproduce as spurious. The combinations of features that regressed are
+/// returned as well.
+fn compare_and_verify<DB: ReadResults>(
+    db: &DB,
+    config: &Config,
+    ex: &Experiment,
+    krate: &Crate,
+    runs: &[Option<BuildTestResult>],
+) -> Fallible<(Vec<Comparison>, Vec<String>)> {
+    let (comparisons, regressed_features) = compare_runs(config, ex, krate, runs);
+    let retries = load_retries(db, ex, krate, runs)?
+        .iter()
+        .map(|retry| compare_runs(config, ex, krate, retry).0)
+        .collect::<Vec<_>>();
+
+    Ok((
+        verify_comparisons(comparisons, &retries),
+        regressed_features,
+    ))
+}
+
+/// Compare the run of each toolchain against the baseline, refining the comparisons with the data
+/// recorded for the mode and the features of the experiment.
+fn compare_runs(
+    config: &Config,
+    ex: &Experiment,
+    krate: &Crate,
+    runs: &[Option<BuildTestResult>],
+) -> (Vec<Comparison>, Vec<String>) {
+    let results = runs
+        .iter()
+        .map(|run| run.as_ref().map(|run| &run.res))
+        .collect::<Vec<_>>();
+    let comparisons = compare_crate(config, ex, krate, &results);
+    let comparisons = match ex.mode {
+        Mode::CompileTime => compare_compile_times(config, runs, comparisons),
+        Mode::LintDiff => compare_warnings(runs, comparisons),
+        Mode::FutureIncompat => compare_future_incompat(runs, comparisons),
+        Mode::Rustfmt => compare_rustfmt_diffs(runs, comparisons),
+        Mode::EditionMigration => compare_migrations(runs, comparisons),
+        _ => comparisons,
+    };
+
+    if ex.features == FeatureSet::Default {
+        (comparisons, Vec::new())
+    } else {
+        compare_feature_results(config, krate, runs, comparisons)
+    }
+}
+
+/// Compare the results of a crate in the experiment, handling crates missing from the baseline
+/// experiment.
+fn compare_crate(
+    config: &Config,
+    ex: &Experiment,
+    krate: &Crate,
+    results: &[Option<&TestResult>],
+) -> Vec<Comparison> {
+    if ex.baseline.is_some() && results[0].is_none() {
+        return vec![Comparison::UnknownBaseline; results.len() - 1];
+    }
+
+    compare_with_baseline(config, krate, results)
+}
+
+/// Compare the result of each toolchain against the first one.
+fn compare_with_baseline(
+    config: &Config,
+    krate: &Crate,
+    results: &[Option<&TestResult>],
+) -> Vec<Comparison> {
+    let (baseline, others) = results
+        .split_first()
+        .expect("experiments have at least two toolchains");
+    others
+        .iter()
+        .map(|&res| compare(config, krate, *baseline, res))
+        .collect()
+}
+
+/// The first comparison worth showing in the summary, or the last one if none of them are.
+fn summarize(comparisons: &[Comparison]) -> Comparison {
+    comparisons
+        .iter()
+        .find(|comparison| comparison.show_in_summary())
+        .or_else(|| comparisons.last())
+        .cloned()
+        .expect("experiments have at least two toolchains")
+}
+
+/// Refine the comparisons of crates built successfully by every toolchain with how long it took
+/// to build them and how much memory they needed.
+fn compare_compile_times(
+    config: &Config,
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> Vec<Comparison> {
+    let compile_time =
+        |run: &Option<BuildTestResult>| run.as_ref().and_then(|run| run.compile_time.as_ref());
+    let baseline = compile_time(&runs[0]);
+    comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(
+            |(comparison, run)| match (comparison, baseline, compile_time(run)) {
+                (Comparison::SameTestSkipped, Some(baseline), Some(compile_time)) => {
+                    compare_compile_time(config, baseline, compile_time)
+                }
+                (comparison, _, _) => comparison,
+            },
+        )
+        .collect()
+}
+
+/// Refine the comparisons of crates checked successfully by every toolchain with whether the
+/// compiler emitted different warnings.
+fn compare_warnings(
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> Vec<Comparison> {
+    comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(|(comparison, run)| match (comparison, &runs[0], run) {
+            (Comparison::SameTestPass, Some(baseline), Some(run))
+                if baseline.warnings != run.warnings =>
+            {
+                Comparison::WarningsChanged
+            }
+            (comparison, _, _) => comparison,
+        })
+        .collect()
+}
+
+/// Refine the comparisons of crates checked successfully by every toolchain with whether they,
+/// or any of their dependencies, trigger future-incompatibility lints.
+fn compare_future_incompat(
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> Vec<Comparison> {
+    comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(|(comparison, run)| match (comparison, run) {
+            (Comparison::SameTestPass, Some(run)) if !run.future_incompat.is_empty() => {
+                Comparison::FutureIncompat
+            }
+            (comparison, _) => comparison,
+        })
+        .collect()
+}
+
+/// Refine the comparisons of crates whose formatting is wrong with every toolchain with whether
+/// rustfmt would change it in a different way.
+fn compare_rustfmt_diffs(
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> Vec<Comparison> {
+    comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(|(comparison, run)| match (comparison, &runs[0], run) {
+            (Comparison::SameTestFail, Some(baseline), Some(run))
+                if baseline.rustfmt_diff != run.rustfmt_diff =>
+            {
+                Comparison::FormattingChanged
+            }
+            (comparison, _, _) => comparison,
+        })
+        .collect()
+}
+
+/// Refine the comparisons of crates with the same result on every toolchain with how their
+/// migration to the next edition went.
+fn compare_migrations(
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> Vec<Comparison> {
+    comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(|(comparison, run)| match (comparison, run) {
+            (Comparison::SameBuildFail, Some(run))
+                if run.res == TestResult::BuildFail(FailureReason::MigrationFailed) =>
+            {
+                Comparison::MigrationFailed
+            }
+            (Comparison::SameBuildFail, _) | (Comparison::SameTestFail, _) => {
+                Comparison::MigratedBroken
+            }
+            (Comparison::SameTestPass, _) | (Comparison::SameTestSkipped, _) => {
+                Comparison::MigratedClean
+            }
+            (comparison, _) => comparison,
+        })
+        .collect()
+}
+
+/// Refine the comparisons of crates built with multiple combinations of features with whether any
+/// of the combinations regressed, even if the result of the whole crate didn't change. The
+/// combinations that regressed with any toolchain are returned as well.
+fn compare_feature_results(
+    config: &Config,
+    krate: &Crate,
+    runs: &[Option<BuildTestResult>],
+    comparisons: Vec<Comparison>,
+) -> (Vec<Comparison>, Vec<String>) {
+    let mut regressed_features = Vec::new();
+    let comparisons = comparisons
+        .into_iter()
+        .zip(&runs[1..])
+        .map(|(comparison, run)| {
+            let (baseline, run) = match (&runs[0], run) {
+                (Some(baseline), Some(run)) => (baseline, run),
+                _ => return comparison,
+            };
+
+            let mut regressed = false;
+            for (features, result) in &run.feature_results {
+                let baseline = baseline.feature_results.get(features);
+                if compare(config, krate, baseline, Some(result)) == Comparison::Regressed {
+                    regressed = true;
+                    if !regressed_features.contains(features) {
+                        regressed_features.push(features.clone());
+                    }
+                }
+            }
+
+            match comparison {
+                Comparison::Fixed
+                | Comparison::SameBuildFail
+                | Comparison::SameTestFail
+                | Comparison::SameTestSkipped
+                | Comparison::SameTestPass
+                    if regressed =>
+                {
+                    Comparison::Regressed
+                }
+                comparison => comparison,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    (comparisons, regressed_features)
+}
+
+/// Change in percent of a measurement compared to the baseline.
+fn delta_percent(baseline: u64, value: u64) -> f64 {
+    if baseline == 0 {
+        0.0
+    } else {
+        (value as f64 - baseline as f64) / baseline as f64 * 100.0
+    }
+}
+
+/// Compare the resources used to build a crate, preferring the CPU time over the wall-clock time
+/// as it's less affected by the load of the machine.
+fn compare_compile_time(config: &Config, c1: &CompileTime, c2: &CompileTime) -> Comparison {
+    let time = match (c1.cpu_time_ms, c2.cpu_time_ms) {
+        (Some(t1), Some(t2)) => delta_percent(t1, t2),
+        _ => delta_percent(c1.wall_time_ms, c2.wall_time_ms),
+    };
+    let memory = match (c1.peak_rss_kb, c2.peak_rss_kb) {
+        (Some(m1), Some(m2)) => delta_percent(m1, m2),
+        _ => 0.0,
+    };
+
+    let threshold = config.compile_time.threshold;
+    if time > threshold || memory > threshold {
+        Comparison::Slower
+    } else if time < -threshold || memory < -threshold {
+        Comparison::Faster
+    } else {
+        Comparison::SameCompileTime
+    }
+}
+
+/// Mark the changes that any of the re-runs done while verifying the experiment didn't reproduce
+/// as spurious.
+fn verify_comparisons(
+    comparisons: Vec<Comparison>,
+    retries: &[Vec<Comparison>],
+) -> Vec<Comparison> {
+    comparisons
+        .into_iter()
+        .enumerate()
+        .map(|(i, comparison)| {
+            if comparison.needs_verification() && retries.iter().any(|retry| retry[i] != comparison)
+            {
+                comparison.spurious()
+            } else {
+                comparison
+            }
+        })
+        .collect()
+}
+
 fn compare(
     config: &Config,
     krate: &Crate,
@@ -408,6 +1123,16 @@ fn compare(
             (BuildFail(_), BuildFail(FailureReason::ICE)) => Comparison::Regressed,
 
             (BuildFail(_), BuildFail(_)) => Comparison::SameBuildFail,
+            // Failing tests are only a regression if they didn't fail before.
+            (
+                TestFail(FailureReason::FailedTests(tests1)),
+                TestFail(FailureReason::FailedTests(tests2)),
+            ) if !tests2.is_subset(tests1) => Comparison::Regressed,
+            // Same for the breaking changes to the public API.
+            (
+                TestFail(FailureReason::BreakingChanges(changes1)),
+                TestFail(FailureReason::BreakingChanges(changes2)),
+            ) if !changes2.is_subset(changes1) => Comparison::Regressed,
             (TestFail(_), TestFail(_)) => Comparison::SameTestFail,
             (TestSkipped, TestSkipped) => Comparison::SameTestSkipped,
             (TestPass, TestPass) => Comparison::SameTestPass,
@@ -584,8 +1309,8 @@ mod tests {
     use super::*;
     use crate::config::{Config, CrateConfig};
     use crate::crates::{Crate, GitHubRepo, RegistryCrate};
-    use crate::experiments::{CapLints, Experiment, Mode, Status};
-    use crate::results::{BrokenReason, DummyDB, FailureReason, TestResult};
+    use crate::experiments::{Experiment, LockfilePolicy, Mode, Status};
+    use crate::results::{BrokenReason, DummyDB, FailureReason, Lockfile, QueryResult, TestResult};
     use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
 
     #[test]
@@ -689,7 +1414,8 @@ mod tests {
 
     #[test]
     fn test_compare() {
-        use crate::results::{FailureReason::*, TestResult::*};
+        use crate::results::{BreakingChange, FailureReason::*, TestResult::*};
+        use std::collections::BTreeSet;
 
         macro_rules! test_compare {
             ($cmp:ident, $config:expr, $reg:expr, [$($a:expr, $b:expr => $c:ident;)*]) => {
@@ -720,6 +1446,9 @@ mod tests {
             [
                 BuildFail(Unknown), BuildFail(Unknown) => SameBuildFail;
                 TestFail(Unknown), TestFail(Unknown) => SameTestFail;
+                TestFail(FailedTests(btreeset!["a".to_string()])), TestFail(FailedTests(btreeset!["a".to_string()])) => SameTestFail;
+                TestFail(FailedTests(btreeset!["a".to_string(), "b".to_string()])), TestFail(FailedTests(btreeset!["a".to_string()])) => SameTestFail;
+                TestFail(Unknown), TestFail(FailedTests(btreeset!["a".to_string()])) => SameTestFail;
                 TestSkipped, TestSkipped => SameTestSkipped;
                 TestPass, TestPass => SameTestPass;
 
@@ -732,6 +1461,9 @@ mod tests {
                 TestPass, BuildFail(Unknown) => Regressed;
                 TestSkipped, BuildFail(Unknown) => Regressed;
                 TestFail(Unknown), BuildFail(Unknown) => Regressed;
+                TestFail(FailedTests(btreeset!["a".to_string()])), TestFail(FailedTests(btreeset!["a".to_string(), "b".to_string()])) => Regressed;
+                TestFail(BreakingChanges(btreeset![BreakingChange::Removed("a".into())])), TestFail(BreakingChanges(btreeset![BreakingChange::Removed("a".into()), BreakingChange::Removed("b".into())])) => Regressed;
+                TestFail(BreakingChanges(btreeset![BreakingChange::Removed("a".into())])), TestFail(BreakingChanges(btreeset![BreakingChange::Removed("a".into())])) => SameTestFail;
 
                 // ICE is special
                 BuildFail(Unknown), BuildFail(ICE) => Regressed;
@@ -798,6 +1530,416 @@ mod tests {
         assert_eq!(compare(&config, &reg, None, None), Comparison::Skipped);
     }
 
+    #[test]
+    fn test_compare_with_baseline() {
+        use crate::results::{FailureReason::*, TestResult::*};
+
+        let config = Config::default();
+        let reg = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1.0".into(),
+        });
+
+        assert_eq!(
+            compare_with_baseline(&config, &reg, &[Some(&TestPass), Some(&TestPass)]),
+            vec![Comparison::SameTestPass]
+        );
+
+        // Every toolchain is compared against the first one
+        let failed = BuildFail(Unknown);
+        let comparisons = compare_with_baseline(
+            &config,
+            &reg,
+            &[Some(&TestPass), Some(&TestPass), Some(&failed), None],
+        );
+        assert_eq!(
+            comparisons,
+            vec![
+                Comparison::SameTestPass,
+                Comparison::Regressed,
+                Comparison::Unknown
+            ]
+        );
+
+        // The first interesting comparison is used as the summary
+        assert_eq!(summarize(&comparisons), Comparison::Regressed);
+    }
+
+    #[test]
+    fn test_verify_comparisons() {
+        use crate::report::Comparison::*;
+
+        // Changes not reproduced by every re-run are spurious
+        assert_eq!(
+            verify_comparisons(
+                vec![Regressed, Fixed, Slower, WarningsChanged, SameTestPass],
+                &[
+                    vec![
+                        Regressed,
+                        Fixed,
+                        SameCompileTime,
+                        WarningsChanged,
+                        SameTestPass
+                    ],
+                    vec![SameTestPass, Fixed, Slower, SameTestPass, Regressed],
+                ]
+            ),
+            vec![
+                SpuriousRegressed,
+                Fixed,
+                SameCompileTime,
+                SameTestPass,
+                SameTestPass
+            ]
+        );
+
+        // Nothing is verified without re-runs
+        assert_eq!(
+            verify_comparisons(vec![Regressed, FormattingChanged], &[]),
+            vec![Regressed, FormattingChanged]
+        );
+    }
+
+    #[test]
+    fn test_verify_against_baseline() {
+        let config = Config::default();
+
+        let krate = |name: &str| {
+            Crate::Registry(RegistryCrate {
+                name: name.into(),
+                version: "1.0.0".into(),
+            })
+        };
+        let (foo, bar) = (krate("foo"), krate("bar"));
+
+        let baseline = Experiment::dummy("baseline");
+        let ex = Experiment {
+            verify_runs: 1,
+            baseline: Some("baseline".into()),
+            ..Experiment::dummy("foo")
+        };
+
+        // The baseline toolchain is never run again, only the other one is
+        let mut db = DummyDB::default();
+        for (krate, retry) in &[
+            (&foo, TestResult::BuildFail(FailureReason::Unknown)),
+            (&bar, TestResult::TestPass),
+        ] {
+            let krate = (*krate).clone();
+            db.add_dummy_result(
+                &baseline,
+                krate.clone(),
+                MAIN_TOOLCHAIN.clone(),
+                TestResult::TestPass,
+            );
+            db.add_dummy_result(
+                &ex,
+                krate.clone(),
+                TEST_TOOLCHAIN.clone(),
+                TestResult::BuildFail(FailureReason::Unknown),
+            );
+            db.add_dummy_retried_result(&ex, krate, TEST_TOOLCHAIN.clone(), retry.clone());
+        }
+
+        let raw = generate_report(&db, &config, &ex, &[foo, bar]).unwrap();
+        let res = |name: &str| {
+            raw.crates
+                .iter()
+                .find(|krate| krate.name == name)
+                .unwrap()
+                .res
+        };
+        assert_eq!(res("foo-1.0.0"), Comparison::Regressed);
+        assert_eq!(res("bar-1.0.0"), Comparison::SpuriousRegressed);
+    }
+
+    #[test]
+    fn test_compare_warnings() {
+        use crate::results::Warning;
+
+        let run = |res: TestResult, lints: &[&str]| {
+            Some(BuildTestResult {
+                res,
+                log: String::new(),
+                reused_from: None,
+                ice: None,
+                compile_time: None,
+                warnings: lints
+                    .iter()
+                    .map(|lint| Warning {
+                        lint: lint.to_string(),
+                        span: "src/lib.rs:1:1".into(),
+                    })
+                    .collect(),
+                future_incompat: FutureIncompats::new(),
+                rustfmt_diff: None,
+                feature_results: FeatureResults::new(),
+                lockfile: None,
+                query_results: QueryResults::new(),
+                unstable_features: UnstableFeatures::new(),
+                dependencies_unstable_features: UnstableFeatures::new(),
+            })
+        };
+        let failed = TestResult::BuildFail(FailureReason::Unknown);
+
+        assert_eq!(
+            compare_warnings(
+                &[
+                    run(TestResult::TestPass, &["dead_code"]),
+                    run(TestResult::TestPass, &["dead_code"]),
+                    run(TestResult::TestPass, &["dead_code", "unused_mut"]),
+                    run(failed, &[]),
+                ],
+                vec![
+                    Comparison::SameTestPass,
+                    Comparison::SameTestPass,
+                    Comparison::Regressed
+                ]
+            ),
+            vec![
+                Comparison::SameTestPass,
+                Comparison::WarningsChanged,
+                Comparison::Regressed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_future_incompat() {
+        use crate::results::FutureIncompat;
+
+        let run = |res: TestResult, lints: &[&str]| {
+            Some(BuildTestResult {
+                res,
+                log: String::new(),
+                reused_from: None,
+                ice: None,
+                compile_time: None,
+                warnings: Warnings::new(),
+                future_incompat: lints
+                    .iter()
+                    .map(|lint| FutureIncompat {
+                        lint: lint.to_string(),
+                        dependency: None,
+                    })
+                    .collect(),
+                rustfmt_diff: None,
+                feature_results: FeatureResults::new(),
+                lockfile: None,
+                query_results: QueryResults::new(),
+                unstable_features: UnstableFeatures::new(),
+                dependencies_unstable_features: UnstableFeatures::new(),
+            })
+        };
+        let failed = TestResult::BuildFail(FailureReason::Unknown);
+
+        assert_eq!(
+            compare_future_incompat(
+                &[
+                    run(TestResult::TestPass, &[]),
+                    run(TestResult::TestPass, &[]),
+                    run(TestResult::TestPass, &["unaligned_references"]),
+                    run(failed, &[]),
+                ],
+                vec![
+                    Comparison::SameTestPass,
+                    Comparison::SameTestPass,
+                    Comparison::Regressed
+                ]
+            ),
+            vec![
+                Comparison::SameTestPass,
+                Comparison::FutureIncompat,
+                Comparison::Regressed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_rustfmt_diffs() {
+        let run = |res: TestResult, hunks: Option<&str>| {
+            Some(BuildTestResult {
+                res,
+                log: String::new(),
+                reused_from: None,
+                ice: None,
+                compile_time: None,
+                warnings: Warnings::new(),
+                future_incompat: FutureIncompats::new(),
+                rustfmt_diff: hunks.map(|hunks| RustfmtDiff {
+                    files_changed: 1,
+                    hunks: hunks.into(),
+                }),
+                feature_results: FeatureResults::new(),
+                lockfile: None,
+                query_results: QueryResults::new(),
+                unstable_features: UnstableFeatures::new(),
+                dependencies_unstable_features: UnstableFeatures::new(),
+            })
+        };
+        let unformatted = TestResult::TestFail(FailureReason::Unformatted);
+
+        assert_eq!(
+            compare_rustfmt_diffs(
+                &[
+                    run(unformatted.clone(), Some("-fn foo( ) {}")),
+                    run(unformatted.clone(), Some("-fn foo( ) {}")),
+                    run(unformatted.clone(), Some("-fn foo(  ) {}")),
+                    run(TestResult::TestPass, None),
+                ],
+                vec![
+                    Comparison::SameTestFail,
+                    Comparison::SameTestFail,
+                    Comparison::Fixed
+                ]
+            ),
+            vec![
+                Comparison::SameTestFail,
+                Comparison::FormattingChanged,
+                Comparison::Fixed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_migrations() {
+        let run = |res: TestResult| {
+            Some(BuildTestResult {
+                res,
+                log: String::new(),
+                reused_from: None,
+                ice: None,
+                compile_time: None,
+                warnings: Warnings::new(),
+                future_incompat: FutureIncompats::new(),
+                rustfmt_diff: None,
+                feature_results: FeatureResults::new(),
+                lockfile: None,
+                query_results: QueryResults::new(),
+                unstable_features: UnstableFeatures::new(),
+                dependencies_unstable_features: UnstableFeatures::new(),
+            })
+        };
+        let migration_failed = TestResult::BuildFail(FailureReason::MigrationFailed);
+
+        assert_eq!(
+            compare_migrations(
+                &[
+                    run(TestResult::TestPass),
+                    run(TestResult::TestPass),
+                    run(TestResult::TestFail(FailureReason::Unknown)),
+                ],
+                vec![Comparison::SameTestPass, Comparison::Regressed]
+            ),
+            vec![Comparison::MigratedClean, Comparison::Regressed]
+        );
+        assert_eq!(
+            compare_migrations(
+                &[run(migration_failed.clone()), run(migration_failed)],
+                vec![Comparison::SameBuildFail]
+            ),
+            vec![Comparison::MigrationFailed]
+        );
+        assert_eq!(
+            compare_migrations(
+                &[
+                    run(TestResult::TestFail(FailureReason::Unknown)),
+                    run(TestResult::TestFail(FailureReason::Unknown)),
+                ],
+                vec![Comparison::SameTestFail]
+            ),
+            vec![Comparison::MigratedBroken]
+        );
+    }
+
+    #[test]
+    fn test_compare_feature_results() {
+        let config = Config::default();
+        let reg = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let failed = TestResult::BuildFail(FailureReason::Unknown);
+        let run = |results: &[(&str, &TestResult)]| {
+            Some(BuildTestResult {
+                res: results
+                    .iter()
+                    .map(|&(_, res)| res.clone())
+                    .find(|res| *res != TestResult::TestPass)
+                    .unwrap_or(TestResult::TestPass),
+                log: String::new(),
+                reused_from: None,
+                ice: None,
+                compile_time: None,
+                warnings: Warnings::new(),
+                future_incompat: FutureIncompats::new(),
+                rustfmt_diff: None,
+                feature_results: results
+                    .iter()
+                    .map(|&(features, res)| (features.to_string(), res.clone()))
+                    .collect(),
+                lockfile: None,
+                query_results: QueryResults::new(),
+                unstable_features: UnstableFeatures::new(),
+                dependencies_unstable_features: UnstableFeatures::new(),
+            })
+        };
+
+        let no_default = "--no-default-features";
+        let with_std = "--no-default-features --features std";
+        assert_eq!(
+            compare_feature_results(
+                &config,
+                &reg,
+                &[
+                    run(&[(no_default, &failed), (with_std, &TestResult::TestPass)]),
+                    run(&[(no_default, &failed), (with_std, &TestResult::TestPass)]),
+                    run(&[(no_default, &failed), (with_std, &failed)]),
+                ],
+                vec![Comparison::SameBuildFail, Comparison::SameBuildFail]
+            ),
+            (
+                vec![Comparison::SameBuildFail, Comparison::Regressed],
+                vec![with_std.to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_compare_compile_time() {
+        let config = Config::default();
+        let compile_time = |wall_time_ms, cpu_time_ms, peak_rss_kb| CompileTime {
+            samples: 3,
+            wall_time_ms,
+            cpu_time_ms,
+            peak_rss_kb,
+        };
+        let base = compile_time(10_000, Some(20_000), Some(100_000));
+
+        macro_rules! test_compare_compile_time {
+            ($($c2:expr => $cmp:ident,)*) => {
+                $(
+                    assert_eq!(
+                        compare_compile_time(&config, &base, &$c2),
+                        Comparison::$cmp
+                    );
+                )*
+            };
+        }
+
+        test_compare_compile_time! {
+            compile_time(10_000, Some(20_500), Some(100_000)) => SameCompileTime,
+            // The CPU time is preferred over the wall-clock time
+            compile_time(20_000, Some(20_000), Some(100_000)) => SameCompileTime,
+            compile_time(10_000, Some(22_000), Some(100_000)) => Slower,
+            compile_time(10_000, Some(18_000), Some(100_000)) => Faster,
+            compile_time(10_000, Some(20_000), Some(110_000)) => Slower,
+            compile_time(10_000, Some(18_000), Some(110_000)) => Slower,
+            compile_time(12_000, None, None) => Slower,
+            compile_time(8_000, None, Some(100_000)) => Faster,
+        }
+    }
+
     #[test]
     fn test_report_generation() {
         let config = Config::default();
@@ -813,22 +1955,7 @@ mod tests {
             version: "1.0.0".into(),
         });
 
-        let ex = Experiment {
-            name: "foo".to_string(),
-            toolchains: [MAIN_TOOLCHAIN.clone(), TEST_TOOLCHAIN.clone()],
-            mode: Mode::BuildAndTest,
-            cap_lints: CapLints::Forbid,
-            priority: 0,
-            created_at: ::chrono::Utc::now(),
-            started_at: None,
-            completed_at: None,
-            github_issue: None,
-            status: Status::GeneratingReport,
-            assigned_to: None,
-            report_url: None,
-            ignore_blacklist: false,
-            requirement: None,
-        };
+        let ex = Experiment::dummy("foo");
 
         let mut db = DummyDB::default();
         db.add_dummy_result(
@@ -880,6 +2007,15 @@ mod tests {
             TEST_TOOLCHAIN.clone(),
             EncodedLog::Plain(b"beta log".to_vec()),
         );
+        db.add_dummy_lockfile(
+            &ex,
+            reg.clone(),
+            TEST_TOOLCHAIN.clone(),
+            Lockfile {
+                policy: LockfilePolicy::AsPrepared,
+                content: "# beta lockfile".into(),
+            },
+        );
 
         let writer = DummyWriter::default();
         gen(&db, &ex, &[gh, reg], &writer, &config, false).unwrap();
@@ -897,6 +2033,10 @@ mod tests {
             &writer.get("beta/gh/brson.hello-rs/log.txt", &mime::TEXT_PLAIN_UTF_8),
             b"beta log"
         );
+        assert_eq!(
+            &writer.get("beta/reg/syn-1.0.0/Cargo.lock", &mime::TEXT_PLAIN_UTF_8),
+            b"# beta lockfile"
+        );
 
         let result: RawTestResults =
             serde_json::from_slice(&writer.get("results.json", &mime::APPLICATION_JSON)).unwrap();
@@ -950,10 +2090,288 @@ mod tests {
             (&reg_result.runs[1]).as_ref().unwrap().log.as_str(),
             "beta/reg/syn-1.0.0"
         );
+        assert_eq!((&reg_result.runs[0]).as_ref().unwrap().lockfile, None);
+        assert_eq!(
+            (&reg_result.runs[1]).as_ref().unwrap().lockfile,
+            Some(LockfilePolicy::AsPrepared)
+        );
 
         assert_eq!(
             writer.get("retry-regressed-list.txt", &mime::TEXT_PLAIN_UTF_8),
             b"brson/hello-rs\nsyn\n",
         );
     }
+
+    #[test]
+    fn test_report_generation_groups_versions() {
+        let config = Config::default();
+
+        let versions = ["1.0.0", "1.1.0"]
+            .iter()
+            .map(|version| {
+                Crate::Registry(RegistryCrate {
+                    name: "syn".into(),
+                    version: (*version).into(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let ex = Experiment::dummy("foo");
+
+        let mut db = DummyDB::default();
+        for krate in &versions {
+            db.add_dummy_result(
+                &ex,
+                krate.clone(),
+                MAIN_TOOLCHAIN.clone(),
+                TestResult::TestPass,
+            );
+            db.add_dummy_result(
+                &ex,
+                krate.clone(),
+                TEST_TOOLCHAIN.clone(),
+                TestResult::BuildFail(FailureReason::Unknown),
+            );
+            for tc in &ex.toolchains {
+                db.add_dummy_log(
+                    &ex,
+                    krate.clone(),
+                    tc.clone(),
+                    EncodedLog::Plain(b"log".to_vec()),
+                );
+            }
+        }
+
+        let writer = DummyWriter::default();
+        gen(&db, &ex, &versions, &writer, &config, true).unwrap();
+
+        // The regressed category lists both versions under the name of the crate
+        let context: serde_json::Value =
+            serde_json::from_slice(&writer.get("index.html.context.json", &mime::APPLICATION_JSON))
+                .unwrap();
+        let (comparison, crates) = (&context["categories"][0][0], &context["categories"][0][1]);
+        assert_eq!(comparison, "regressed");
+        assert_eq!(crates["Versions"]["count"], 1);
+        let names = crates["Versions"]["versions"]["syn"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|krate| krate["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["syn-1.0.0", "syn-1.1.0"]);
+
+        let markdown = String::from_utf8(writer.get("markdown.md", &mime::TEXT_PLAIN)).unwrap();
+        let group = markdown.find("\n* syn\n").unwrap();
+        let first = markdown
+            .find("  * [syn-1.0.0](https://crates.io/crates/syn/1.0.0) regressed")
+            .unwrap();
+        let second = markdown
+            .find("  * [syn-1.1.0](https://crates.io/crates/syn/1.1.0) regressed")
+            .unwrap();
+        assert!(group < first && first < second);
+    }
+
+    #[test]
+    fn test_gen_query_csv() {
+        let config = Config::default();
+
+        let syn = Crate::Registry(RegistryCrate {
+            name: "syn".into(),
+            version: "1.0.0".into(),
+        });
+        let serde = Crate::Registry(RegistryCrate {
+            name: "serde".into(),
+            version: "1.0.0".into(),
+        });
+
+        let ex = Experiment {
+            mode: Mode::Query,
+            queries: vec![
+                "unsafe=unsafe".parse().unwrap(),
+                "transmute=transmute".parse().unwrap(),
+            ],
+            ..Experiment::dummy("foo")
+        };
+
+        let mut db = DummyDB::default();
+        for (krate, unsafe_matches) in &[(&syn, 3), (&serde, 0)] {
+            for tc in &ex.toolchains {
+                db.add_dummy_result(&ex, (*krate).clone(), tc.clone(), TestResult::TestPass);
+                let mut query_results = QueryResults::new();
+                query_results.insert(
+                    "unsafe".into(),
+                    QueryResult {
+                        matches: *unsafe_matches,
+                        lines: Vec::new(),
+                    },
+                );
+                db.add_dummy_query_results(&ex, (*krate).clone(), tc.clone(), query_results);
+            }
+        }
+
+        let raw = generate_report(&db, &config, &ex, &[syn, serde]).unwrap();
+        assert_eq!(
+            String::from_utf8(gen_query_csv(&raw, &ex).unwrap()).unwrap(),
+            "crate,unsafe,transmute\n\
+             serde-1.0.0,0,0\n\
+             syn-1.0.0,3,0\n\
+             (total matches),3,0\n\
+             (crates matching),1,0\n"
+        );
+    }
+
+    #[test]
+    fn test_dependencies_unstable_features() {
+        use std::collections::BTreeSet;
+
+        let config = Config::default();
+
+        let foo = Crate::Registry(RegistryCrate {
+            name: "foo".into(),
+            version: "1.0.0".into(),
+        });
+        let bar = Crate::Registry(RegistryCrate {
+            name: "bar".into(),
+            version: "1.0.0".into(),
+        });
+
+        let ex = Experiment {
+            mode: Mode::UnstableFeatures,
+            ..Experiment::dummy("foo")
+        };
+
+        let mut db = DummyDB::default();
+        for tc in &ex.toolchains {
+            for krate in &[&foo, &bar] {
+                db.add_dummy_result(&ex, (*krate).clone(), tc.clone(), TestResult::TestPass);
+            }
+            db.add_dummy_unstable_features(
+                &ex,
+                foo.clone(),
+                tc.clone(),
+                btreeset!["never_type".into()],
+            );
+            db.add_dummy_unstable_features(
+                &ex,
+                bar.clone(),
+                tc.clone(),
+                btreeset!["specialization".into()],
+            );
+            db.add_dummy_lockfile(
+                &ex,
+                bar.clone(),
+                tc.clone(),
+                Lockfile {
+                    policy: LockfilePolicy::AsPrepared,
+                    content: "[[package]]\n\
+                              name = \"bar\"\n\
+                              version = \"1.0.0\"\n\
+                              source = \"registry+https://github.com/rust-lang/crates.io-index\"\n\
+                              [[package]]\n\
+                              name = \"foo\"\n\
+                              version = \"1.0.0\"\n\
+                              source = \"registry+https://github.com/rust-lang/crates.io-index\"\n"
+                        .into(),
+                },
+            );
+        }
+
+        let raw = generate_report(&db, &config, &ex, &[foo, bar]).unwrap();
+        let runs = |name: &str| {
+            raw.crates
+                .iter()
+                .find(|krate| krate.name == name)
+                .unwrap()
+                .runs
+                .iter()
+                .map(|run| run.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        for run in runs("bar-1.0.0") {
+            assert_eq!(run.unstable_features, btreeset!["specialization".into()]);
+            assert_eq!(
+                run.dependencies_unstable_features,
+                btreeset!["never_type".into()]
+            );
+        }
+        for run in runs("foo-1.0.0") {
+            assert!(run.dependencies_unstable_features.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_crates_with_comparisons() {
+        let config = Config::default();
+
+        let krate = |name: &str| {
+            Crate::Registry(RegistryCrate {
+                name: name.into(),
+                version: "1.0.0".into(),
+            })
+        };
+        let (foo, bar, baz) = (krate("foo"), krate("bar"), krate("baz"));
+
+        let mut ex = Experiment {
+            status: Status::NeedsReport,
+            ..Experiment::dummy("foo")
+        };
+
+        let mut db = DummyDB::default();
+        for (krate, main, test) in &[
+            (
+                &foo,
+                TestResult::TestPass,
+                TestResult::BuildFail(FailureReason::Unknown),
+            ),
+            (
+                &bar,
+                TestResult::BuildFail(FailureReason::Unknown),
+                TestResult::TestPass,
+            ),
+            (&baz, TestResult::TestPass, TestResult::TestPass),
+        ] {
+            db.add_dummy_result(&ex, (*krate).clone(), MAIN_TOOLCHAIN.clone(), main.clone());
+            db.add_dummy_result(&ex, (*krate).clone(), TEST_TOOLCHAIN.clone(), test.clone());
+        }
+
+        let crates = [foo.clone(), bar.clone(), baz.clone()];
+        assert_eq!(
+            crates_with_comparisons(&db, &config, &ex, &crates, &[Comparison::Regressed]).unwrap(),
+            vec![foo.clone()]
+        );
+
+        let mut selected = crates_with_comparisons(
+            &db,
+            &config,
+            &ex,
+            &crates,
+            &[Comparison::Regressed, Comparison::Fixed],
+        )
+        .unwrap();
+        selected.sort();
+        let mut expected = vec![foo, bar];
+        expected.sort();
+        assert_eq!(selected, expected);
+
+        // Crates can be picked as soon as every crate is tested, but not while the experiment runs
+        for status in &[
+            Status::GeneratingReport,
+            Status::ReportFailed,
+            Status::Completed,
+        ] {
+            ex.status = *status;
+            assert!(
+                crates_with_comparisons(&db, &config, &ex, &crates, &[Comparison::Regressed])
+                    .is_ok()
+            );
+        }
+        for status in &[Status::Queued, Status::Running, Status::Verifying] {
+            ex.status = *status;
+            assert!(
+                crates_with_comparisons(&db, &config, &ex, &crates, &[Comparison::Regressed])
+                    .is_err()
+            );
+        }
+    }
 }
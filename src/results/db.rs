@@ -1,12 +1,15 @@
 use crate::config::Config;
 use crate::crates::Crate;
 use crate::db::{Database, QueryUtils};
-use crate::experiments::{Experiment, Status};
+use crate::experiments::{Experiment, Mode, Status};
 use crate::prelude::*;
 use crate::results::{
-    DeleteResults, EncodedLog, EncodingType, ReadResults, TestResult, WriteResults,
+    CompileTime, DeleteResults, EncodedLog, EncodingType, FeatureResults, FutureIncompat,
+    FutureIncompats, Ice, Lockfile, QueryResult, QueryResults, ReadResults, ResultExtras,
+    RustfmtDiff, TestResult, UnstableFeatures, Warning, Warnings, WriteResults,
 };
 use crate::toolchain::Toolchain;
+use chrono::{Duration, Utc};
 use rustwide::logging::{self, LogStorage};
 
 #[derive(Deserialize)]
@@ -14,8 +17,13 @@ pub struct TaskResult {
     #[serde(rename = "crate")]
     pub krate: Crate,
     pub toolchain: Toolchain,
+    /// The compiler the toolchain resolved to on the agent, if it was resolved.
+    #[serde(default)]
+    pub compiler: Option<String>,
     pub result: TestResult,
     pub log: String,
+    #[serde(default)]
+    pub extras: ResultExtras,
 }
 
 #[derive(Deserialize)]
@@ -24,6 +32,89 @@ pub struct ProgressData {
     pub version: Option<(Crate, Crate)>,
 }
 
+/// Only results of registry crates are cached, as their source can't change between experiments.
+/// Patched toolchains are excluded as well, as the patched branches can change. The cached results
+/// are keyed on the compiler the toolchain resolved to, as channels like `nightly` change over
+/// time.
+fn is_cacheable(krate: &Crate, toolchain: &Toolchain) -> bool {
+    matches!(krate, Crate::Registry(_)) && toolchain.patches.is_empty()
+}
+
+/// A table storing part of the data recorded for a crate besides its result.
+struct ExtrasTable {
+    name: &'static str,
+    /// The columns holding the data, besides the experiment, the crate and the toolchain.
+    columns: &'static str,
+    /// Whether the data of the re-runs done while verifying is stored as well.
+    retried: bool,
+}
+
+/// The tables storing the data recorded for a crate besides its result, which is copied along with
+/// cached results and deleted along with the results.
+const EXTRAS_TABLES: &[ExtrasTable] = &[
+    ExtrasTable {
+        name: "test_outcomes",
+        columns: "test, outcome",
+        retried: false,
+    },
+    ExtrasTable {
+        name: "ices",
+        columns: "message, location, query_stack",
+        retried: false,
+    },
+    ExtrasTable {
+        name: "compile_times",
+        columns: "samples, wall_time_ms, cpu_time_ms, peak_rss_kb",
+        retried: true,
+    },
+    ExtrasTable {
+        name: "warnings",
+        columns: "lint, span",
+        retried: true,
+    },
+    ExtrasTable {
+        name: "future_incompat",
+        columns: "lint, dependency",
+        retried: true,
+    },
+    ExtrasTable {
+        name: "rustfmt_diffs",
+        columns: "files_changed, hunks",
+        retried: true,
+    },
+    ExtrasTable {
+        name: "feature_results",
+        columns: "features, result",
+        retried: true,
+    },
+    ExtrasTable {
+        name: "lockfiles",
+        columns: "policy, content",
+        retried: false,
+    },
+    ExtrasTable {
+        name: "query_results",
+        columns: "query, matches, lines",
+        retried: false,
+    },
+    ExtrasTable {
+        name: "unstable_features",
+        columns: "feature",
+        retried: false,
+    },
+];
+
+/// The retry the data compared between the runs of a crate belongs to. While verifying, the
+/// result of the re-run is stored before the rest of its data.
+fn current_retry(ex: &Experiment) -> &'static str {
+    if ex.status == Status::Verifying {
+        "(SELECT COALESCE(MAX(retry), 0) FROM results \
+         WHERE experiment = ?1 AND crate = ?2 AND toolchain = ?3)"
+    } else {
+        "0"
+    }
+}
+
 pub struct DatabaseDB<'a> {
     db: &'a Database,
 }
@@ -44,10 +135,12 @@ impl<'a> DatabaseDB<'a> {
                 ex,
                 &result.krate,
                 &result.toolchain,
+                result.compiler.as_deref(),
                 &result.result,
                 &base64::decode(&result.log).with_context(|_| "invalid base64 log provided")?,
                 encoding_type,
             )?;
+            self.store_extras(ex, &result.toolchain, &result.krate, &result.extras)?;
 
             if let Some((old, new)) = &data.version {
                 self.update_crate_version(ex, old, new)?;
@@ -60,24 +153,93 @@ impl<'a> DatabaseDB<'a> {
     }
 
     fn mark_crate_as_completed(&self, ex: &Experiment, krate: &Crate) -> Fallible<usize> {
+        if ex.status == Status::Verifying {
+            return self.mark_crate_as_verified(ex, krate);
+        }
+
         self.db.execute(
             "UPDATE experiment_crates SET status = ?1 WHERE experiment = ?2 AND crate = ?3 \
-             AND ( (SELECT COUNT(*) FROM results WHERE experiment = ?2 AND crate = ?3) > 1 )",
-            &[&Status::Completed.to_string(), &ex.name, &krate.id()],
+             AND (SELECT COUNT(*) FROM results WHERE experiment = ?2 AND crate = ?3) >= ?4",
+            &[
+                &Status::Completed.to_string(),
+                &ex.name,
+                &krate.id(),
+                &(ex.built_toolchains().len() as u32),
+            ],
         )
     }
 
+    /// While verifying, a crate is queued again after each complete re-run, until it was run
+    /// `verify_runs` more times.
+    fn mark_crate_as_verified(&self, ex: &Experiment, krate: &Crate) -> Fallible<usize> {
+        let results: u32 = self
+            .db
+            .get_row(
+                "SELECT COUNT(*) AS count FROM results WHERE experiment = ?1 AND crate = ?2;",
+                &[&ex.name, &krate.id()],
+                |r| r.get("count"),
+            )?
+            .unwrap_or(0);
+        let toolchains = ex.built_toolchains().len() as u32;
+
+        if results >= toolchains * (ex.verify_runs + 1) {
+            self.db.execute(
+                "UPDATE experiment_crates SET status = ?1 WHERE experiment = ?2 AND crate = ?3;",
+                &[&Status::Completed.to_string(), &ex.name, &krate.id()],
+            )
+        } else if results % toolchains == 0 {
+            self.db.execute(
+                "UPDATE experiment_crates SET status = ?1, assigned_to = NULL \
+                 WHERE experiment = ?2 AND crate = ?3;",
+                &[&Status::Queued.to_string(), &ex.name, &krate.id()],
+            )
+        } else {
+            Ok(0)
+        }
+    }
+
     fn store_result(
         &self,
         ex: &Experiment,
         krate: &Crate,
         toolchain: &Toolchain,
+        compiler: Option<&str>,
         res: &TestResult,
         log: &[u8],
         desired_encoding_type: EncodingType,
     ) -> Fallible<()> {
         let encoded_log = EncodedLog::from_plain_slice(log, desired_encoding_type)?;
         self.insert_into_results(ex, krate, toolchain, res, encoded_log)?;
+
+        // Re-runs done while verifying are not cached, as they only exist to detect spurious
+        // results, and neither are the results of crates that were not actually built or built
+        // by an unknown compiler.
+        let cacheable = ex.status != Status::Verifying
+            && is_cacheable(krate, toolchain)
+            && !matches!(res, TestResult::Skipped | TestResult::Error);
+        if let Some(compiler) = compiler.filter(|_| cacheable) {
+            // The toolchain name includes its rustflags
+            self.db.execute(
+                "INSERT INTO results_cache \
+                 (crate, toolchain, compiler, mode, cap_lints, features, profile, lockfile, \
+                 queries, experiment, created_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11);",
+                &[
+                    &krate.id(),
+                    &toolchain.to_string(),
+                    &compiler,
+                    &ex.mode.to_str(),
+                    &ex.cap_lints.to_str(),
+                    &ex.features.to_string(),
+                    &ex.profile.to_string(),
+                    &ex.lockfile.to_str(),
+                    &serde_json::to_string(&ex.queries)?,
+                    &ex.name,
+                    &Utc::now(),
+                ],
+            )?;
+        }
+
         Ok(())
     }
 
@@ -89,9 +251,20 @@ impl<'a> DatabaseDB<'a> {
         res: &TestResult,
         log: EncodedLog,
     ) -> Fallible<usize> {
+        // Results stored while verifying the experiment are re-runs of an existing result
+        let retry = if ex.status == Status::Verifying {
+            "(SELECT COALESCE(MAX(retry) + 1, 0) FROM results \
+             WHERE experiment = ?1 AND crate = ?2 AND toolchain = ?3)"
+        } else {
+            "0"
+        };
+
         self.db.execute(
-            "INSERT INTO results (experiment, crate, toolchain, result, log, encoding) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+            &format!(
+                "INSERT INTO results (experiment, crate, toolchain, result, log, encoding, retry) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, {});",
+                retry
+            ),
             &[
                 &ex.name,
                 &krate.id(),
@@ -102,6 +275,157 @@ impl<'a> DatabaseDB<'a> {
             ],
         )
     }
+
+    fn store_extras(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        extras: &ResultExtras,
+    ) -> Fallible<()> {
+        let (krate, toolchain) = (krate.id(), toolchain.to_string());
+        // Re-runs done while verifying only need the data compared between the runs
+        let verifying = ex.status == Status::Verifying;
+        let retry = current_retry(ex);
+
+        self.db.transaction(|t| {
+            if !verifying {
+                for (test, outcome) in &extras.tests {
+                    t.execute(
+                        "INSERT INTO test_outcomes (experiment, crate, toolchain, test, outcome) \
+                         VALUES (?1, ?2, ?3, ?4, ?5);",
+                        &[&ex.name, &krate, &toolchain, test, &outcome.to_str()],
+                    )?;
+                }
+                if let Some(ice) = &extras.ice {
+                    t.execute(
+                        "INSERT INTO ices \
+                         (experiment, crate, toolchain, message, location, query_stack) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+                        &[
+                            &ex.name,
+                            &krate,
+                            &toolchain,
+                            &ice.message,
+                            &ice.location,
+                            &ice.query_stack.join("\n"),
+                        ],
+                    )?;
+                }
+                if let Some(lockfile) = &extras.lockfile {
+                    t.execute(
+                        "INSERT INTO lockfiles (experiment, crate, toolchain, policy, content) \
+                         VALUES (?1, ?2, ?3, ?4, ?5);",
+                        &[
+                            &ex.name,
+                            &krate,
+                            &toolchain,
+                            &lockfile.policy.to_str(),
+                            &lockfile.content,
+                        ],
+                    )?;
+                }
+                for (query, result) in &extras.query_results {
+                    t.execute(
+                        "INSERT INTO query_results \
+                         (experiment, crate, toolchain, query, matches, lines) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6);",
+                        &[
+                            &ex.name,
+                            &krate,
+                            &toolchain,
+                            query,
+                            &result.matches,
+                            &serde_json::to_string(&result.lines)?,
+                        ],
+                    )?;
+                }
+                for feature in &extras.unstable_features {
+                    t.execute(
+                        "INSERT INTO unstable_features (experiment, crate, toolchain, feature) \
+                         VALUES (?1, ?2, ?3, ?4);",
+                        &[&ex.name, &krate, &toolchain, feature],
+                    )?;
+                }
+            }
+
+            if let Some(compile_time) = &extras.compile_time {
+                t.execute(
+                    &format!(
+                        "INSERT INTO compile_times (experiment, crate, toolchain, samples, \
+                         wall_time_ms, cpu_time_ms, peak_rss_kb, retry) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, {});",
+                        retry
+                    ),
+                    &[
+                        &ex.name,
+                        &krate,
+                        &toolchain,
+                        &compile_time.samples,
+                        &(compile_time.wall_time_ms as i64),
+                        &compile_time.cpu_time_ms.map(|time| time as i64),
+                        &compile_time.peak_rss_kb.map(|rss| rss as i64),
+                    ],
+                )?;
+            }
+            for warning in &extras.warnings {
+                t.execute(
+                    &format!(
+                        "INSERT INTO warnings (experiment, crate, toolchain, lint, span, retry) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, {});",
+                        retry
+                    ),
+                    &[&ex.name, &krate, &toolchain, &warning.lint, &warning.span],
+                )?;
+            }
+            for lint in &extras.future_incompat {
+                t.execute(
+                    &format!(
+                        "INSERT INTO future_incompat \
+                         (experiment, crate, toolchain, lint, dependency, retry) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, {});",
+                        retry
+                    ),
+                    &[
+                        &ex.name,
+                        &krate,
+                        &toolchain,
+                        &lint.lint,
+                        &lint.dependency.as_ref().map(Crate::id).unwrap_or_default(),
+                    ],
+                )?;
+            }
+            if let Some(diff) = &extras.rustfmt_diff {
+                t.execute(
+                    &format!(
+                        "INSERT INTO rustfmt_diffs \
+                         (experiment, crate, toolchain, files_changed, hunks, retry) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, {});",
+                        retry
+                    ),
+                    &[
+                        &ex.name,
+                        &krate,
+                        &toolchain,
+                        &diff.files_changed,
+                        &diff.hunks,
+                    ],
+                )?;
+            }
+            for (features, result) in &extras.feature_results {
+                t.execute(
+                    &format!(
+                        "INSERT INTO feature_results \
+                         (experiment, crate, toolchain, features, result, retry) \
+                         VALUES (?1, ?2, ?3, ?4, ?5, {});",
+                        retry
+                    ),
+                    &[&ex.name, &krate, &toolchain, features, &result.to_string()],
+                )?;
+            }
+            Ok(())
+        })
+    }
 }
 
 impl<'a> ReadResults for DatabaseDB<'a> {
@@ -113,9 +437,13 @@ impl<'a> ReadResults for DatabaseDB<'a> {
     ) -> Fallible<Option<EncodedLog>> {
         self.db.get_row(
             "SELECT log, encoding FROM results \
-             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = 0 \
              LIMIT 1;",
-            &[&ex.name, &toolchain.to_string(), &krate.id()],
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
             |row| {
                 let log: Vec<u8> = row.get("log");
                 let encoding: String = row.get("encoding");
@@ -139,9 +467,13 @@ impl<'a> ReadResults for DatabaseDB<'a> {
             .db
             .query(
                 "SELECT result FROM results \
-                 WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 \
+                 WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = 0 \
                  LIMIT 1;",
-                &[&ex.name, &toolchain.to_string(), &krate.id()],
+                &[
+                    &ex.results_experiment(toolchain),
+                    &toolchain.to_string(),
+                    &krate.id(),
+                ],
                 |row| row.get("result"),
             )?
             .pop();
@@ -152,6 +484,282 @@ impl<'a> ReadResults for DatabaseDB<'a> {
             Ok(None)
         }
     }
+
+    fn load_reused_from(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<String>> {
+        Ok(self
+            .db
+            .get_row(
+                "SELECT reused_from FROM results \
+                 WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = 0 \
+                 LIMIT 1;",
+                &[
+                    &ex.results_experiment(toolchain),
+                    &toolchain.to_string(),
+                    &krate.id(),
+                ],
+                |row| row.get("reused_from"),
+            )?
+            .and_then(|reused_from: Option<String>| reused_from))
+    }
+
+    fn load_retried_test_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<TestResult>> {
+        let results: Vec<String> = self.db.query(
+            "SELECT result FROM results \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry > 0 \
+             ORDER BY retry;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
+            |row| row.get("result"),
+        )?;
+
+        results.into_iter().map(|res| Ok(res.parse()?)).collect()
+    }
+
+    fn load_ice(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Ice>> {
+        self.db.get_row(
+            "SELECT message, location, query_stack FROM ices \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
+            |row| {
+                let query_stack: String = row.get("query_stack");
+                Ice {
+                    message: row.get("message"),
+                    location: row.get("location"),
+                    query_stack: query_stack.lines().map(|l| l.to_string()).collect(),
+                }
+            },
+        )
+    }
+
+    fn load_compile_time(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Option<CompileTime>> {
+        self.db.get_row(
+            "SELECT samples, wall_time_ms, cpu_time_ms, peak_rss_kb FROM compile_times \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = ?4;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+                &retry,
+            ],
+            |row| {
+                let wall_time_ms: i64 = row.get("wall_time_ms");
+                let cpu_time_ms: Option<i64> = row.get("cpu_time_ms");
+                let peak_rss_kb: Option<i64> = row.get("peak_rss_kb");
+                CompileTime {
+                    samples: row.get("samples"),
+                    wall_time_ms: wall_time_ms as u64,
+                    cpu_time_ms: cpu_time_ms.map(|time| time as u64),
+                    peak_rss_kb: peak_rss_kb.map(|rss| rss as u64),
+                }
+            },
+        )
+    }
+    fn load_warnings(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Warnings> {
+        let warnings = self.db.query(
+            "SELECT lint, span FROM warnings \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = ?4;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+                &retry,
+            ],
+            |row| Warning {
+                lint: row.get("lint"),
+                span: row.get("span"),
+            },
+        )?;
+        Ok(warnings.into_iter().collect())
+    }
+    fn load_future_incompat(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<FutureIncompats> {
+        let rows: Vec<(String, String)> = self.db.query(
+            "SELECT lint, dependency FROM future_incompat \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = ?4;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+                &retry,
+            ],
+            |row| (row.get("lint"), row.get("dependency")),
+        )?;
+
+        // Lints triggered by the crate itself are stored without a dependency
+        rows.into_iter()
+            .map(|(lint, dependency)| {
+                Ok(FutureIncompat {
+                    lint,
+                    dependency: if dependency.is_empty() {
+                        None
+                    } else {
+                        Some(dependency.parse()?)
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn load_rustfmt_diff(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Option<RustfmtDiff>> {
+        self.db.get_row(
+            "SELECT files_changed, hunks FROM rustfmt_diffs \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = ?4;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+                &retry,
+            ],
+            |row| RustfmtDiff {
+                files_changed: row.get("files_changed"),
+                hunks: row.get("hunks"),
+            },
+        )
+    }
+
+    fn load_feature_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<FeatureResults> {
+        let rows: Vec<(String, String)> = self.db.query(
+            "SELECT features, result FROM feature_results \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3 AND retry = ?4;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+                &retry,
+            ],
+            |row| (row.get("features"), row.get("result")),
+        )?;
+
+        rows.into_iter()
+            .map(|(features, result)| Ok((features, result.parse()?)))
+            .collect()
+    }
+
+    fn load_lockfile(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Lockfile>> {
+        let row: Option<(String, String)> = self.db.get_row(
+            "SELECT policy, content FROM lockfiles \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
+            |row| (row.get("policy"), row.get("content")),
+        )?;
+
+        row.map(|(policy, content)| {
+            Ok(Lockfile {
+                policy: policy.parse()?,
+                content,
+            })
+        })
+        .transpose()
+    }
+
+    fn load_query_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<QueryResults> {
+        let rows: Vec<(String, u32, String)> = self.db.query(
+            "SELECT query, matches, lines FROM query_results \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
+            |row| (row.get("query"), row.get("matches"), row.get("lines")),
+        )?;
+
+        rows.into_iter()
+            .map(|(query, matches, lines)| {
+                Ok((
+                    query,
+                    QueryResult {
+                        matches,
+                        lines: serde_json::from_str(&lines)?,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn load_unstable_features(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<UnstableFeatures> {
+        let features: Vec<String> = self.db.query(
+            "SELECT feature FROM unstable_features \
+             WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+            &[
+                &ex.results_experiment(toolchain),
+                &toolchain.to_string(),
+                &krate.id(),
+            ],
+            |row| row.get("feature"),
+        )?;
+        Ok(features.into_iter().collect())
+    }
 }
 
 impl<'a> WriteResults for DatabaseDB<'a> {
@@ -164,6 +772,84 @@ impl<'a> WriteResults for DatabaseDB<'a> {
         self.load_test_result(ex, toolchain, krate)
     }
 
+    fn reuse_cached_result(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        compiler: &str,
+        krate: &Crate,
+        config: &Config,
+    ) -> Fallible<Option<TestResult>> {
+        let max_age_days = config.server.results_cache.max_age_days;
+        if max_age_days == 0 || ex.status == Status::Verifying || !is_cacheable(krate, toolchain) {
+            return Ok(None);
+        }
+        // Compile times measured in different experiments are not comparable
+        if ex.mode == Mode::CompileTime {
+            return Ok(None);
+        }
+
+        // The newest result is reused when several experiments built the crate
+        let source: Option<String> = self.db.get_row(
+            "SELECT experiment FROM results_cache \
+             WHERE crate = ?1 AND toolchain = ?2 AND compiler = ?3 AND mode = ?4 \
+             AND cap_lints = ?5 AND features = ?6 AND profile = ?7 AND lockfile = ?8 \
+             AND queries = ?9 AND experiment != ?10 AND created_at >= ?11 \
+             ORDER BY created_at DESC LIMIT 1;",
+            &[
+                &krate.id(),
+                &toolchain.to_string(),
+                &compiler,
+                &ex.mode.to_str(),
+                &ex.cap_lints.to_str(),
+                &ex.features.to_string(),
+                &ex.profile.to_string(),
+                &ex.lockfile.to_str(),
+                &serde_json::to_string(&ex.queries)?,
+                &ex.name,
+                &(Utc::now() - Duration::days(i64::from(max_age_days))),
+            ],
+            |row| row.get("experiment"),
+        )?;
+        let source = match source {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+
+        // The result could be missing if it was deleted from the experiment that produced it
+        let copied = self.db.execute(
+            "INSERT INTO results \
+             (experiment, crate, toolchain, result, log, encoding, reused_from) \
+             SELECT ?1, crate, toolchain, result, log, encoding, experiment FROM results \
+             WHERE experiment = ?2 AND crate = ?3 AND toolchain = ?4 AND retry = 0;",
+            &[&ex.name, &source, &krate.id(), &toolchain.to_string()],
+        )?;
+        if copied == 0 {
+            return Ok(None);
+        }
+        // Only the data of the original run is reused, not the one of the re-runs
+        for table in EXTRAS_TABLES {
+            self.db.execute(
+                &format!(
+                    "INSERT INTO {table} (experiment, crate, toolchain, {columns}) \
+                     SELECT ?1, crate, toolchain, {columns} FROM {table} \
+                     WHERE experiment = ?2 AND crate = ?3 AND toolchain = ?4{retry};",
+                    table = table.name,
+                    columns = table.columns,
+                    retry = if table.retried { " AND retry = 0" } else { "" },
+                ),
+                &[&ex.name, &source, &krate.id(), &toolchain.to_string()],
+            )?;
+        }
+
+        info!(
+            "reused the result of {} on {} from experiment {}",
+            krate, toolchain, source
+        );
+        self.mark_crate_as_completed(ex, krate)?;
+        self.load_test_result(ex, toolchain, krate)
+    }
+
     fn update_crate_version(&self, ex: &Experiment, old: &Crate, new: &Crate) -> Fallible<()> {
         self.db.execute(
             "UPDATE experiment_crates SET crate = ?1 WHERE experiment = ?2 AND crate = ?3;",
@@ -172,10 +858,26 @@ impl<'a> WriteResults for DatabaseDB<'a> {
         Ok(())
     }
 
+    fn record_extras<F>(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        update: F,
+    ) -> Fallible<()>
+    where
+        F: FnOnce(&mut ResultExtras),
+    {
+        let mut extras = ResultExtras::default();
+        update(&mut extras);
+        self.store_extras(ex, toolchain, krate, &extras)
+    }
+
     fn record_result<F>(
         &self,
         ex: &Experiment,
         toolchain: &Toolchain,
+        compiler: Option<&str>,
         krate: &Crate,
         existing_logs: Option<LogStorage>,
         config: &Config,
@@ -192,6 +894,7 @@ impl<'a> WriteResults for DatabaseDB<'a> {
             ex,
             krate,
             toolchain,
+            compiler,
             &result,
             output.as_bytes(),
             encoding_type,
@@ -204,6 +907,12 @@ impl<'a> DeleteResults for DatabaseDB<'a> {
     fn delete_all_results(&self, ex: &Experiment) -> Fallible<()> {
         self.db
             .execute("DELETE FROM results WHERE experiment = ?1;", &[&ex.name])?;
+        for table in EXTRAS_TABLES {
+            self.db.execute(
+                &format!("DELETE FROM {} WHERE experiment = ?1;", table.name),
+                &[&ex.name],
+            )?;
+        }
         Ok(())
     }
 
@@ -212,6 +921,15 @@ impl<'a> DeleteResults for DatabaseDB<'a> {
             "DELETE FROM results WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
             &[&ex.name, &tc.to_string(), &krate.id()],
         )?;
+        for table in EXTRAS_TABLES {
+            self.db.execute(
+                &format!(
+                    "DELETE FROM {} WHERE experiment = ?1 AND toolchain = ?2 AND crate = ?3;",
+                    table.name
+                ),
+                &[&ex.name, &tc.to_string(), &krate.id()],
+            )?;
+        }
         Ok(())
     }
 }
@@ -222,14 +940,16 @@ mod tests {
     use crate::actions::{Action, ActionsCtx, CreateExperiment};
     use crate::config::Config;
     use crate::crates::{Crate, RegistryCrate};
-    use crate::db::Database;
-    use crate::experiments::Experiment;
+    use crate::db::{Database, QueryUtils};
+    use crate::experiments::{Experiment, FeatureSet, LockfilePolicy, Status};
     use crate::prelude::*;
     use crate::results::{
-        DeleteResults, EncodedLog, EncodingType, FailureReason, ReadResults, TestResult,
-        WriteResults,
+        CompileTime, DeleteResults, EncodedLog, EncodingType, FailureReason, FeatureResults,
+        FutureIncompat, FutureIncompats, Ice, Lockfile, MatchedLine, QueryResult, QueryResults,
+        ReadResults, ResultExtras, RustfmtDiff, TestOutcome, TestResult, UnstableFeatures, Warning,
+        Warnings, WriteResults,
     };
-    use crate::toolchain::{MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
+    use crate::toolchain::{Toolchain, MAIN_TOOLCHAIN, TEST_TOOLCHAIN};
     use base64;
     use std::collections::BTreeSet;
 
@@ -310,6 +1030,7 @@ mod tests {
             .record_result(
                 &ex,
                 &MAIN_TOOLCHAIN,
+                None,
                 &krate,
                 None,
                 &config,
@@ -358,6 +1079,7 @@ mod tests {
             .record_result(
                 &ex,
                 &TEST_TOOLCHAIN,
+                None,
                 &krate,
                 None,
                 &config,
@@ -423,8 +1145,18 @@ mod tests {
                     results: vec![TaskResult {
                         krate: updated.clone(),
                         toolchain: MAIN_TOOLCHAIN.clone(),
+                        compiler: None,
                         result: TestResult::TestPass,
                         log: base64::encode("foo"),
+                        extras: ResultExtras {
+                            tests: vec![
+                                ("lib: tests::foo".to_string(), TestOutcome::Passed),
+                                ("lib: tests::bar".to_string(), TestOutcome::Ignored),
+                            ]
+                            .into_iter()
+                            .collect(),
+                            ..ResultExtras::default()
+                        },
                     }],
                     version: Some((krate.clone(), updated.clone())),
                 },
@@ -442,6 +1174,21 @@ mod tests {
                 .unwrap(),
             Some(TestResult::TestPass)
         );
+        assert_eq!(
+            db.query(
+                "SELECT test, outcome FROM test_outcomes WHERE crate = ?1 ORDER BY test;",
+                &[&updated.id()],
+                |row| (
+                    row.get::<_, String>("test"),
+                    row.get::<_, String>("outcome")
+                ),
+            )
+            .unwrap(),
+            vec![
+                ("lib: tests::bar".to_string(), "ignored".to_string()),
+                ("lib: tests::foo".to_string(), "passed".to_string()),
+            ]
+        );
 
         assert_eq!(
             results.load_log(&ex, &MAIN_TOOLCHAIN, &krate).unwrap(),
@@ -454,4 +1201,663 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn test_store_ice() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let ice = Ice {
+            message: "no entry found".into(),
+            location: Some("src/librustc/hir/map/mod.rs:42:3".into()),
+            query_stack: vec![
+                "#0 [type_of] processing `foo`".into(),
+                "#1 [typeck_tables_of] processing `bar`".into(),
+            ],
+        };
+
+        results
+            .store(
+                &ex,
+                &ProgressData {
+                    results: vec![TaskResult {
+                        krate: krate.clone(),
+                        toolchain: MAIN_TOOLCHAIN.clone(),
+                        compiler: None,
+                        result: TestResult::BuildFail(FailureReason::ICE),
+                        log: base64::encode("foo"),
+                        extras: ResultExtras {
+                            ice: Some(ice.clone()),
+                            ..ResultExtras::default()
+                        },
+                    }],
+                    version: None,
+                },
+                EncodingType::Plain,
+            )
+            .unwrap();
+
+        assert_eq!(
+            results.load_ice(&ex, &MAIN_TOOLCHAIN, &krate).unwrap(),
+            Some(ice)
+        );
+        assert_eq!(
+            results.load_ice(&ex, &TEST_TOOLCHAIN, &krate).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_warnings() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let warnings = vec![
+            Warning {
+                lint: "unused_variables".into(),
+                span: "src/lib.rs:12:9".into(),
+            },
+            Warning {
+                lint: "dead_code".into(),
+                span: "src/lib.rs:42:1".into(),
+            },
+        ]
+        .into_iter()
+        .collect::<Warnings>();
+
+        results
+            .store(
+                &ex,
+                &ProgressData {
+                    results: vec![TaskResult {
+                        krate: krate.clone(),
+                        toolchain: MAIN_TOOLCHAIN.clone(),
+                        compiler: None,
+                        result: TestResult::TestPass,
+                        log: base64::encode("foo"),
+                        extras: ResultExtras {
+                            warnings: warnings.clone(),
+                            ..ResultExtras::default()
+                        },
+                    }],
+                    version: None,
+                },
+                EncodingType::Plain,
+            )
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_warnings(&ex, &MAIN_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            warnings
+        );
+        assert!(results
+            .load_warnings(&ex, &TEST_TOOLCHAIN, &krate, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_future_incompat() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let future_incompat = vec![
+            FutureIncompat {
+                lint: "unaligned_references".into(),
+                dependency: None,
+            },
+            FutureIncompat {
+                lint: "semicolon_in_expressions_from_macros".into(),
+                dependency: Some(Crate::Registry(RegistryCrate {
+                    name: "nom".into(),
+                    version: "4.2.3".into(),
+                })),
+            },
+        ]
+        .into_iter()
+        .collect::<FutureIncompats>();
+
+        results
+            .store(
+                &ex,
+                &ProgressData {
+                    results: vec![TaskResult {
+                        krate: krate.clone(),
+                        toolchain: MAIN_TOOLCHAIN.clone(),
+                        compiler: None,
+                        result: TestResult::TestPass,
+                        log: base64::encode("foo"),
+                        extras: ResultExtras {
+                            future_incompat: future_incompat.clone(),
+                            ..ResultExtras::default()
+                        },
+                    }],
+                    version: None,
+                },
+                EncodingType::Plain,
+            )
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_future_incompat(&ex, &MAIN_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            future_incompat
+        );
+        assert!(results
+            .load_future_incompat(&ex, &TEST_TOOLCHAIN, &krate, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_compile_time() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let compile_time = CompileTime {
+            samples: 3,
+            wall_time_ms: 12_345,
+            cpu_time_ms: Some(23_456),
+            peak_rss_kb: None,
+        };
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.compile_time = Some(compile_time.clone())
+            })
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_compile_time(&ex, &MAIN_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            Some(compile_time)
+        );
+        assert_eq!(
+            results
+                .load_compile_time(&ex, &TEST_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_rustfmt_diff() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let diff = RustfmtDiff {
+            files_changed: 1,
+            hunks: "Diff in src/lib.rs at line 1:\n-fn foo( ) {}\n+fn foo() {}".into(),
+        };
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.rustfmt_diff = Some(diff.clone())
+            })
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_rustfmt_diff(&ex, &MAIN_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            Some(diff)
+        );
+        assert_eq!(
+            results
+                .load_rustfmt_diff(&ex, &TEST_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_feature_results() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let feature_results = vec![
+            ("--no-default-features".to_string(), TestResult::TestPass),
+            (
+                "--no-default-features --features spin_no_std".to_string(),
+                TestResult::BuildFail(FailureReason::Unknown),
+            ),
+        ]
+        .into_iter()
+        .collect::<FeatureResults>();
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.feature_results = feature_results.clone()
+            })
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_feature_results(&ex, &MAIN_TOOLCHAIN, &krate, 0)
+                .unwrap(),
+            feature_results
+        );
+        assert!(results
+            .load_feature_results(&ex, &TEST_TOOLCHAIN, &krate, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_lockfile() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let lockfile = Lockfile {
+            policy: LockfilePolicy::MinimalVersions,
+            content: "[[package]]\nname = \"lazy_static\"\nversion = \"1.0.0\"\n".into(),
+        };
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.lockfile = Some(lockfile.clone())
+            })
+            .unwrap();
+
+        assert_eq!(
+            results.load_lockfile(&ex, &MAIN_TOOLCHAIN, &krate).unwrap(),
+            Some(lockfile)
+        );
+        assert_eq!(
+            results.load_lockfile(&ex, &TEST_TOOLCHAIN, &krate).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_query_results() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let mut query_results = QueryResults::new();
+        query_results.insert(
+            "unsafe".into(),
+            QueryResult {
+                matches: 2,
+                lines: vec![MatchedLine {
+                    file: "src/lib.rs".into(),
+                    line: 42,
+                    content: "unsafe { unsafe_fn() }".into(),
+                }],
+            },
+        );
+        query_results.insert("transmute".into(), QueryResult::default());
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.query_results = query_results.clone()
+            })
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_query_results(&ex, &MAIN_TOOLCHAIN, &krate)
+                .unwrap(),
+            query_results
+        );
+        assert!(results
+            .load_query_results(&ex, &TEST_TOOLCHAIN, &krate)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_unstable_features() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("dummy").apply(&ctx).unwrap();
+        let ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let features: UnstableFeatures = btreeset!["never_type".into(), "specialization".into()];
+
+        results
+            .record_extras(&ex, &MAIN_TOOLCHAIN, &krate, |extras| {
+                extras.unstable_features = features.clone()
+            })
+            .unwrap();
+
+        assert_eq!(
+            results
+                .load_unstable_features(&ex, &MAIN_TOOLCHAIN, &krate)
+                .unwrap(),
+            features
+        );
+        assert!(results
+            .load_unstable_features(&ex, &TEST_TOOLCHAIN, &krate)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_store_retries() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment {
+            verify_runs: 1,
+            ..CreateExperiment::dummy("dummy")
+        }
+        .apply(&ctx)
+        .unwrap();
+        let mut ex = Experiment::get(&db, "dummy").unwrap().unwrap();
+
+        let krate = Crate::Local("build-pass".into());
+        let store = |ex: &Experiment, toolchain: &Toolchain, result: TestResult| {
+            results
+                .store(
+                    ex,
+                    &ProgressData {
+                        results: vec![TaskResult {
+                            krate: krate.clone(),
+                            toolchain: toolchain.clone(),
+                            compiler: None,
+                            result,
+                            log: base64::encode("foo"),
+                            extras: ResultExtras::default(),
+                        }],
+                        version: None,
+                    },
+                    EncodingType::Plain,
+                )
+                .unwrap();
+        };
+        let crate_status = || -> String {
+            db.get_row(
+                "SELECT status FROM experiment_crates WHERE experiment = ?1 AND crate = ?2;",
+                &[&"dummy", &krate.id()],
+                |row| row.get("status"),
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        // Store the original results
+        store(&ex, &MAIN_TOOLCHAIN, TestResult::TestPass);
+        store(
+            &ex,
+            &TEST_TOOLCHAIN,
+            TestResult::BuildFail(FailureReason::Unknown),
+        );
+        assert_eq!(crate_status(), Status::Completed.to_str());
+
+        // The regressed crate is queued again and completed after the verification run
+        assert_eq!(ex.requeue_for_verification(&db, &config).unwrap(), 1);
+        assert_eq!(crate_status(), Status::Queued.to_str());
+        ex.status = Status::Verifying;
+        store(&ex, &MAIN_TOOLCHAIN, TestResult::TestPass);
+        assert_eq!(crate_status(), Status::Queued.to_str());
+        store(&ex, &TEST_TOOLCHAIN, TestResult::TestPass);
+        assert_eq!(crate_status(), Status::Completed.to_str());
+        assert_eq!(ex.requeue_for_verification(&db, &config).unwrap(), 0);
+
+        // The data compared between the runs is recorded for the re-runs too
+        let warnings = vec![Warning {
+            lint: "dead_code".into(),
+            span: "src/lib.rs:1:1".into(),
+        }]
+        .into_iter()
+        .collect::<Warnings>();
+        results
+            .record_extras(&ex, &TEST_TOOLCHAIN, &krate, |extras| {
+                extras.warnings = warnings.clone()
+            })
+            .unwrap();
+        assert!(results
+            .load_warnings(&ex, &TEST_TOOLCHAIN, &krate, 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            results
+                .load_warnings(&ex, &TEST_TOOLCHAIN, &krate, 1)
+                .unwrap(),
+            warnings
+        );
+
+        assert_eq!(
+            results
+                .load_test_result(&ex, &TEST_TOOLCHAIN, &krate)
+                .unwrap(),
+            Some(TestResult::BuildFail(FailureReason::Unknown))
+        );
+        assert_eq!(
+            results
+                .load_retried_test_results(&ex, &TEST_TOOLCHAIN, &krate)
+                .unwrap(),
+            vec![TestResult::TestPass]
+        );
+        assert_eq!(ex.raw_progress(&db).unwrap().0, 2);
+    }
+
+    #[test]
+    fn test_reuse_cached_result() {
+        let db = Database::temp().unwrap();
+        let results = DatabaseDB::new(&db);
+        let mut config = Config::default();
+        let ctx = ActionsCtx::new(&db, &config);
+
+        crate::crates::lists::setup_test_lists(&db, &config).unwrap();
+
+        CreateExperiment::dummy("first").apply(&ctx).unwrap();
+        CreateExperiment::dummy("second").apply(&ctx).unwrap();
+        CreateExperiment {
+            features: FeatureSet::AllFeatures,
+            ..CreateExperiment::dummy("all-features")
+        }
+        .apply(&ctx)
+        .unwrap();
+        let first = Experiment::get(&db, "first").unwrap().unwrap();
+        let second = Experiment::get(&db, "second").unwrap().unwrap();
+        let all_features = Experiment::get(&db, "all-features").unwrap().unwrap();
+
+        let krate = Crate::Registry(RegistryCrate {
+            name: "lazy_static".into(),
+            version: "1".into(),
+        });
+        let nightly: Toolchain = "nightly".parse().unwrap();
+        for (tc, compiler) in &[(&*MAIN_TOOLCHAIN, None), (&nightly, Some("abc"))] {
+            results
+                .record_result(
+                    &first,
+                    tc,
+                    *compiler,
+                    &krate,
+                    None,
+                    &config,
+                    EncodingType::Plain,
+                    || {
+                        info!("cached log");
+                        Ok(TestResult::TestPass)
+                    },
+                )
+                .unwrap();
+        }
+        let lockfile = Lockfile {
+            policy: LockfilePolicy::AsPrepared,
+            content: "[[package]]\nname = \"lazy_static\"\nversion = \"1.0.0\"\n".into(),
+        };
+        results
+            .record_extras(&first, &nightly, &krate, |extras| {
+                extras.lockfile = Some(lockfile.clone())
+            })
+            .unwrap();
+
+        // Results built with other settings are cached alongside the first ones
+        results
+            .record_result(
+                &all_features,
+                &nightly,
+                Some("abc"),
+                &krate,
+                None,
+                &config,
+                EncodingType::Plain,
+                || Ok(TestResult::BuildFail(FailureReason::Unknown)),
+            )
+            .unwrap();
+
+        // The cache is disabled by default
+        assert!(results
+            .reuse_cached_result(&second, &nightly, "abc", &krate, &config)
+            .unwrap()
+            .is_none());
+
+        // Only results with the same toolchain are reused
+        config.server.results_cache.max_age_days = 7;
+        let other_nightly: Toolchain = "nightly+rustflags=-Zfoo".parse().unwrap();
+        assert!(results
+            .reuse_cached_result(&second, &other_nightly, "abc", &krate, &config)
+            .unwrap()
+            .is_none());
+
+        // The channel resolves to another compiler than the one the result was built with
+        assert!(results
+            .reuse_cached_result(&second, &nightly, "def", &krate, &config)
+            .unwrap()
+            .is_none());
+
+        // Results built by an unknown compiler are not cached
+        assert!(results
+            .reuse_cached_result(&second, &MAIN_TOOLCHAIN, "abc", &krate, &config)
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            results
+                .reuse_cached_result(&second, &nightly, "abc", &krate, &config)
+                .unwrap(),
+            Some(TestResult::TestPass)
+        );
+        assert_eq!(
+            results.get_result(&second, &nightly, &krate).unwrap(),
+            Some(TestResult::TestPass)
+        );
+        assert_eq!(
+            results.load_log(&second, &nightly, &krate).unwrap(),
+            results.load_log(&first, &nightly, &krate).unwrap()
+        );
+        assert_eq!(
+            results.load_reused_from(&second, &nightly, &krate).unwrap(),
+            Some("first".to_string())
+        );
+
+        // The data recorded besides the result is copied along with it
+        assert_eq!(
+            results.load_lockfile(&second, &nightly, &krate).unwrap(),
+            Some(lockfile)
+        );
+        assert_eq!(
+            results.load_reused_from(&first, &nightly, &krate).unwrap(),
+            None
+        );
+    }
 }
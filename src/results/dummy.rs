@@ -1,7 +1,10 @@
 use crate::crates::Crate;
 use crate::experiments::Experiment;
 use crate::prelude::*;
-use crate::results::{EncodedLog, ReadResults, TestResult};
+use crate::results::{
+    CompileTime, EncodedLog, FeatureResults, FutureIncompats, Ice, Lockfile, QueryResults,
+    ReadResults, RustfmtDiff, TestResult, UnstableFeatures, Warnings,
+};
 use crate::toolchain::Toolchain;
 use std::collections::HashMap;
 
@@ -9,6 +12,16 @@ use std::collections::HashMap;
 struct DummyData {
     logs: HashMap<(Crate, Toolchain), EncodedLog>,
     results: HashMap<(Crate, Toolchain), TestResult>,
+    retried_results: HashMap<(Crate, Toolchain), Vec<TestResult>>,
+    ices: HashMap<(Crate, Toolchain), Ice>,
+    compile_times: HashMap<(Crate, Toolchain), CompileTime>,
+    warnings: HashMap<(Crate, Toolchain), Warnings>,
+    future_incompat: HashMap<(Crate, Toolchain), FutureIncompats>,
+    rustfmt_diffs: HashMap<(Crate, Toolchain), RustfmtDiff>,
+    feature_results: HashMap<(Crate, Toolchain), FeatureResults>,
+    lockfiles: HashMap<(Crate, Toolchain), Lockfile>,
+    query_results: HashMap<(Crate, Toolchain), QueryResults>,
+    unstable_features: HashMap<(Crate, Toolchain), UnstableFeatures>,
 }
 
 #[derive(Default)]
@@ -17,11 +30,11 @@ pub struct DummyDB {
 }
 
 impl DummyDB {
-    fn get_data(&self, ex: &Experiment) -> Fallible<&DummyData> {
+    fn get_data(&self, name: &str) -> Fallible<&DummyData> {
         Ok(self
             .experiments
-            .get(&ex.name)
-            .ok_or_else(|| err_msg(format!("missing experiment {}", ex.name)))?)
+            .get(name)
+            .ok_or_else(|| err_msg(format!("missing experiment {}", name)))?)
     }
 
     pub fn add_dummy_log(&mut self, ex: &Experiment, krate: Crate, tc: Toolchain, log: EncodedLog) {
@@ -45,6 +58,142 @@ impl DummyDB {
             .results
             .insert((krate, tc), res);
     }
+
+    pub fn add_dummy_retried_result(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        res: TestResult,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .retried_results
+            .entry((krate, tc))
+            .or_insert_with(Vec::new)
+            .push(res);
+    }
+
+    pub fn add_dummy_ice(&mut self, ex: &Experiment, krate: Crate, tc: Toolchain, ice: Ice) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .ices
+            .insert((krate, tc), ice);
+    }
+
+    pub fn add_dummy_compile_time(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        compile_time: CompileTime,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .compile_times
+            .insert((krate, tc), compile_time);
+    }
+
+    pub fn add_dummy_warnings(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        warnings: Warnings,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .warnings
+            .insert((krate, tc), warnings);
+    }
+
+    pub fn add_dummy_future_incompat(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        future_incompat: FutureIncompats,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .future_incompat
+            .insert((krate, tc), future_incompat);
+    }
+
+    pub fn add_dummy_rustfmt_diff(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        diff: RustfmtDiff,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .rustfmt_diffs
+            .insert((krate, tc), diff);
+    }
+
+    pub fn add_dummy_feature_results(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        results: FeatureResults,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .feature_results
+            .insert((krate, tc), results);
+    }
+
+    pub fn add_dummy_lockfile(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        lockfile: Lockfile,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .lockfiles
+            .insert((krate, tc), lockfile);
+    }
+
+    pub fn add_dummy_query_results(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        results: QueryResults,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .query_results
+            .insert((krate, tc), results);
+    }
+
+    pub fn add_dummy_unstable_features(
+        &mut self,
+        ex: &Experiment,
+        krate: Crate,
+        tc: Toolchain,
+        features: UnstableFeatures,
+    ) {
+        self.experiments
+            .entry(ex.name.to_string())
+            .or_insert_with(DummyData::default)
+            .unstable_features
+            .insert((krate, tc), features);
+    }
 }
 
 impl ReadResults for DummyDB {
@@ -55,7 +204,7 @@ impl ReadResults for DummyDB {
         krate: &Crate,
     ) -> Fallible<Option<EncodedLog>> {
         Ok(self
-            .get_data(ex)?
+            .get_data(ex.results_experiment(toolchain))?
             .logs
             .get(&(krate.clone(), toolchain.clone()))
             .cloned())
@@ -68,9 +217,160 @@ impl ReadResults for DummyDB {
         krate: &Crate,
     ) -> Fallible<Option<TestResult>> {
         Ok(self
-            .get_data(ex)?
+            .get_data(ex.results_experiment(toolchain))?
             .results
             .get(&(krate.clone(), toolchain.clone()))
             .cloned())
     }
+
+    fn load_reused_from(
+        &self,
+        _ex: &Experiment,
+        _toolchain: &Toolchain,
+        _krate: &Crate,
+    ) -> Fallible<Option<String>> {
+        Ok(None)
+    }
+
+    fn load_retried_test_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<TestResult>> {
+        // Only the results of the re-runs are stored, the rest of their data is the original one
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .retried_results
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_ice(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Ice>> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .ices
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned())
+    }
+
+    fn load_compile_time(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        _retry: u32,
+    ) -> Fallible<Option<CompileTime>> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .compile_times
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned())
+    }
+
+    fn load_warnings(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        _retry: u32,
+    ) -> Fallible<Warnings> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .warnings
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_future_incompat(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        _retry: u32,
+    ) -> Fallible<FutureIncompats> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .future_incompat
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_rustfmt_diff(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        _retry: u32,
+    ) -> Fallible<Option<RustfmtDiff>> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .rustfmt_diffs
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned())
+    }
+
+    fn load_feature_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        _retry: u32,
+    ) -> Fallible<FeatureResults> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .feature_results
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_lockfile(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Lockfile>> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .lockfiles
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned())
+    }
+
+    fn load_query_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<QueryResults> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .query_results
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn load_unstable_features(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<UnstableFeatures> {
+        Ok(self
+            .get_data(ex.results_experiment(toolchain))?
+            .unstable_features
+            .get(&(krate.clone(), toolchain.clone()))
+            .cloned()
+            .unwrap_or_default())
+    }
 }
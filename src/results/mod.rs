@@ -2,8 +2,8 @@ mod db;
 #[cfg(test)]
 mod dummy;
 use crate::config::Config;
-use crate::crates::Crate;
-use crate::experiments::Experiment;
+use crate::crates::{Crate, RegistryCrate};
+use crate::experiments::{Experiment, LockfilePolicy};
 use crate::prelude::*;
 
 pub use crate::results::db::{DatabaseDB, ProgressData};
@@ -14,7 +14,7 @@ use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use rustwide::logging::LogStorage;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::{fmt, io::Read, io::Write, str::FromStr};
 
 pub trait ReadResults {
@@ -30,6 +30,80 @@ pub trait ReadResults {
         toolchain: &Toolchain,
         krate: &Crate,
     ) -> Fallible<Option<TestResult>>;
+    /// The name of the experiment the result was reused from, if it was not built again.
+    fn load_reused_from(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<String>>;
+    fn load_retried_test_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Vec<TestResult>>;
+    fn load_ice(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Ice>>;
+    /// The data compared between the runs of a crate is loaded for a single run: `retry` is 0
+    /// for the original run, and counts the re-runs done while verifying the experiment.
+    fn load_compile_time(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Option<CompileTime>>;
+    fn load_warnings(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Warnings>;
+    fn load_future_incompat(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<FutureIncompats>;
+    fn load_rustfmt_diff(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<Option<RustfmtDiff>>;
+    fn load_feature_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        retry: u32,
+    ) -> Fallible<FeatureResults>;
+    fn load_lockfile(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<Option<Lockfile>>;
+    fn load_query_results(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<QueryResults>;
+    fn load_unstable_features(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+    ) -> Fallible<UnstableFeatures>;
 }
 
 pub trait WriteResults {
@@ -39,11 +113,35 @@ pub trait WriteResults {
         toolchain: &Toolchain,
         krate: &Crate,
     ) -> Fallible<Option<TestResult>>;
+    /// Copy a recent enough result of the same crate and toolchain, built by the same compiler,
+    /// from another experiment, returning it if one was found.
+    fn reuse_cached_result(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        compiler: &str,
+        krate: &Crate,
+        config: &Config,
+    ) -> Fallible<Option<TestResult>>;
     fn update_crate_version(&self, ex: &Experiment, old: &Crate, new: &Crate) -> Fallible<()>;
+    /// Record the data collected while building the crate besides its result, setting it on
+    /// the extras of the crate with `update`.
+    fn record_extras<F>(
+        &self,
+        ex: &Experiment,
+        toolchain: &Toolchain,
+        krate: &Crate,
+        update: F,
+    ) -> Fallible<()>
+    where
+        F: FnOnce(&mut ResultExtras);
+    /// Record the result of the crate, caching it if the compiler the toolchain resolved to is
+    /// known.
     fn record_result<F>(
         &self,
         ex: &Experiment,
         toolchain: &Toolchain,
+        compiler: Option<&str>,
         krate: &Crate,
         existing_logs: Option<LogStorage>,
         config: &Config,
@@ -199,6 +297,19 @@ pub enum FailureReason {
     ICE,
     CompilerError(BTreeSet<DiagnosticCode>),
     DependsOn(BTreeSet<Crate>),
+    FailedTests(BTreeSet<String>),
+    LinkerError,
+    MissingSystemLibs(BTreeSet<String>),
+    BuildScriptPanic,
+    ProcMacroPanic,
+    NetworkAccess,
+    NoSpace,
+    Crash,
+    UndefinedBehavior,
+    MiriUnsupported,
+    Unformatted,
+    MigrationFailed,
+    BreakingChanges(BTreeSet<BreakingChange>),
 }
 
 impl Fail for FailureReason {}
@@ -210,6 +321,16 @@ impl ::std::fmt::Display for FailureReason {
             FailureReason::OOM => write!(f, "oom"),
             FailureReason::Timeout => write!(f, "timeout"),
             FailureReason::ICE => write!(f, "ice"),
+            FailureReason::LinkerError => write!(f, "linker-error"),
+            FailureReason::BuildScriptPanic => write!(f, "build-script-panic"),
+            FailureReason::ProcMacroPanic => write!(f, "proc-macro-panic"),
+            FailureReason::NetworkAccess => write!(f, "network-access"),
+            FailureReason::NoSpace => write!(f, "no-space"),
+            FailureReason::Crash => write!(f, "crash"),
+            FailureReason::UndefinedBehavior => write!(f, "undefined-behavior"),
+            FailureReason::MiriUnsupported => write!(f, "miri-unsupported"),
+            FailureReason::Unformatted => write!(f, "unformatted"),
+            FailureReason::MigrationFailed => write!(f, "migration-failed"),
             FailureReason::CompilerError(codes) => write!(
                 f,
                 "compiler-error({})",
@@ -227,10 +348,56 @@ impl ::std::fmt::Display for FailureReason {
                     .collect::<Vec<String>>()
                     .join(", "),
             ),
+            FailureReason::FailedTests(tests) => write!(
+                f,
+                "failed-tests({})",
+                tests
+                    .iter()
+                    .map(|test| escape_item(test))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+            FailureReason::MissingSystemLibs(libs) => write!(
+                f,
+                "missing-system-libs({})",
+                libs.iter().cloned().collect::<Vec<String>>().join(", "),
+            ),
+            FailureReason::BreakingChanges(changes) => write!(
+                f,
+                "breaking-changes({})",
+                changes
+                    .iter()
+                    .map(|change| change.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
         }
     }
 }
 
+/// Escape the separator of the items of a failure reason, for the items that can contain it,
+/// such as the names of the tests.
+fn escape_item(item: &str) -> String {
+    item.replace('\\', "\\\\").replace(',', "\\,")
+}
+
+/// Split the items of a failure reason escaped with `escape_item`.
+fn split_escaped_items(contents: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => items.last_mut().unwrap().extend(chars.next()),
+            ',' if chars.peek() == Some(&' ') => {
+                chars.next();
+                items.push(String::new());
+            }
+            c => items.last_mut().unwrap().push(c),
+        }
+    }
+    items
+}
+
 impl ::std::str::FromStr for FailureReason {
     type Err = ::failure::Error;
 
@@ -253,6 +420,19 @@ impl ::std::str::FromStr for FailureReason {
                     }
                     Ok(FailureReason::DependsOn(krates))
                 }
+                "failed-tests" => Ok(FailureReason::FailedTests(
+                    split_escaped_items(&s[idx + 1..s.len() - 1])
+                        .into_iter()
+                        .collect(),
+                )),
+                "missing-system-libs" => Ok(FailureReason::MissingSystemLibs(
+                    contents.map(|lib| lib.to_string()).collect(),
+                )),
+                "breaking-changes" => Ok(FailureReason::BreakingChanges(
+                    contents
+                        .map(|change| change.parse())
+                        .collect::<Fallible<_>>()?,
+                )),
                 _ => bail!("unexpected value"),
             }
         } else {
@@ -261,6 +441,16 @@ impl ::std::str::FromStr for FailureReason {
                 "oom" => Ok(FailureReason::OOM),
                 "timeout" => Ok(FailureReason::Timeout),
                 "ice" => Ok(FailureReason::ICE),
+                "linker-error" => Ok(FailureReason::LinkerError),
+                "build-script-panic" => Ok(FailureReason::BuildScriptPanic),
+                "proc-macro-panic" => Ok(FailureReason::ProcMacroPanic),
+                "network-access" => Ok(FailureReason::NetworkAccess),
+                "no-space" => Ok(FailureReason::NoSpace),
+                "crash" => Ok(FailureReason::Crash),
+                "undefined-behavior" => Ok(FailureReason::UndefinedBehavior),
+                "miri-unsupported" => Ok(FailureReason::MiriUnsupported),
+                "unformatted" => Ok(FailureReason::Unformatted),
+                "migration-failed" => Ok(FailureReason::MigrationFailed),
                 _ => bail!("unexpected value"),
             }
         }
@@ -270,20 +460,68 @@ impl ::std::str::FromStr for FailureReason {
 impl FailureReason {
     pub(crate) fn is_spurious(&self) -> bool {
         match *self {
-            FailureReason::OOM | FailureReason::Timeout => true,
+            FailureReason::OOM | FailureReason::Timeout | FailureReason::NoSpace => true,
             FailureReason::CompilerError(_)
             | FailureReason::DependsOn(_)
+            | FailureReason::FailedTests(_)
+            | FailureReason::LinkerError
+            | FailureReason::MissingSystemLibs(_)
+            | FailureReason::BuildScriptPanic
+            | FailureReason::ProcMacroPanic
+            | FailureReason::NetworkAccess
+            | FailureReason::Crash
+            | FailureReason::UndefinedBehavior
+            | FailureReason::MiriUnsupported
+            | FailureReason::Unformatted
+            | FailureReason::MigrationFailed
+            | FailureReason::BreakingChanges(_)
             | FailureReason::Unknown
             | FailureReason::ICE => false,
         }
     }
 }
 
+/// A change to the public API of a crate breaking the code written against its previous
+/// semver-compatible release, identified by the path of the item.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BreakingChange {
+    Removed(String),
+    SignatureChanged(String),
+    NewRequiredItem(String),
+}
+
+impl ::std::fmt::Display for BreakingChange {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self {
+            BreakingChange::Removed(path) => write!(f, "removed {}", path),
+            BreakingChange::SignatureChanged(path) => write!(f, "changed {}", path),
+            BreakingChange::NewRequiredItem(path) => write!(f, "new-required-item {}", path),
+        }
+    }
+}
+
+impl ::std::str::FromStr for BreakingChange {
+    type Err = ::failure::Error;
+
+    fn from_str(s: &str) -> ::failure::Fallible<BreakingChange> {
+        let mut parts = s.splitn(2, ' ');
+        match (parts.next(), parts.next()) {
+            (Some("removed"), Some(path)) => Ok(BreakingChange::Removed(path.into())),
+            (Some("changed"), Some(path)) => Ok(BreakingChange::SignatureChanged(path.into())),
+            (Some("new-required-item"), Some(path)) => {
+                Ok(BreakingChange::NewRequiredItem(path.into()))
+            }
+            _ => bail!("invalid breaking change: {}", s),
+        }
+    }
+}
+
 string_enum!(pub enum BrokenReason {
     Unknown => "unknown",
     CargoToml => "cargo-toml",
     Yanked => "yanked",
     MissingGitRepository => "missing-git-repository",
+    Unresolvable => "unresolvable",
 });
 
 test_result_enum!(pub enum TestResult {
@@ -302,6 +540,204 @@ test_result_enum!(pub enum TestResult {
 
 impl_serde_from_parse!(TestResult, expecting = "a test result");
 
+string_enum!(pub enum TestOutcome {
+    Passed => "passed",
+    Failed => "failed",
+    Ignored => "ignored",
+});
+
+/// The outcome of each test of a crate, keyed by the test binary and the name of the test.
+pub type TestOutcomes = BTreeMap<String, TestOutcome>;
+
+/// The result of each combination of features a crate was built with, keyed by the Cargo flags
+/// selecting the combination.
+pub type FeatureResults = BTreeMap<String, TestResult>;
+
+/// A warning emitted by the compiler for the crate being built.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+pub struct Warning {
+    /// The name of the lint, or the message for warnings not emitted by a lint.
+    pub lint: String,
+    /// Where the warning was emitted, as `file:line:column`.
+    pub span: String,
+}
+
+pub type Warnings = BTreeSet<Warning>;
+
+/// A future-incompatibility lint triggered while building a crate.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Serialize, Deserialize)]
+pub struct FutureIncompat {
+    pub lint: String,
+    /// The dependency triggering the lint, or `None` if it's triggered by the crate itself.
+    pub dependency: Option<Crate>,
+}
+
+pub type FutureIncompats = BTreeSet<FutureIncompat>;
+
+/// Changes `cargo fmt` would make to the code of a crate.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct RustfmtDiff {
+    pub files_changed: u32,
+    /// The diff hunks printed by rustfmt, with the paths relative to the root of the crate.
+    pub hunks: String,
+}
+
+/// The `Cargo.lock` a crate was built with.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The policy the lockfile was updated with after preparing the crate.
+    pub policy: LockfilePolicy,
+    pub content: String,
+}
+
+/// The sources crates.io packages are locked with, depending on the protocol used by Cargo.
+const CRATES_IO_SOURCES: &[&str] = &[
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+#[derive(Deserialize)]
+struct LockfilePackages {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+}
+
+impl Lockfile {
+    /// The packages published on crates.io locked in the lockfile, which include all the
+    /// dependencies of the crate, direct or not.
+    pub fn registry_packages(&self) -> Fallible<Vec<RegistryCrate>> {
+        let lockfile: LockfilePackages = toml::from_str(&self.content)?;
+        Ok(lockfile
+            .package
+            .into_iter()
+            .filter(|package| {
+                package
+                    .source
+                    .as_ref()
+                    .map(|source| CRATES_IO_SOURCES.contains(&source.as_str()))
+                    .unwrap_or(false)
+            })
+            .map(|package| RegistryCrate {
+                name: package.name,
+                version: package.version,
+            })
+            .collect())
+    }
+}
+
+/// A line of the source code of a crate matched by a query.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct MatchedLine {
+    /// The path of the file, relative to the root of the crate.
+    pub file: String,
+    pub line: u32,
+    pub content: String,
+}
+
+/// The matches of a query in the source code of a crate.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default, Serialize, Deserialize)]
+pub struct QueryResult {
+    pub matches: u32,
+    /// The lines containing the first matches, as recording all of them could take too much
+    /// space for queries matching common code.
+    pub lines: Vec<MatchedLine>,
+}
+
+/// The results of the queries of an experiment on a crate, keyed by the name of the query.
+pub type QueryResults = BTreeMap<String, QueryResult>;
+
+/// The names of the unstable features enabled by a crate.
+pub type UnstableFeatures = BTreeSet<String>;
+
+/// Resources used to build a crate from scratch, as the median of the repeated samples.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct CompileTime {
+    pub samples: u32,
+    pub wall_time_ms: u64,
+    /// User and system time spent by all the compiler invocations.
+    pub cpu_time_ms: Option<u64>,
+    /// Peak resident memory of the most expensive compiler invocation.
+    pub peak_rss_kb: Option<u64>,
+}
+
+/// Details of an internal compiler error, extracted from the output of the compiler.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+pub struct Ice {
+    pub message: String,
+    /// Where the compiler panicked, as `file:line:column`.
+    pub location: Option<String>,
+    pub query_stack: Vec<String>,
+}
+
+impl Ice {
+    /// Identify the compiler bug that caused the ICE, ignoring what depends on the crate being
+    /// built (names and numbers in the message, the outer queries) or on the exact version of
+    /// the compiler (line numbers).
+    pub fn signature(&self) -> String {
+        let mut message = String::new();
+        let mut in_quotes = false;
+        let mut prev_digit = false;
+        for c in self.message.lines().next().unwrap_or("").chars() {
+            let digit = !in_quotes && c.is_ascii_digit();
+            if c == '`' {
+                if !in_quotes {
+                    message.push_str("`_`");
+                }
+                in_quotes = !in_quotes;
+            } else if digit {
+                if !prev_digit {
+                    message.push('N');
+                }
+            } else if !in_quotes {
+                message.push(c);
+            }
+            prev_digit = digit;
+        }
+
+        let mut signature = message;
+        if let Some(location) = &self.location {
+            signature.push_str(" at ");
+            signature.push_str(location.splitn(2, ':').next().unwrap_or(location));
+        }
+        // Query stack entries look like `#0 [type_of] computing type of `foo``
+        let query = self
+            .query_stack
+            .first()
+            .and_then(|entry| entry.split('[').nth(1))
+            .and_then(|query| query.split(']').next());
+        if let Some(query) = query {
+            signature.push_str(" in ");
+            signature.push_str(query);
+        }
+
+        signature
+    }
+}
+
+/// The data recorded for a crate besides its result, which depends on the mode and the features
+/// of the experiment. What wasn't recorded is left empty.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultExtras {
+    pub tests: TestOutcomes,
+    pub ice: Option<Ice>,
+    pub compile_time: Option<CompileTime>,
+    pub warnings: Warnings,
+    pub future_incompat: FutureIncompats,
+    pub rustfmt_diff: Option<RustfmtDiff>,
+    pub feature_results: FeatureResults,
+    pub lockfile: Option<Lockfile>,
+    pub query_results: QueryResults,
+    pub unstable_features: UnstableFeatures,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::crates::*;
@@ -311,6 +747,7 @@ mod tests {
     #[test]
     fn test_test_result_parsing() {
         use super::{
+            BreakingChange,
             FailureReason::*,
             TestResult::{self, *},
         };
@@ -343,7 +780,21 @@ mod tests {
             "build-fail:compiler-error(001)" => BuildFail(CompilerError(btreeset!["001".parse().unwrap()])),
             "build-fail:oom" => BuildFail(OOM),
             "build-fail:ice" => BuildFail(ICE),
+            "build-fail:linker-error" => BuildFail(LinkerError),
+            "build-fail:missing-system-libs(ssl, z)" => BuildFail(MissingSystemLibs(btreeset!["ssl".to_string(), "z".to_string()])),
+            "build-fail:build-script-panic" => BuildFail(BuildScriptPanic),
+            "build-fail:proc-macro-panic" => BuildFail(ProcMacroPanic),
+            "test-fail:network-access" => TestFail(NetworkAccess),
+            "build-fail:no-space" => BuildFail(NoSpace),
+            "test-fail:crash" => TestFail(Crash),
+            "test-fail:undefined-behavior" => TestFail(UndefinedBehavior),
+            "test-fail:miri-unsupported" => TestFail(MiriUnsupported),
+            "test-fail:unformatted" => TestFail(Unformatted),
+            "build-fail:migration-failed" => BuildFail(MigrationFailed),
+            "test-fail:breaking-changes(removed foo::bar, new-required-item foo::Baz::qux)" => TestFail(BreakingChanges(btreeset![BreakingChange::Removed("foo::bar".into()), BreakingChange::NewRequiredItem("foo::Baz::qux".into())])),
             "test-fail:timeout" => TestFail(Timeout),
+            "test-fail:failed-tests(lib: tests::bar, lib: tests::foo)" => TestFail(FailedTests(btreeset!["lib: tests::foo".to_string(), "lib: tests::bar".to_string()])),
+            r"test-fail:failed-tests(lib: tests::bar\, baz, lib: tests::foo\\)" => TestFail(FailedTests(btreeset!["lib: tests::bar, baz".to_string(), r"lib: tests::foo\".to_string()])),
             "test-pass" => TestPass,
             "error" => Error,
             "build-fail:depends-on(reg/clint/0.2.1)" => BuildFail(DependsOn(btreeset![Crate::Registry(RegistryCrate{name: "clint".to_string(), version: "0.2.1".to_string()})])),
@@ -357,4 +808,82 @@ mod tests {
         assert!(TestResult::from_str("error:oom").is_err());
         assert!(TestResult::from_str("build-fail:pleasedonotaddthis").is_err());
     }
+
+    #[test]
+    fn test_lockfile_registry_packages() {
+        use super::Lockfile;
+        use crate::experiments::LockfilePolicy;
+
+        let lockfile = Lockfile {
+            policy: LockfilePolicy::AsPrepared,
+            content: r#"
+                [[package]]
+                name = "foo"
+                version = "0.1.0"
+                dependencies = ["lazy_static", "bar"]
+
+                [[package]]
+                name = "lazy_static"
+                version = "1.4.0"
+                source = "registry+https://github.com/rust-lang/crates.io-index"
+
+                [[package]]
+                name = "bar"
+                version = "0.2.0"
+                source = "git+https://github.com/example/bar#f00"
+
+                [[package]]
+                name = "baz"
+                version = "1.0.0"
+                source = "sparse+https://index.crates.io/"
+            "#
+            .into(),
+        };
+
+        assert_eq!(
+            lockfile.registry_packages().unwrap(),
+            vec![
+                RegistryCrate {
+                    name: "lazy_static".into(),
+                    version: "1.4.0".into(),
+                },
+                RegistryCrate {
+                    name: "baz".into(),
+                    version: "1.0.0".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ice_signature() {
+        use super::Ice;
+
+        let ice = |message: &str, location: &str, query: &str| Ice {
+            message: message.into(),
+            location: Some(location.into()),
+            query_stack: vec![
+                query.into(),
+                "#1 [typeck_tables_of] processing `main`".into(),
+            ],
+        };
+
+        let first = ice(
+            "no type for node HirId { owner: DefIndex(12), local_id: 3 }: `foo::bar`",
+            "src/librustc/ty/context.rs:123:9",
+            "#0 [typeck_tables_of] processing `foo::bar`",
+        );
+        let second = ice(
+            "no type for node HirId { owner: DefIndex(7), local_id: 42 }: `baz`",
+            "src/librustc/ty/context.rs:130:9",
+            "#0 [typeck_tables_of] processing `baz`",
+        );
+
+        assert_eq!(
+            first.signature(),
+            "no type for node HirId { owner: DefIndex(N), local_id: N }: `_` \
+             at src/librustc/ty/context.rs in typeck_tables_of"
+        );
+        assert_eq!(first.signature(), second.signature());
+    }
 }
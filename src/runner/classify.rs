@@ -0,0 +1,308 @@
+use crate::results::{FailureReason, Ice};
+use std::collections::BTreeSet;
+
+const NETWORK_ERRORS: &[&str] = &[
+    "Network is unreachable",
+    "failed to lookup address information",
+    "Temporary failure in name resolution",
+    "Could not resolve host",
+];
+
+const PROC_MACRO_PANICS: &[&str] = &[
+    "proc-macro derive panicked",
+    "proc macro panicked",
+    "custom attribute panicked",
+];
+
+/// Detects why a command failed from its output, for the failures the compiler doesn't report
+/// with a structured diagnostic.
+#[derive(Default)]
+pub(super) struct OutputClassifier {
+    no_space: bool,
+    network_access: bool,
+    missing_libs: BTreeSet<String>,
+    linker_error: bool,
+    proc_macro_panic: bool,
+    build_script_panic: bool,
+    crash: bool,
+    undefined_behavior: bool,
+    miri_unsupported: bool,
+}
+
+impl OutputClassifier {
+    pub(super) fn process_line(&mut self, line: &str) {
+        if line.contains("No space left on device") {
+            self.no_space = true;
+        } else if line.starts_with("error: Undefined Behavior: ") {
+            self.undefined_behavior = true;
+        } else if line.starts_with("error: unsupported operation: ") {
+            self.miri_unsupported = true;
+        } else if NETWORK_ERRORS.iter().any(|err| line.contains(err)) {
+            self.network_access = true;
+        } else if let Some(lib) = missing_library(line) {
+            self.missing_libs.insert(lib.to_string());
+        } else if line.contains("linking with `") {
+            self.linker_error = true;
+        } else if PROC_MACRO_PANICS.iter().any(|err| line.contains(err)) {
+            self.proc_macro_panic = true;
+        } else if line.contains("failed to run custom build command for `") {
+            self.build_script_panic = true;
+        } else if line.contains("(signal: ")
+            && (line.contains("SIGSEGV") || line.contains("SIGABRT"))
+        {
+            self.crash = true;
+        }
+    }
+
+    /// The most specific reason found in the output: a failure can cause the following ones,
+    /// for example a missing system library makes the build script or the linker fail.
+    pub(super) fn into_reason(self) -> Option<FailureReason> {
+        if self.no_space {
+            Some(FailureReason::NoSpace)
+        } else if self.network_access {
+            Some(FailureReason::NetworkAccess)
+        } else if self.undefined_behavior {
+            Some(FailureReason::UndefinedBehavior)
+        } else if self.miri_unsupported {
+            Some(FailureReason::MiriUnsupported)
+        } else if !self.missing_libs.is_empty() {
+            Some(FailureReason::MissingSystemLibs(self.missing_libs))
+        } else if self.linker_error {
+            Some(FailureReason::LinkerError)
+        } else if self.proc_macro_panic {
+            Some(FailureReason::ProcMacroPanic)
+        } else if self.build_script_panic {
+            Some(FailureReason::BuildScriptPanic)
+        } else if self.crash {
+            Some(FailureReason::Crash)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extracts the details of the first internal compiler error found in the output.
+#[derive(Default)]
+pub(super) struct IceCollector {
+    message: Option<String>,
+    location: Option<String>,
+    query_stack: Vec<String>,
+    in_query_stack: bool,
+    query_stack_done: bool,
+    message_on_next_line: bool,
+}
+
+impl IceCollector {
+    pub(super) fn process_line(&mut self, line: &str) {
+        let line = line.trim();
+
+        if self.message_on_next_line {
+            self.message_on_next_line = false;
+            self.set_message(line);
+        } else if self.in_query_stack {
+            if line.starts_with("end of query stack") {
+                self.in_query_stack = false;
+                self.query_stack_done = true;
+            } else if line.starts_with('#') {
+                self.query_stack.push(line.to_string());
+            }
+        } else if line.starts_with("query stack during panic:") {
+            self.in_query_stack = !self.query_stack_done;
+        } else if let (true, Some(idx)) = (line.starts_with("thread '"), line.find("panicked at "))
+        {
+            let panic = &line[idx + "panicked at ".len()..];
+            if panic.starts_with('\'') {
+                // Older compilers print `panicked at 'message', location`
+                if let Some(end) = panic.rfind("', ") {
+                    self.set_message(&panic[1..end]);
+                    self.set_location(&panic[end + "', ".len()..]);
+                }
+            } else {
+                // Newer compilers print the message on the line after the location
+                self.set_location(panic.trim_end_matches(':'));
+                self.message_on_next_line = true;
+            }
+        } else if let Some(idx) = line.find("internal compiler error: ") {
+            let message = &line[idx + "internal compiler error: ".len()..];
+            // Bugs reported by the compiler itself are prefixed by their location
+            match split_location(message) {
+                Some((location, message)) => {
+                    self.set_location(location);
+                    self.set_message(message);
+                }
+                None if message != "unexpected panic" => self.set_message(message),
+                None => {}
+            }
+        }
+    }
+
+    fn set_message(&mut self, message: &str) {
+        if self.message.is_none() && !message.is_empty() {
+            self.message = Some(message.to_string());
+        }
+    }
+
+    fn set_location(&mut self, location: &str) {
+        if self.location.is_none() && !location.is_empty() {
+            self.location = Some(location.to_string());
+        }
+    }
+
+    pub(super) fn into_ice(self) -> Option<Ice> {
+        let location = self.location;
+        let query_stack = self.query_stack;
+        self.message.map(|message| Ice {
+            message,
+            location,
+            query_stack,
+        })
+    }
+}
+
+/// Split a message like `src/librustc/foo.rs:12:34: message` into its location and the rest.
+fn split_location(message: &str) -> Option<(&str, &str)> {
+    let idx = message.find(".rs:")? + ".rs".len();
+    let mut parts = message[idx + 1..].splitn(3, ':');
+    let line = parts.next()?;
+    let column = parts.next()?;
+    let rest = parts.next()?;
+
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if is_number(line) && is_number(column) {
+        let location_len = idx + 1 + line.len() + 1 + column.len();
+        Some((&message[..location_len], rest.trim()))
+    } else {
+        None
+    }
+}
+
+/// Extract the name of a system library the linker or pkg-config couldn't find.
+fn missing_library(line: &str) -> Option<&str> {
+    let lib = if let Some(idx) = line.find("cannot find -l") {
+        let lib = &line[idx + "cannot find -l".len()..];
+        lib.split(|c: char| c.is_whitespace() || c == ':').next()
+    } else if let Some(idx) = line.find("The system library `") {
+        let lib = &line[idx + "The system library `".len()..];
+        lib.split('`').next()
+    } else if line.contains("was not found in the pkg-config search path") {
+        line.trim().splitn(3, ' ').nth(1)
+    } else {
+        None
+    };
+    lib.filter(|lib| !lib.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IceCollector, OutputClassifier};
+    use crate::results::{FailureReason, Ice};
+    use std::collections::BTreeSet;
+
+    fn classify(output: &str) -> Option<FailureReason> {
+        let mut classifier = OutputClassifier::default();
+        for line in output.lines() {
+            classifier.process_line(line);
+        }
+        classifier.into_reason()
+    }
+
+    #[test]
+    fn test_classify_output() {
+        macro_rules! test_classify {
+            ($($output:expr => $reason:expr,)*) => {
+                $(
+                    assert_eq!(classify($output), $reason);
+                )*
+            };
+        }
+
+        test_classify! {
+            "error: could not compile `foo`" => None,
+            "error: linking with `cc` failed: exit code: 1" => Some(FailureReason::LinkerError),
+            "error: linking with `cc` failed: exit code: 1\n  = note: /usr/bin/ld: cannot find -lssl\n" =>
+                Some(FailureReason::MissingSystemLibs(btreeset!["ssl".to_string()])),
+            "  = note: ld: cannot find -lz: No such file or directory" =>
+                Some(FailureReason::MissingSystemLibs(btreeset!["z".to_string()])),
+            "error: failed to run custom build command for `openssl-sys v0.9.58`\n\
+             The system library `openssl` required by crate `openssl-sys` was not found." =>
+                Some(FailureReason::MissingSystemLibs(btreeset!["openssl".to_string()])),
+            "Package alsa was not found in the pkg-config search path." =>
+                Some(FailureReason::MissingSystemLibs(btreeset!["alsa".to_string()])),
+            "error: failed to run custom build command for `foo v0.1.0`" =>
+                Some(FailureReason::BuildScriptPanic),
+            "error: proc-macro derive panicked" => Some(FailureReason::ProcMacroPanic),
+            "error: failed to run custom build command for `foo v0.1.0`\n\
+             Caused by: failed to lookup address information: Name or service not known" =>
+                Some(FailureReason::NetworkAccess),
+            "error: failed to write: No space left on device (os error 28)" =>
+                Some(FailureReason::NoSpace),
+            "error: test failed, to rerun pass '--lib'\n\
+             process didn't exit successfully: `/target/debug/deps/foo-abc` (signal: 11, SIGSEGV: invalid memory reference)" =>
+                Some(FailureReason::Crash),
+            "error: Undefined Behavior: dereferencing pointer failed: null pointer is not a valid pointer\n\
+             error: test failed, to rerun pass '--lib'" =>
+                Some(FailureReason::UndefinedBehavior),
+            "error: unsupported operation: can't call foreign function `SSL_new` on OS `linux`" =>
+                Some(FailureReason::MiriUnsupported),
+        }
+    }
+
+    #[test]
+    fn test_collect_ice() {
+        let collect = |output: &str| {
+            let mut collector = IceCollector::default();
+            for line in output.lines() {
+                collector.process_line(line);
+            }
+            collector.into_ice()
+        };
+
+        assert_eq!(collect("error: could not compile `foo`"), None);
+
+        let old_panic = "\
+error: internal compiler error: unexpected panic
+thread 'rustc' panicked at 'called `Option::unwrap()` on a `None` value', src/librustc/ty/mod.rs:12:5
+query stack during panic:
+#0 [type_of] processing `foo`
+#1 [typeck_tables_of] processing `bar`
+end of query stack
+query stack during panic:
+#0 [mir_built] processing `baz`
+end of query stack";
+        assert_eq!(
+            collect(old_panic),
+            Some(Ice {
+                message: "called `Option::unwrap()` on a `None` value".into(),
+                location: Some("src/librustc/ty/mod.rs:12:5".into()),
+                query_stack: vec![
+                    "#0 [type_of] processing `foo`".into(),
+                    "#1 [typeck_tables_of] processing `bar`".into(),
+                ],
+            })
+        );
+
+        let new_panic = "\
+thread 'rustc' panicked at compiler/rustc_middle/src/ty/mod.rs:12:5:
+index out of bounds
+error: the compiler unexpectedly panicked. this is a bug.";
+        assert_eq!(
+            collect(new_panic),
+            Some(Ice {
+                message: "index out of bounds".into(),
+                location: Some("compiler/rustc_middle/src/ty/mod.rs:12:5".into()),
+                query_stack: Vec::new(),
+            })
+        );
+
+        let bug =
+            "error: internal compiler error: src/librustc/hir/map/mod.rs:42:3: no entry found";
+        assert_eq!(
+            collect(bug),
+            Some(Ice {
+                message: "no entry found".into(),
+                location: Some("src/librustc/hir/map/mod.rs:42:3".into()),
+                query_stack: Vec::new(),
+            })
+        );
+    }
+}
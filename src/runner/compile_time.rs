@@ -0,0 +1,145 @@
+use crate::prelude::*;
+use crate::results::CompileTime;
+use rustwide::cmd::{Command, SandboxBuilder};
+use rustwide::Workspace;
+use std::time::Duration;
+
+/// Where GNU time is installed in the sandbox image.
+pub(super) const TIME_BINARY: &str = "/usr/bin/time";
+/// Compiler invocations are wrapped with GNU time, printing the resources they used in this
+/// format (configured through the `TIME` environment variable) after they exit.
+pub(super) const TIME_FORMAT: &str = "crater-rustc-time: %U %S %M";
+const TIME_PREFIX: &str = "crater-rustc-time: ";
+
+/// Ensure GNU time is installed in the sandbox image, as the compile times can't be measured
+/// without it.
+pub(super) fn check_time_installed(workspace: &Workspace) -> Fallible<()> {
+    let sandbox = SandboxBuilder::new().enable_networking(false);
+    Command::new_sandboxed(workspace, sandbox, TIME_BINARY)
+        .args(&["true"])
+        .run()
+        .with_context(|_| {
+            format!(
+                "measuring compile times requires GNU time at {} in the sandbox image",
+                TIME_BINARY
+            )
+        })?;
+    Ok(())
+}
+
+/// Resources used by a single build of the crate from scratch.
+pub(super) struct Sample {
+    wall_time_ms: u64,
+    cpu_time_ms: Option<u64>,
+    peak_rss_kb: Option<u64>,
+}
+
+/// Adds up the resources used by all the compiler invocations of a build.
+#[derive(Default)]
+pub(super) struct SampleCollector {
+    cpu_time_ms: Option<u64>,
+    peak_rss_kb: Option<u64>,
+}
+
+impl SampleCollector {
+    pub(super) fn process_line(&mut self, line: &str) {
+        let line = line.trim();
+        if !line.starts_with(TIME_PREFIX) {
+            return;
+        }
+
+        let mut parts = line[TIME_PREFIX.len()..].split_whitespace();
+        let user = parts.next().and_then(parse_seconds);
+        let system = parts.next().and_then(parse_seconds);
+        let rss = parts.next().and_then(|rss| rss.parse::<u64>().ok());
+        if let (Some(user), Some(system), Some(rss)) = (user, system, rss) {
+            *self.cpu_time_ms.get_or_insert(0) += user + system;
+            self.peak_rss_kb = Some(self.peak_rss_kb.map_or(rss, |peak| peak.max(rss)));
+        }
+    }
+
+    pub(super) fn into_sample(self, wall_time: Duration) -> Sample {
+        Sample {
+            wall_time_ms: wall_time.as_millis() as u64,
+            cpu_time_ms: self.cpu_time_ms,
+            peak_rss_kb: self.peak_rss_kb,
+        }
+    }
+}
+
+fn parse_seconds(seconds: &str) -> Option<u64> {
+    seconds
+        .parse::<f64>()
+        .ok()
+        .map(|seconds| (seconds * 1000.0).round() as u64)
+}
+
+fn median(mut values: Vec<u64>) -> Option<u64> {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[mid - 1] + values[mid]) / 2),
+        _ => Some(values[mid]),
+    }
+}
+
+/// Combine the samples of a crate, using the median of each measurement as it's less affected
+/// by outliers than the mean.
+pub(super) fn combine_samples(samples: &[Sample]) -> Option<CompileTime> {
+    Some(CompileTime {
+        samples: samples.len() as u32,
+        wall_time_ms: median(samples.iter().map(|s| s.wall_time_ms).collect())?,
+        cpu_time_ms: median(samples.iter().filter_map(|s| s.cpu_time_ms).collect()),
+        peak_rss_kb: median(samples.iter().filter_map(|s| s.peak_rss_kb).collect()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combine_samples, SampleCollector};
+    use crate::results::CompileTime;
+    use std::time::Duration;
+
+    #[test]
+    fn test_combine_samples() {
+        let outputs = &[
+            (
+                1_000,
+                "crater-rustc-time: 0.50 0.10 10240\n\
+                 crater-rustc-time: 1.20 0.30 51200\n",
+            ),
+            (
+                3_000,
+                "   Compiling foo v0.1.0\n\
+                 Command exited with non-zero status 1\n\
+                 crater-rustc-time: 2.00 0.25 40960\n\
+                 crater-rustc-time: 1.00 0.25 81920\n",
+            ),
+            (2_000, "crater-rustc-time: 1.50 0.50 61440\n"),
+        ];
+
+        let samples = outputs
+            .iter()
+            .map(|&(wall_time, output)| {
+                let mut collector = SampleCollector::default();
+                for line in output.lines() {
+                    collector.process_line(line);
+                }
+                collector.into_sample(Duration::from_millis(wall_time))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            combine_samples(&samples),
+            Some(CompileTime {
+                samples: 3,
+                wall_time_ms: 2_000,
+                cpu_time_ms: Some(2_100),
+                peak_rss_kb: Some(61_440),
+            })
+        );
+        assert_eq!(combine_samples(&samples[..2]).unwrap().wall_time_ms, 2_000);
+        assert_eq!(combine_samples(&[]), None);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::prelude::*;
+use crate::results::{FailureReason, TestResult, WriteResults};
+use crate::runner::lockfile;
+use crate::runner::tasks::TaskCtx;
+use crate::runner::test::detect_broken;
+use crate::runner::{sandbox_target_path, OverrideResult, SANDBOX_SOURCE_DIR};
+use failure::Error;
+use remove_dir_all::remove_dir_all;
+use rustwide::cmd::{ProcessLinesActions, SandboxBuilder};
+use rustwide::Build;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+/// The source directory is mounted read-only in the sandbox, so the crate is migrated in a copy
+/// living in the target directory.
+const MIGRATION_DIR: &str = "edition-migration";
+
+const EDITIONS: &[&str] = &["2015", "2018", "2021", "2024"];
+
+/// The edition following the one declared by a manifest, or `None` if it's either the newest
+/// edition or not a package manifest at all.
+fn next_edition(manifest: &toml::Value) -> Option<&'static str> {
+    let package = manifest.get("package")?;
+    // Editions inherited from the workspace are bumped in the workspace manifest instead
+    let current = match package.get("edition") {
+        Some(edition) => edition.as_str()?,
+        None => "2015",
+    };
+    let pos = EDITIONS.iter().position(|&edition| edition == current)?;
+    EDITIONS.get(pos + 1).cloned()
+}
+
+/// Set the edition of the package declared by the manifest. Only the `edition` key is changed,
+/// leaving the comments and the layout of the rest of the manifest untouched.
+fn set_edition(manifest: &str, edition: &str) -> String {
+    let edition_line = format!("edition = \"{}\"", edition);
+    let mut lines = manifest.lines().map(String::from).collect::<Vec<_>>();
+
+    let mut package_header = None;
+    let mut in_package = false;
+    let mut replaced = false;
+    for (i, line) in lines.iter_mut().enumerate() {
+        let content = line.split('#').next().unwrap_or("").trim();
+        if content.starts_with('[') {
+            in_package = content == "[package]";
+            if in_package {
+                package_header = Some(i);
+            }
+        } else if in_package && content.split('=').next().map(str::trim) == Some("edition") {
+            *line = match line.find('#') {
+                Some(comment) => format!("{} {}", edition_line, &line[comment..]),
+                None => edition_line.clone(),
+            };
+            replaced = true;
+        }
+    }
+    if let (false, Some(header)) = (replaced, package_header) {
+        lines.insert(header + 1, edition_line);
+    }
+
+    let mut manifest_out = lines.join("\n");
+    if manifest.ends_with('\n') {
+        manifest_out.push('\n');
+    }
+    manifest_out
+}
+
+/// Bump the edition of all the package manifests in the directory.
+fn bump_editions(dir: &Path) -> Fallible<()> {
+    let entries = WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != "target");
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_name() != "Cargo.toml" {
+            continue;
+        }
+
+        let content = fs::read_to_string(entry.path())?;
+        if let Some(edition) = next_edition(&toml::from_str(&content)?) {
+            fs::write(entry.path(), set_edition(&content, edition).as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Fallible<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let dest = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// Migrate the crate to the next edition with `cargo fix --edition`, returning the directory with
+/// the migrated source code. This is done once while preparing the crate with the start toolchain
+/// of the experiment, and every toolchain then builds the migrated source code. The changes made
+/// by the migration are printed in the log.
+pub(super) fn migrate<DB: WriteResults>(ctx: &TaskCtx<DB>) -> Fallible<TempDir> {
+    let sandbox = SandboxBuilder::new()
+        .memory_limit(Some(ctx.config.sandbox.memory_limit.to_bytes()))
+        .enable_networking(false);
+    let krate = &ctx.krate.to_rustwide();
+    let mut build_dir = ctx.build_dir.lock().unwrap();
+    let mut build = build_dir.build(&ctx.toolchain, krate, sandbox);
+    for patch in ctx.toolchain.patches.iter() {
+        build = build.patch_with_git(&patch.name, &patch.repo, &patch.branch);
+    }
+
+    let migrated = tempfile::tempdir()?;
+    detect_broken(build.run(|build| {
+        lockfile::write_resolved(ctx, build)?;
+        migrate_in(ctx, build, migrated.path())
+    }))?;
+    Ok(migrated)
+}
+
+fn migrate_in<DB: WriteResults>(ctx: &TaskCtx<DB>, build_env: &Build, dest: &Path) -> Fallible<()> {
+    let source_dir = build_env.host_source_dir();
+    let migration_dir = build_env.host_target_dir().join(MIGRATION_DIR);
+    if migration_dir.exists() {
+        remove_dir_all(&migration_dir)?;
+    }
+    copy_dir(&source_dir, &migration_dir)?;
+
+    let sandbox_dir = sandbox_target_path(MIGRATION_DIR);
+    let manifest_path = format!("{}/Cargo.toml", sandbox_dir);
+    let mut fixes_failed = false;
+    let res = build_env
+        .cmd(ctx.toolchain.source.cargo())
+        .args(&[
+            "fix",
+            "--edition",
+            "--allow-no-vcs",
+            "--frozen",
+            "--all",
+            "--all-targets",
+            "--manifest-path",
+            &manifest_path,
+        ])
+        .env("CARGO_INCREMENTAL", "0")
+        .process_lines(&mut |line: &str, _: &mut ProcessLinesActions| {
+            if line.contains("failed to automatically apply fixes suggested by rustc") {
+                fixes_failed = true;
+            }
+        })
+        .run();
+    let res = match res {
+        Ok(()) if fixes_failed => Err(err_msg("cargo fix failed to apply the suggested fixes")),
+        other => other.map_err(Error::from),
+    };
+    if let Err(err) = res {
+        return Err(err
+            .context(OverrideResult(TestResult::BuildFail(
+                FailureReason::MigrationFailed,
+            )))
+            .into());
+    }
+    bump_editions(&migration_dir)?;
+
+    info!("changes made by the migration:");
+    // diff exits with a non-zero status when the directories differ
+    let _ = build_env
+        .cmd("diff")
+        .args(&["-ru", SANDBOX_SOURCE_DIR, &sandbox_dir])
+        .run();
+
+    copy_dir(&migration_dir, dest)?;
+    remove_dir_all(&migration_dir)?;
+
+    Ok(())
+}
+
+/// Replace the source code of the crate with the one migrated while preparing it.
+pub(super) fn apply_migration<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+) -> Fallible<()> {
+    let migrated = ctx
+        .state
+        .lock()
+        .migrated_sources
+        .get(ctx.krate)
+        .map(|dir| dir.path().to_path_buf())
+        .ok_or_else(|| err_msg("the crate wasn't migrated while preparing it"))?;
+    copy_dir(&migrated, &build_env.host_source_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_edition, set_edition};
+
+    #[test]
+    fn test_next_edition() {
+        let manifest = |content: &str| -> toml::Value { toml::from_str(content).unwrap() };
+
+        assert_eq!(
+            next_edition(&manifest("[package]\nname = \"foo\"")),
+            Some("2018")
+        );
+        assert_eq!(
+            next_edition(&manifest("[package]\nedition = \"2018\"")),
+            Some("2021")
+        );
+        assert_eq!(
+            next_edition(&manifest("[package]\nedition = \"2024\"")),
+            None
+        );
+        assert_eq!(next_edition(&manifest("[workspace]\nmembers = []")), None);
+        assert_eq!(
+            next_edition(&manifest("[package]\nedition.workspace = true")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_set_edition() {
+        assert_eq!(
+            set_edition(
+                "# The foo crate\n\
+                 [package]\n\
+                 name = \"foo\"\n\
+                 edition = \"2018\" # the edition\n\
+                 \n\
+                 [dependencies]\n\
+                 edition = \"1\"\n",
+                "2021"
+            ),
+            "# The foo crate\n\
+             [package]\n\
+             name = \"foo\"\n\
+             edition = \"2021\" # the edition\n\
+             \n\
+             [dependencies]\n\
+             edition = \"1\"\n"
+        );
+        assert_eq!(
+            set_edition(
+                "[dependencies]\nlibc = \"0.2\"\n\n[package]\nname = \"foo\"",
+                "2018"
+            ),
+            "[dependencies]\nlibc = \"0.2\"\n\n[package]\nedition = \"2018\"\nname = \"foo\""
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::experiments::FeatureSet;
+
+/// The Cargo flags selecting each combination of features built by an experiment, given the
+/// features of the crate (excluding `default`).
+pub(super) fn combinations(set: FeatureSet, features: &[String]) -> Vec<Vec<String>> {
+    match set {
+        FeatureSet::Default => vec![Vec::new()],
+        FeatureSet::AllFeatures => vec![vec!["--all-features".into()]],
+        FeatureSet::NoDefaultFeatures => vec![vec!["--no-default-features".into()]],
+        FeatureSet::Powerset(max) => {
+            let mut combinations = Vec::new();
+            // Smaller combinations come first, so the cap drops the larger ones
+            'sizes: for size in 0..=features.len() {
+                // Indexes of the features in the current combination, in increasing order
+                let mut indexes = (0..size).collect::<Vec<_>>();
+                loop {
+                    if combinations.len() >= max as usize {
+                        break 'sizes;
+                    }
+
+                    let mut args = vec!["--no-default-features".to_string()];
+                    if size > 0 {
+                        let selected = indexes
+                            .iter()
+                            .map(|&i| features[i].as_str())
+                            .collect::<Vec<_>>();
+                        args.push("--features".into());
+                        args.push(selected.join(","));
+                    }
+                    combinations.push(args);
+
+                    // Move to the next combination of the same size, if there is one
+                    let next = (0..size)
+                        .rev()
+                        .find(|&i| indexes[i] < features.len() - size + i);
+                    match next {
+                        Some(i) => {
+                            let first = indexes[i] + 1;
+                            for (offset, index) in indexes[i..].iter_mut().enumerate() {
+                                *index = first + offset;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+            }
+            combinations
+        }
+    }
+}
+
+/// The name of a combination of features in the results.
+pub(super) fn label(args: &[String]) -> String {
+    if args.is_empty() {
+        "default".into()
+    } else {
+        args.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combinations, label};
+    use crate::experiments::FeatureSet;
+
+    #[test]
+    fn test_combinations() {
+        let features = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let labels = |set| {
+            combinations(set, &features)
+                .iter()
+                .map(|args| label(args))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(labels(FeatureSet::Default), vec!["default"]);
+        assert_eq!(labels(FeatureSet::AllFeatures), vec!["--all-features"]);
+        assert_eq!(
+            labels(FeatureSet::NoDefaultFeatures),
+            vec!["--no-default-features"]
+        );
+        assert_eq!(
+            labels(FeatureSet::Powerset(100)),
+            vec![
+                "--no-default-features",
+                "--no-default-features --features a",
+                "--no-default-features --features b",
+                "--no-default-features --features c",
+                "--no-default-features --features a,b",
+                "--no-default-features --features a,c",
+                "--no-default-features --features b,c",
+                "--no-default-features --features a,b,c",
+            ]
+        );
+        assert_eq!(
+            labels(FeatureSet::Powerset(3)),
+            vec![
+                "--no-default-features",
+                "--no-default-features --features a",
+                "--no-default-features --features b",
+            ]
+        );
+        assert_eq!(
+            combinations(FeatureSet::Powerset(3), &[]),
+            vec![vec!["--no-default-features".to_string()]]
+        );
+    }
+}
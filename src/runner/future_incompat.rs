@@ -0,0 +1,148 @@
+use crate::crates::{Crate, RegistryCrate};
+use crate::results::{FutureIncompat, FutureIncompats};
+use cargo_metadata::PackageId;
+use std::collections::HashSet;
+
+const PACKAGE_PREFIX: &str = "The package `";
+const PACKAGE_SUFFIX: &str = "` currently triggers the following future incompatibility lints:";
+const LINT_LEVELS: &[&str] = &["#[allow(", "#[warn(", "#[deny(", "#[forbid("];
+
+/// Collects the future-incompatibility lints from the report cargo prints at the end of the
+/// build when `--future-incompat-report` is passed.
+pub(super) struct FutureIncompatCollector {
+    local_packages: HashSet<(String, String)>,
+    /// The package whose lints are being listed, `None` in the inner option if it's local.
+    current: Option<Option<Crate>>,
+    future_incompat: FutureIncompats,
+}
+
+impl FutureIncompatCollector {
+    pub(super) fn new(local_packages_id: &HashSet<PackageId>) -> Self {
+        FutureIncompatCollector {
+            // Package ids are in the "name version (source)" format
+            local_packages: local_packages_id
+                .iter()
+                .filter_map(|id| {
+                    let mut parts = id.repr.split(' ');
+                    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+                })
+                .collect(),
+            current: None,
+            future_incompat: FutureIncompats::new(),
+        }
+    }
+
+    pub(super) fn process_line(&mut self, line: &str) {
+        if line.starts_with(PACKAGE_PREFIX) && line.ends_with(PACKAGE_SUFFIX) {
+            let package = &line[PACKAGE_PREFIX.len()..line.len() - PACKAGE_SUFFIX.len()];
+            let mut parts = package.splitn(2, ' ');
+            self.current = match (parts.next(), parts.next()) {
+                (Some(name), Some(version)) => {
+                    let version = version.trim_start_matches('v');
+                    if self
+                        .local_packages
+                        .contains(&(name.to_string(), version.to_string()))
+                    {
+                        Some(None)
+                    } else {
+                        Some(Some(Crate::Registry(RegistryCrate {
+                            name: name.to_string(),
+                            version: version.to_string(),
+                        })))
+                    }
+                }
+                _ => None,
+            };
+        } else if line.starts_with('>') {
+            if let Some(dependency) = &self.current {
+                if let Some(lint) = find_lint(line) {
+                    self.future_incompat.insert(FutureIncompat {
+                        lint: lint.to_string(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        } else if !line.trim().is_empty() {
+            self.current = None;
+        }
+    }
+
+    pub(super) fn into_future_incompat(self) -> FutureIncompats {
+        self.future_incompat
+    }
+}
+
+fn find_lint(line: &str) -> Option<&str> {
+    LINT_LEVELS
+        .iter()
+        .filter_map(|level| {
+            let start = line.find(level)? + level.len();
+            let end = line[start..].find(")]")?;
+            Some((start, &line[start..start + end]))
+        })
+        .min_by_key(|&(start, _)| start)
+        .map(|(_, lint)| lint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FutureIncompatCollector;
+    use crate::crates::{Crate, RegistryCrate};
+    use crate::results::FutureIncompat;
+    use cargo_metadata::PackageId;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_collect_future_incompat() {
+        let local_packages_id =
+            vec![
+                serde_json::from_value::<PackageId>(json!("foo 0.1.0 (path+file:///foo)")).unwrap(),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let output = "\
+warning: the following packages contain code that will be rejected by a future version of Rust: foo v0.1.0, nom v4.2.3
+The package `nom v4.2.3` currently triggers the following future incompatibility lints:
+> warning: trailing semicolon in macro used in expression position
+>    --> /cargo/registry/src/nom-4.2.3/src/macros.rs:10:5
+>     |
+>     = note: `#[allow(semicolon_in_expressions_from_macros)]` on by default
+>
+The package `foo v0.1.0` currently triggers the following future incompatibility lints:
+> warning: reference to packed field is unaligned
+>   --> src/lib.rs:12:9
+>    |
+>    = note: `#[warn(unaligned_references)]` on by default
+>
+   Compiling bar v1.0.0
+> = note: `#[warn(dead_code)]` on by default
+";
+
+        let mut collector = FutureIncompatCollector::new(&local_packages_id);
+        for line in output.lines() {
+            collector.process_line(line);
+        }
+
+        assert_eq!(
+            collector
+                .into_future_incompat()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                FutureIncompat {
+                    lint: "semicolon_in_expressions_from_macros".into(),
+                    dependency: Some(Crate::Registry(RegistryCrate {
+                        name: "nom".into(),
+                        version: "4.2.3".into(),
+                    })),
+                },
+                FutureIncompat {
+                    lint: "unaligned_references".into(),
+                    dependency: None,
+                },
+            ]
+        );
+    }
+}
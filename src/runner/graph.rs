@@ -114,10 +114,12 @@ impl TasksGraph {
         &mut self,
         ex: &Experiment,
         db: &DB,
+        state: &RunnerState,
+        config: &Config,
         worker: &str,
     ) -> WalkResult {
         let root = self.root;
-        self.walk_graph(root, ex, db, worker)
+        self.walk_graph(root, ex, db, state, config, worker)
     }
 
     fn walk_graph<DB: WriteResults>(
@@ -125,6 +127,8 @@ impl TasksGraph {
         node: NodeIndex,
         ex: &Experiment,
         db: &DB,
+        state: &RunnerState,
+        config: &Config,
         worker: &str,
     ) -> WalkResult {
         log::trace!(
@@ -140,7 +144,7 @@ impl TasksGraph {
             running: false,
         } = self.graph[node]
         {
-            if !task.needs_exec(ex, db) {
+            if !task.needs_exec(ex, db, state, config) {
                 already_executed = true;
             }
         }
@@ -156,7 +160,7 @@ impl TasksGraph {
 
         let mut blocked = false;
         for neighbor in neighbors.drain(..) {
-            match self.walk_graph(neighbor, ex, db, worker) {
+            match self.walk_graph(neighbor, ex, db, state, config, worker) {
                 WalkResult::Task(id, task) => return WalkResult::Task(id, task),
                 WalkResult::Finished => return WalkResult::Finished,
                 WalkResult::Blocked => blocked = true,
@@ -260,7 +264,7 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
 
     for krate in crates {
         if !ex.ignore_blacklist && config.should_skip(krate) {
-            for tc in &ex.toolchains {
+            for tc in ex.built_toolchains() {
                 let id = graph.add_task(
                     Task {
                         krate: krate.clone(),
@@ -283,7 +287,7 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
 
         let quiet = config.is_quiet(krate);
         let mut builds = Vec::new();
-        for tc in &ex.toolchains {
+        for tc in ex.built_toolchains() {
             let build_id = graph.add_task(
                 Task {
                     krate: krate.clone(),
@@ -292,7 +296,7 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
                             tc: tc.clone(),
                             quiet,
                         },
-                        Mode::BuildAndTest
+                        Mode::BuildAndTest | Mode::EditionMigration
                             if !ex.ignore_blacklist && config.should_skip_tests(krate) =>
                         {
                             TaskStep::BuildOnly {
@@ -300,7 +304,7 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
                                 quiet,
                             }
                         }
-                        Mode::BuildAndTest => TaskStep::BuildAndTest {
+                        Mode::BuildAndTest | Mode::EditionMigration => TaskStep::BuildAndTest {
                             tc: tc.clone(),
                             quiet,
                         },
@@ -317,6 +321,31 @@ pub(super) fn build_graph(ex: &Experiment, crates: &[Crate], config: &Config) ->
                             quiet,
                         },
                         Mode::UnstableFeatures => TaskStep::UnstableFeatures { tc: tc.clone() },
+                        Mode::CompileTime => TaskStep::CompileTime {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::LintDiff => TaskStep::LintDiff {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::FutureIncompat => TaskStep::FutureIncompat {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::Miri => TaskStep::Miri {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::Rustfmt => TaskStep::Rustfmt {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::SemverCheck => TaskStep::SemverCheck {
+                            tc: tc.clone(),
+                            quiet,
+                        },
+                        Mode::Query => TaskStep::Query { tc: tc.clone() },
                     },
                 },
                 &[prepare_id],
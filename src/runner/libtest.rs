@@ -0,0 +1,178 @@
+use crate::results::{TestOutcome, TestOutcomes};
+
+#[derive(Deserialize)]
+struct JsonEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+}
+
+/// Collects the outcome of each test from the output of `cargo test`, understanding both the
+/// JSON and the human-readable output formats of libtest.
+#[derive(Default)]
+pub(super) struct OutcomesCollector {
+    binary: String,
+    outcomes: TestOutcomes,
+}
+
+impl OutcomesCollector {
+    pub(super) fn process_line(&mut self, line: &str) {
+        let line = line.trim();
+
+        if line.starts_with('{') {
+            self.process_json(line);
+        } else if line.starts_with("Running ") {
+            self.binary = binary_name(&line["Running ".len()..]);
+        } else if line.starts_with("Doc-tests ") {
+            self.binary = line.to_lowercase();
+        } else if line.starts_with("test ") {
+            self.process_human(&line["test ".len()..]);
+        }
+    }
+
+    fn process_json(&mut self, line: &str) {
+        let event = match serde_json::from_str::<JsonEvent>(line) {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+        if event.kind != "test" {
+            return;
+        }
+
+        let outcome = match event.event.as_str() {
+            "ok" => TestOutcome::Passed,
+            "failed" | "timeout" => TestOutcome::Failed,
+            "ignored" => TestOutcome::Ignored,
+            _ => return,
+        };
+        if let Some(name) = event.name {
+            self.record(&name, outcome);
+        }
+    }
+
+    fn process_human(&mut self, line: &str) {
+        let idx = match line.rfind(" ... ") {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        let status = &line[idx + " ... ".len()..];
+        let outcome = if status == "ok" {
+            TestOutcome::Passed
+        } else if status == "FAILED" {
+            TestOutcome::Failed
+        } else if status.starts_with("ignored") {
+            TestOutcome::Ignored
+        } else {
+            return;
+        };
+        self.record(&line[..idx], outcome);
+    }
+
+    fn record(&mut self, name: &str, outcome: TestOutcome) {
+        // Tests with the same name can be present in different test binaries
+        self.outcomes
+            .insert(format!("{}: {}", self.binary, name), outcome);
+    }
+
+    pub(super) fn into_outcomes(self) -> TestOutcomes {
+        self.outcomes
+    }
+}
+
+/// Extract a stable name from the `Running` line cargo prints before executing a test binary,
+/// which includes a hash changing between toolchains in the file name.
+fn binary_name(running: &str) -> String {
+    // Recent versions of cargo print the source file before the binary path
+    if let Some(idx) = running.find(" (") {
+        return running[..idx].to_string();
+    }
+
+    let file = running.rsplit('/').next().unwrap_or(running);
+    match file.rfind('-') {
+        Some(idx)
+            if file.len() - idx - 1 == 16
+                && file[idx + 1..].chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            file[..idx].to_string()
+        }
+        _ => file.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutcomesCollector;
+    use crate::results::TestOutcome;
+
+    fn collect(output: &str) -> Vec<(String, TestOutcome)> {
+        let mut collector = OutcomesCollector::default();
+        for line in output.lines() {
+            collector.process_line(line);
+        }
+        collector.into_outcomes().into_iter().collect()
+    }
+
+    #[test]
+    fn test_human_output() {
+        let output = "
+     Running target/debug/deps/foo-0123456789abcdef
+
+running 3 tests
+test tests::first ... ok
+test tests::second ... FAILED
+test tests::third ... ignored, needs network
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+
+     Running unittests src/main.rs (target/debug/deps/foo-fedcba9876543210)
+
+running 1 test
+test tests::first ... ok
+   Doc-tests foo
+
+running 1 test
+test src/lib.rs - Foo::bar (line 12) ... ok
+";
+
+        assert_eq!(
+            collect(output),
+            vec![
+                (
+                    "doc-tests foo: src/lib.rs - Foo::bar (line 12)".to_string(),
+                    TestOutcome::Passed
+                ),
+                ("foo: tests::first".to_string(), TestOutcome::Passed),
+                ("foo: tests::second".to_string(), TestOutcome::Failed),
+                ("foo: tests::third".to_string(), TestOutcome::Ignored),
+                (
+                    "unittests src/main.rs: tests::first".to_string(),
+                    TestOutcome::Passed
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_output() {
+        let output = r#"
+     Running target/debug/deps/foo-0123456789abcdef
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::first" }
+{ "type": "test", "name": "tests::first", "event": "ok" }
+{ "type": "test", "name": "tests::second", "event": "failed", "stdout": "panicked" }
+{ "type": "test", "name": "tests::third", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }
+"#;
+
+        assert_eq!(
+            collect(output),
+            vec![
+                ("foo: tests::first".to_string(), TestOutcome::Passed),
+                ("foo: tests::second".to_string(), TestOutcome::Failed),
+                ("foo: tests::third".to_string(), TestOutcome::Ignored),
+            ]
+        );
+    }
+}
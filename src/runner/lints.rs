@@ -0,0 +1,153 @@
+use crate::results::{Warning, Warnings};
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use cargo_metadata::{Message, PackageId};
+use std::collections::HashSet;
+
+/// Collects the warnings emitted by the compiler for the local packages, from the JSON output
+/// of cargo.
+pub(super) struct WarningsCollector<'a> {
+    local_packages_id: &'a HashSet<PackageId>,
+    warnings: Warnings,
+}
+
+impl<'a> WarningsCollector<'a> {
+    pub(super) fn new(local_packages_id: &'a HashSet<PackageId>) -> Self {
+        WarningsCollector {
+            local_packages_id,
+            warnings: Warnings::new(),
+        }
+    }
+
+    pub(super) fn process_line(&mut self, line: &str) {
+        if !line.starts_with('{') {
+            return;
+        }
+
+        let message = match serde_json::from_str(line) {
+            Ok(Message::CompilerMessage(message)) => message,
+            _ => return,
+        };
+        if !self.local_packages_id.contains(&message.package_id) {
+            return;
+        }
+
+        let diagnostic = message.message;
+        match diagnostic.level {
+            DiagnosticLevel::Warning => {}
+            _ => return,
+        }
+
+        // Summaries like "2 warnings emitted" don't point to any code
+        let span = match diagnostic.spans.iter().find(|span| span.is_primary) {
+            Some(span) => format!(
+                "{}:{}:{}",
+                span.file_name, span.line_start, span.column_start
+            ),
+            None => return,
+        };
+        let lint = match diagnostic.code {
+            Some(code) => code.code,
+            None => diagnostic.message,
+        };
+        self.warnings.insert(Warning { lint, span });
+    }
+
+    pub(super) fn into_warnings(self) -> Warnings {
+        self.warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WarningsCollector;
+    use crate::results::Warning;
+    use cargo_metadata::PackageId;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    fn compiler_message(package: &str, level: &str, code: Option<&str>, span: bool) -> String {
+        let code = match code {
+            Some(code) => json!({ "code": code, "explanation": null }),
+            None => json!(null),
+        };
+        let spans = if span {
+            json!([{
+                "file_name": "src/lib.rs",
+                "byte_start": 100,
+                "byte_end": 103,
+                "line_start": 12,
+                "line_end": 12,
+                "column_start": 9,
+                "column_end": 12,
+                "is_primary": true,
+                "text": [],
+                "label": null,
+                "suggested_replacement": null,
+                "suggestion_applicability": null,
+                "expansion": null,
+            }])
+        } else {
+            json!([])
+        };
+
+        json!({
+            "reason": "compiler-message",
+            "package_id": package,
+            "target": {
+                "kind": ["lib"],
+                "crate_types": ["lib"],
+                "name": "foo",
+                "src_path": "/foo/src/lib.rs",
+                "edition": "2018",
+                "doctest": true,
+            },
+            "message": {
+                "message": "unused variable: `foo`",
+                "code": code,
+                "level": level,
+                "spans": spans,
+                "children": [],
+                "rendered": "warning: unused variable: `foo`",
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_collect_warnings() {
+        let local = "foo 0.1.0 (path+file:///foo)";
+        let dependency = "bar 1.0.0 (registry+https://github.com/rust-lang/crates.io-index)";
+        let local_packages_id = vec![serde_json::from_value::<PackageId>(json!(local)).unwrap()]
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let output = vec![
+            "   Compiling foo v0.1.0".to_string(),
+            compiler_message(local, "warning", Some("unused_variables"), true),
+            compiler_message(local, "warning", None, true),
+            // Summary of the warnings of the crate
+            compiler_message(local, "warning", None, false),
+            compiler_message(local, "error", Some("E0425"), true),
+            compiler_message(dependency, "warning", Some("dead_code"), true),
+        ];
+
+        let mut collector = WarningsCollector::new(&local_packages_id);
+        for line in &output {
+            collector.process_line(line);
+        }
+
+        assert_eq!(
+            collector.into_warnings().into_iter().collect::<Vec<_>>(),
+            vec![
+                Warning {
+                    lint: "unused variable: `foo`".into(),
+                    span: "src/lib.rs:12:9".into(),
+                },
+                Warning {
+                    lint: "unused_variables".into(),
+                    span: "src/lib.rs:12:9".into(),
+                },
+            ]
+        );
+    }
+}
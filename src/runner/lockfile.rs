@@ -0,0 +1,96 @@
+use crate::crates::Crate;
+use crate::experiments::{Experiment, LockfilePolicy, Mode};
+use crate::prelude::*;
+use crate::results::{BrokenReason, Lockfile, TestResult, WriteResults};
+use crate::runner::tasks::TaskCtx;
+use crate::runner::OverrideResult;
+use failure::Error;
+use rustwide::cmd::Command;
+use rustwide::{Build, Workspace};
+use std::fs;
+
+fn unresolvable(err: Error) -> Error {
+    err.context(OverrideResult(TestResult::BrokenCrate(
+        BrokenReason::Unresolvable,
+    )))
+    .into()
+}
+
+/// Resolve the dependencies of the crate according to the lockfile policy of the experiment,
+/// returning the resulting lockfile. This is done once while preparing the crate, so that every
+/// toolchain builds it with the same dependencies.
+pub(super) fn resolve(
+    workspace: &Workspace,
+    ex: &Experiment,
+    krate: &Crate,
+) -> Fallible<Option<String>> {
+    let update_args: &[&str] = match ex.lockfile {
+        LockfilePolicy::AsPrepared => return Ok(None),
+        LockfilePolicy::Update => &["update"],
+        LockfilePolicy::MinimalVersions => &["update", "-Zminimal-versions"],
+    };
+    // Query experiments don't build the crates, so no toolchain is installed
+    let toolchain = match ex.built_toolchains().first() {
+        Some(toolchain) if ex.mode != Mode::Query => toolchain,
+        _ => return Ok(None),
+    };
+
+    let source_dir = tempfile::tempdir()?;
+    krate
+        .to_rustwide()
+        .copy_source_to(workspace, source_dir.path())?;
+
+    // Resolving the dependencies needs network access, so cargo is run outside the sandbox like
+    // it is while preparing the crate
+    let mut cmd = Command::new(workspace, toolchain.source.cargo())
+        .args(update_args)
+        .cd(source_dir.path());
+    // `-Z` flags are otherwise only accepted by nightly cargo
+    if ex.lockfile == LockfilePolicy::MinimalVersions {
+        cmd = cmd.env("RUSTC_BOOTSTRAP", "1");
+    }
+    cmd.run().map_err(unresolvable)?;
+
+    Ok(Some(fs::read_to_string(
+        source_dir.path().join("Cargo.lock"),
+    )?))
+}
+
+/// Replace the lockfile generated while preparing the build with the one resolved for the crate,
+/// if any.
+pub(super) fn write_resolved<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+) -> Fallible<()> {
+    let source_dir = build_env.host_source_dir();
+    let resolved = ctx.state.lock().lockfiles.get(ctx.krate).cloned();
+    if let Some(content) = resolved {
+        fs::write(source_dir.join("Cargo.lock"), content)?;
+        Command::new(ctx.workspace, ctx.toolchain.source.cargo())
+            .args(&["fetch", "--locked"])
+            .cd(&source_dir)
+            .run()
+            .map_err(unresolvable)?;
+    }
+    Ok(())
+}
+
+/// Replace the lockfile generated while preparing the build with the one resolved for the crate,
+/// if any, and record the lockfile the crate is going to be built with.
+pub(super) fn update<DB: WriteResults>(ctx: &TaskCtx<DB>, build_env: &Build) -> Fallible<()> {
+    write_resolved(ctx, build_env)?;
+
+    let path = build_env.host_source_dir().join("Cargo.lock");
+    if path.exists() {
+        let lockfile = Lockfile {
+            policy: ctx.experiment.lockfile,
+            content: fs::read_to_string(&path)?,
+        };
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.lockfile = Some(lockfile)
+            })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::prelude::*;
+use crate::toolchain::Toolchain;
+use rustwide::cmd::{Command, MountKind, SandboxBuilder};
+use rustwide::Workspace;
+use std::path::{Path, PathBuf};
+
+/// Where the standard library built for Miri is mounted inside the sandbox.
+pub(super) const SANDBOX_SYSROOT_DIR: &str = "/opt/crater/miri-sysroot";
+
+fn sysroot_dir(toolchain: &Toolchain) -> PathBuf {
+    crate::dirs::WORK_DIR
+        .join("miri-sysroots")
+        .join(toolchain.to_path_component())
+}
+
+/// Build the standard library Miri interprets the crates with. Fetching its dependencies needs
+/// network access, so it's built once per toolchain outside the sandbox, and then mounted in the
+/// sandbox of every crate with `mount_sysroot`.
+pub(super) fn setup(workspace: &Workspace, toolchain: &Toolchain) -> Fallible<()> {
+    let mut args = vec!["miri", "setup"];
+    if let Some(ref target) = toolchain.target {
+        args.push("--target");
+        args.push(target);
+    }
+
+    Command::new(workspace, toolchain.source.cargo())
+        .args(&args)
+        .env("MIRI_SYSROOT", sysroot_dir(toolchain))
+        .run()
+        .with_context(|_| format!("failed to set up miri for {}", toolchain))?;
+    Ok(())
+}
+
+pub(super) fn mount_sysroot(sandbox: SandboxBuilder, toolchain: &Toolchain) -> SandboxBuilder {
+    sandbox.mount(
+        &sysroot_dir(toolchain),
+        Path::new(SANDBOX_SYSROOT_DIR),
+        MountKind::ReadOnly,
+    )
+}
@@ -1,16 +1,29 @@
+mod classify;
+mod compile_time;
+mod edition;
+mod features;
+mod future_incompat;
 mod graph;
+mod libtest;
+mod lints;
+mod lockfile;
+mod miri;
+mod public_api;
+mod query;
+mod rustfmt;
 mod tasks;
 mod test;
 mod unstable_features;
 mod worker;
 
 use crate::config::Config;
-use crate::crates::Crate;
+use crate::crates::{Crate, RegistryCrate};
 use crate::experiments::{Experiment, Mode};
 use crate::prelude::*;
 use crate::results::{TestResult, WriteResults};
 use crate::runner::graph::build_graph;
 use crate::runner::worker::{DiskSpaceWatcher, Worker};
+use crate::toolchain::Toolchain;
 use crossbeam_utils::thread::{scope, ScopedJoinHandle};
 use rustwide::logging::LogStorage;
 use rustwide::Workspace;
@@ -19,6 +32,11 @@ use std::path::Path;
 use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
+use tempfile::TempDir;
+
+/// Where rustwide mounts the source code of the crate and its target directory inside the sandbox.
+const SANDBOX_SOURCE_DIR: &str = "/opt/rustwide/workdir";
+const SANDBOX_TARGET_DIR: &str = "/opt/rustwide/target";
 
 const DISK_SPACE_WATCHER_INTERVAL: Duration = Duration::from_secs(300);
 const DISK_SPACE_WATCHER_THRESHOLD: f32 = 0.85;
@@ -29,6 +47,14 @@ pub struct OverrideResult(TestResult);
 
 struct RunnerStateInner {
     prepare_logs: HashMap<Crate, LogStorage>,
+    /// The lockfiles resolved while preparing the crates, shared by all the toolchains.
+    lockfiles: HashMap<Crate, String>,
+    /// The source code of the crates migrated to the next edition while preparing them.
+    migrated_sources: HashMap<Crate, TempDir>,
+    /// The releases the public API of the crates is compared with, looked up before running.
+    previous_releases: HashMap<Crate, RegistryCrate>,
+    /// The compilers the installed toolchains resolve to, which the cached results are keyed on.
+    compilers: HashMap<Toolchain, String>,
 }
 
 struct RunnerState {
@@ -40,6 +66,10 @@ impl RunnerState {
         RunnerState {
             inner: Mutex::new(RunnerStateInner {
                 prepare_logs: HashMap::new(),
+                lockfiles: HashMap::new(),
+                migrated_sources: HashMap::new(),
+                previous_releases: HashMap::new(),
+                compilers: HashMap::new(),
             }),
         }
     }
@@ -65,11 +95,35 @@ pub fn run_ex<DB: WriteResults + Sync>(
     let graph = Mutex::new(build_graph(ex, crates, config));
 
     info!("preparing the execution...");
-    for tc in &ex.toolchains {
+    // Query experiments only search the source code of the crates, and the crates are migrated to
+    // the next edition with the start toolchain even if its results come from a baseline
+    let toolchains: &[_] = match ex.mode {
+        Mode::Query => &[],
+        Mode::EditionMigration => &ex.toolchains,
+        _ => ex.built_toolchains(),
+    };
+    let mut compilers = HashMap::new();
+    for tc in toolchains {
         tc.install(workspace)?;
+        compilers.insert(tc.clone(), tc.resolve_compiler(workspace)?);
+        if let Some(ref target) = tc.target {
+            tc.add_target(workspace, target)?;
+        }
         if ex.mode == Mode::Clippy {
             tc.add_component(workspace, "clippy")?;
         }
+        if ex.mode == Mode::Rustfmt {
+            tc.add_component(workspace, "rustfmt")?;
+        }
+        if ex.mode == Mode::Miri {
+            // Miri builds its own standard library from the sources
+            tc.add_component(workspace, "miri")?;
+            tc.add_component(workspace, "rust-src")?;
+            miri::setup(workspace, tc)?;
+        }
+    }
+    if ex.mode == Mode::CompileTime {
+        compile_time::check_time_installed(workspace)?;
     }
 
     info!("running tasks in {} threads...", threads_count);
@@ -78,6 +132,11 @@ pub fn run_ex<DB: WriteResults + Sync>(
     let parked_threads: Mutex<HashMap<thread::ThreadId, thread::Thread>> =
         Mutex::new(HashMap::new());
     let state = RunnerState::new();
+    state.lock().compilers = compilers;
+    if ex.mode == Mode::SemverCheck {
+        info!("looking up the previous releases of the crates...");
+        state.lock().previous_releases = public_api::previous_releases(crates)?;
+    }
 
     let workers = (0..threads_count)
         .map(|i| {
@@ -128,12 +187,17 @@ pub fn run_ex<DB: WriteResults + Sync>(
 
     // Only the root node must be present
     let mut g = graph.lock().unwrap();
-    assert!(g.next_task(ex, db, "master").is_finished());
+    assert!(g.next_task(ex, db, &state, config, "master").is_finished());
     assert_eq!(g.pending_crates_count(), 0);
 
     Ok(())
 }
 
+/// The path inside the sandbox of a directory living in the target directory of the build.
+fn sandbox_target_path(dir: &str) -> String {
+    format!("{}/{}", SANDBOX_TARGET_DIR, dir)
+}
+
 fn join_threads<'a, I>(iter: I) -> bool
 where
     I: Iterator<Item = ScopedJoinHandle<'a, Fallible<()>>>,
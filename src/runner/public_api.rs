@@ -0,0 +1,370 @@
+use crate::crates::{published_versions, update_index, Crate, RegistryCrate};
+use crate::prelude::*;
+use crate::results::{BreakingChange, WriteResults};
+use crate::runner::sandbox_target_path;
+use crate::runner::tasks::TaskCtx;
+use cargo_metadata::Version;
+use remove_dir_all::remove_dir_all;
+use rustwide::cmd::Command;
+use rustwide::{Build, Crate as RustwideCrate};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// The directory inside the target directory the previous release of the crate is extracted in.
+const BASELINE_DIR: &str = "semver-baseline";
+
+fn semver_compatible(a: &Version, b: &Version) -> bool {
+    if a.major != b.major {
+        false
+    } else if a.major > 0 {
+        true
+    } else {
+        // 0.0.x releases are never compatible with each other
+        a.minor == b.minor && a.minor > 0
+    }
+}
+
+/// The newest release older than `current` and semver-compatible with it, ignoring prereleases.
+fn previous_compatible<'a>(
+    current: &Version,
+    versions: impl Iterator<Item = &'a str>,
+) -> Option<Version> {
+    versions
+        .filter_map(|version| Version::parse(version).ok())
+        .filter(|version| {
+            !version.is_prerelease() && version < current && semver_compatible(version, current)
+        })
+        .max()
+}
+
+/// Look up in the crates.io index the releases the public API of the crates should be compared
+/// with, for the crates published on crates.io that have one.
+pub(super) fn previous_releases(crates: &[Crate]) -> Fallible<HashMap<Crate, RegistryCrate>> {
+    update_index()?;
+    let names = crates
+        .iter()
+        .filter_map(|krate| match krate {
+            Crate::Registry(krate) => Some(krate.name.clone()),
+            _ => None,
+        })
+        .collect();
+    let versions = published_versions(&names)?;
+
+    let mut releases = HashMap::new();
+    for krate in crates {
+        let registry_crate = match krate {
+            Crate::Registry(krate) => krate,
+            _ => continue,
+        };
+        let current = match Version::parse(&registry_crate.version) {
+            Ok(current) => current,
+            Err(_) => continue,
+        };
+        let published = versions
+            .get(&registry_crate.name)
+            .into_iter()
+            .flatten()
+            .map(|version| version.as_str());
+        if let Some(previous) = previous_compatible(&current, published) {
+            releases.insert(
+                krate.clone(),
+                RegistryCrate {
+                    name: registry_crate.name.clone(),
+                    version: previous.to_string(),
+                },
+            );
+        }
+    }
+    Ok(releases)
+}
+
+/// Extract the source code of the previous release in the target directory of the build and
+/// fetch its dependencies, returning the path of its manifest inside the sandbox.
+pub(super) fn prepare_baseline<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    baseline: &RegistryCrate,
+) -> Fallible<String> {
+    let dir = build_env.host_target_dir().join(BASELINE_DIR);
+    if dir.exists() {
+        remove_dir_all(&dir)?;
+    }
+    let krate = RustwideCrate::crates_io(&baseline.name, &baseline.version);
+    krate.fetch(ctx.workspace)?;
+    krate.copy_source_to(ctx.workspace, &dir)?;
+
+    // Resolving and fetching the dependencies needs network access, so cargo is run outside
+    // the sandbox like it is while preparing the crate
+    let generate_args: &[&str] = &["generate-lockfile"];
+    let fetch_args: &[&str] = &["fetch", "--locked"];
+    for args in &[generate_args, fetch_args] {
+        Command::new(ctx.workspace, ctx.toolchain.source.cargo())
+            .args(*args)
+            .cd(&dir)
+            .run()?;
+    }
+
+    Ok(format!("{}/Cargo.toml", sandbox_target_path(BASELINE_DIR)))
+}
+
+/// Remove the previous release extracted by `prepare_baseline`.
+pub(super) fn cleanup_baseline(build_env: &Build) -> Fallible<()> {
+    let dir = build_env.host_target_dir().join(BASELINE_DIR);
+    if dir.exists() {
+        remove_dir_all(&dir)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+struct ApiItem {
+    kind: String,
+    /// The signature of functions and methods, without the rustdoc IDs.
+    signature: Option<Value>,
+    /// The names of the items of a trait its implementations must provide.
+    required_items: BTreeSet<String>,
+}
+
+/// The public API of a crate, extracted from the rustdoc JSON output of its library. Items are
+/// identified by their canonical path, and the inherent methods by the path of their type.
+#[derive(Debug, Default)]
+pub(super) struct PublicApi {
+    items: BTreeMap<String, ApiItem>,
+}
+
+impl PublicApi {
+    /// Read the API from the JSON file rustdoc wrote in the `doc` directory.
+    pub(super) fn read(doc_dir: &Path) -> Fallible<PublicApi> {
+        for entry in fs::read_dir(doc_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+                let doc: Value = serde_json::from_slice(&fs::read(&path)?)?;
+                return Ok(PublicApi::from_json(&doc));
+            }
+        }
+        bail!("rustdoc didn't output any JSON file");
+    }
+
+    fn from_json(doc: &Value) -> PublicApi {
+        let index = &doc["index"];
+        let get = |id: &Value| match id {
+            Value::String(id) => index.get(id),
+            Value::Number(id) => index.get(id.to_string()),
+            _ => None,
+        };
+
+        let mut items = BTreeMap::new();
+        let paths = match doc["paths"].as_object() {
+            Some(paths) => paths,
+            None => return PublicApi { items },
+        };
+        for (id, summary) in paths {
+            // Only items defined by the crate itself are part of its API
+            let item = match index.get(id) {
+                Some(item) if summary["crate_id"] == 0 => item,
+                _ => continue,
+            };
+            let path = summary["path"]
+                .as_array()
+                .map(|segments| {
+                    segments
+                        .iter()
+                        .filter_map(|segment| segment.as_str())
+                        .collect::<Vec<_>>()
+                        .join("::")
+                })
+                .unwrap_or_default();
+            let kind = summary["kind"].as_str().unwrap_or("unknown").to_string();
+
+            let inner = &item["inner"];
+            let mut signature = None;
+            let mut required_items = BTreeSet::new();
+            if let Some(function) = inner.get("function") {
+                signature = Some(strip_ids(function));
+            } else if let Some(trait_items) = inner["trait"]["items"].as_array() {
+                required_items = trait_items
+                    .iter()
+                    .filter_map(get)
+                    .filter(|trait_item| is_required(trait_item))
+                    .filter_map(|trait_item| trait_item["name"].as_str())
+                    .map(|name| name.to_string())
+                    .collect();
+            } else {
+                let impls = ["struct", "enum", "union"]
+                    .iter()
+                    .filter_map(|kind| inner[kind]["impls"].as_array())
+                    .flatten()
+                    .filter_map(get)
+                    .map(|imp| &imp["inner"]["impl"])
+                    .filter(|imp| imp["trait"].is_null());
+                for imp in impls {
+                    let methods = imp["items"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(get)
+                        .filter(|method| method["visibility"] == "public");
+                    for method in methods {
+                        if let (Some(name), Some(function)) =
+                            (method["name"].as_str(), method["inner"].get("function"))
+                        {
+                            items.insert(
+                                format!("{}::{}", path, name),
+                                ApiItem {
+                                    kind: "method".into(),
+                                    signature: Some(strip_ids(function)),
+                                    required_items: BTreeSet::new(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+
+            items.insert(
+                path,
+                ApiItem {
+                    kind,
+                    signature,
+                    required_items,
+                },
+            );
+        }
+
+        PublicApi { items }
+    }
+
+    /// The changes to the API breaking the code written against the previous release.
+    pub(super) fn breaking_changes(&self, previous: &PublicApi) -> BTreeSet<BreakingChange> {
+        let mut changes = BTreeSet::new();
+        for (path, old) in &previous.items {
+            let new = match self.items.get(path) {
+                Some(new) => new,
+                None => {
+                    changes.insert(BreakingChange::Removed(path.clone()));
+                    continue;
+                }
+            };
+
+            if old.kind != new.kind || old.signature != new.signature {
+                changes.insert(BreakingChange::SignatureChanged(path.clone()));
+            }
+            for item in new.required_items.difference(&old.required_items) {
+                changes.insert(BreakingChange::NewRequiredItem(format!(
+                    "{}::{}",
+                    path, item
+                )));
+            }
+        }
+        changes
+    }
+}
+
+/// Whether the implementations of a trait must provide the trait item.
+fn is_required(item: &Value) -> bool {
+    let inner = &item["inner"];
+    if let Some(function) = inner.get("function") {
+        function["has_body"] == false
+    } else if let Some(ty) = inner.get("assoc_type") {
+        ty["type"].is_null() && ty["default"].is_null()
+    } else if let Some(constant) = inner.get("assoc_const") {
+        constant["value"].is_null() && constant["default"].is_null()
+    } else {
+        false
+    }
+}
+
+/// Remove the rustdoc IDs, which differ between two builds of the same code.
+fn strip_ids(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| *key != "id")
+                .map(|(key, value)| (key.clone(), strip_ids(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(strip_ids).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{previous_compatible, PublicApi};
+    use crate::results::BreakingChange;
+    use cargo_metadata::Version;
+    use serde_json::json;
+
+    #[test]
+    fn test_previous_compatible() {
+        let versions = [
+            "0.0.1",
+            "0.0.2",
+            "0.1.0",
+            "0.1.1",
+            "0.2.0",
+            "1.0.0",
+            "1.1.0",
+            "1.2.0-beta.1",
+            "1.2.0",
+            "2.0.0",
+        ];
+        let previous = |current: &str| {
+            previous_compatible(&Version::parse(current).unwrap(), versions.iter().cloned())
+                .map(|version| version.to_string())
+        };
+
+        assert_eq!(previous("1.2.0"), Some("1.1.0".into()));
+        assert_eq!(previous("1.0.0"), None);
+        assert_eq!(previous("2.0.0"), None);
+        assert_eq!(previous("0.1.1"), Some("0.1.0".into()));
+        assert_eq!(previous("0.2.0"), None);
+        assert_eq!(previous("0.0.2"), None);
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let function = |has_body: bool, output: &str| {
+            json!({"function": {
+                "sig": {"inputs": [], "output": {"resolved_path": {"path": output, "id": 42}}},
+                "has_body": has_body,
+            }})
+        };
+        let api = |items: serde_json::Value| {
+            PublicApi::from_json(&json!({
+                "index": items,
+                "paths": {
+                    "0": {"crate_id": 0, "path": ["foo", "bar"], "kind": "function"},
+                    "1": {"crate_id": 0, "path": ["foo", "baz"], "kind": "function"},
+                    "2": {"crate_id": 0, "path": ["foo", "Trait"], "kind": "trait"},
+                    "9": {"crate_id": 1, "path": ["std", "String"], "kind": "struct"},
+                },
+            }))
+        };
+
+        let old = api(json!({
+            "0": {"name": "bar", "inner": function(true, "u32")},
+            "1": {"name": "baz", "inner": function(true, "u32")},
+            "2": {"name": "Trait", "inner": {"trait": {"items": [3]}}},
+            "3": {"name": "provided", "inner": function(true, "u32")},
+        }));
+        let new = api(json!({
+            "1": {"name": "baz", "inner": function(true, "u64")},
+            "2": {"name": "Trait", "inner": {"trait": {"items": [3, 4]}}},
+            "3": {"name": "provided", "inner": function(true, "u32")},
+            "4": {"name": "required", "inner": function(false, "u32")},
+        }));
+
+        assert!(old.breaking_changes(&old).is_empty());
+        assert_eq!(
+            new.breaking_changes(&old),
+            btreeset![
+                BreakingChange::Removed("foo::bar".into()),
+                BreakingChange::SignatureChanged("foo::baz".into()),
+                BreakingChange::NewRequiredItem("foo::Trait::required".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::experiments::Query;
+use crate::prelude::*;
+use crate::results::{EncodingType, MatchedLine, QueryResults, TestResult, WriteResults};
+use crate::runner::tasks::TaskCtx;
+use crate::runner::unstable_features::is_hidden;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// How many matched lines are recorded for each query, as common patterns could otherwise match
+/// thousands of lines in a single crate.
+const MAX_MATCHED_LINES: usize = 20;
+/// Longer lines are truncated, as they're usually generated or minified code.
+const MAX_LINE_LENGTH: usize = 256;
+
+fn truncate(line: &str) -> String {
+    let line = line.trim();
+    match line.char_indices().nth(MAX_LINE_LENGTH) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// Search all the Rust source files in the directory with the queries, line by line.
+fn search_source(queries: &[Query], dir: &Path) -> Fallible<QueryResults> {
+    let mut results: QueryResults = queries
+        .iter()
+        .map(|query| (query.name.clone(), Default::default()))
+        .collect();
+
+    // Sorting the entries records the same matched lines every time the crate is searched, and
+    // the root is never skipped as temporary directories are hidden themselves
+    let entries = WalkDir::new(dir)
+        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_hidden(e));
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type().is_file()
+            || entry.path().extension().and_then(|ext| ext.to_str()) != Some("rs")
+        {
+            continue;
+        }
+
+        let file = entry
+            .path()
+            .strip_prefix(dir)?
+            .to_string_lossy()
+            .into_owned();
+        // Invalid UTF-8 is often found in test fixtures, and shouldn't prevent searching the
+        // rest of the file
+        let content = fs::read(entry.path())?;
+        let content = String::from_utf8_lossy(&content);
+
+        for (idx, line) in content.lines().enumerate() {
+            for query in queries {
+                let matches = query.regex.find_iter(line).count() as u32;
+                if matches == 0 {
+                    continue;
+                }
+
+                let result = results.get_mut(&query.name).unwrap();
+                result.matches += matches;
+                if result.lines.len() < MAX_MATCHED_LINES {
+                    result.lines.push(MatchedLine {
+                        file: file.clone(),
+                        line: idx as u32 + 1,
+                        content: truncate(line),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Search the source code of the crate with the queries of the experiment and record the matches.
+/// The crate is never built, so the toolchain is only used to key the results.
+pub(super) fn run_query<DB: WriteResults>(ctx: &TaskCtx<DB>) -> Fallible<()> {
+    if let Some(res) = ctx
+        .db
+        .get_result(ctx.experiment, ctx.toolchain, ctx.krate)?
+    {
+        info!("skipping crate {}. existing result: {}", ctx.krate, res);
+        return Ok(());
+    }
+
+    let log_storage = ctx
+        .state
+        .lock()
+        .prepare_logs
+        .get(&ctx.krate)
+        .map(|s| s.duplicate());
+    // The toolchains aren't installed to search the source code, so the results aren't cached
+    ctx.db.record_result(
+        ctx.experiment,
+        ctx.toolchain,
+        None,
+        ctx.krate,
+        log_storage,
+        ctx.config,
+        EncodingType::Plain,
+        || {
+            info!(
+                "searching {} with the queries of {}",
+                ctx.krate, ctx.experiment.name
+            );
+            let source_dir = tempfile::tempdir()?;
+            ctx.krate
+                .to_rustwide()
+                .copy_source_to(ctx.workspace, source_dir.path())?;
+
+            let results = search_source(&ctx.experiment.queries, source_dir.path())?;
+            for (name, result) in &results {
+                info!("query {}: {} matches", name, result.matches);
+                for line in &result.lines {
+                    info!("{}:{}: {}", line.file, line.line, line.content);
+                }
+            }
+            ctx.db
+                .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                    extras.query_results = results
+                })?;
+
+            Ok(TestResult::TestPass)
+        },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{search_source, MAX_MATCHED_LINES};
+    use crate::results::MatchedLine;
+    use std::fs;
+
+    #[test]
+    fn test_search_source() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(
+            dir.path().join("src").join("lib.rs"),
+            "fn foo() {\n    unsafe { bar() }; unsafe { baz() }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("src").join("many.rs"),
+            "unsafe\n".repeat(30),
+        )
+        .unwrap();
+        fs::write(dir.path().join(".git").join("hidden.rs"), "unsafe").unwrap();
+        fs::write(dir.path().join("README.md"), "unsafe").unwrap();
+
+        let queries = vec![
+            "unsafe=unsafe".parse().unwrap(),
+            "transmute=mem::transmute".parse().unwrap(),
+        ];
+        let results = search_source(&queries, dir.path()).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results["transmute"].lines.is_empty());
+        assert_eq!(results["transmute"].matches, 0);
+
+        let unsafe_result = &results["unsafe"];
+        assert_eq!(unsafe_result.matches, 32);
+        assert_eq!(unsafe_result.lines.len(), MAX_MATCHED_LINES);
+        assert!(unsafe_result.lines.contains(&MatchedLine {
+            file: "src/lib.rs".into(),
+            line: 2,
+            content: "unsafe { bar() }; unsafe { baz() }".into(),
+        }));
+    }
+}
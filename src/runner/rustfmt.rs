@@ -0,0 +1,105 @@
+use crate::results::RustfmtDiff;
+use crate::runner::SANDBOX_SOURCE_DIR;
+use std::collections::BTreeSet;
+
+/// Collects the diff hunks printed by `cargo fmt -- --check`.
+#[derive(Default)]
+pub(super) struct DiffCollector {
+    files: BTreeSet<String>,
+    hunks: Vec<String>,
+    in_hunk: bool,
+}
+
+impl DiffCollector {
+    pub(super) fn process_line(&mut self, line: &str) {
+        if line.starts_with("Diff in ") {
+            let header = line.replace(&format!("{}/", SANDBOX_SOURCE_DIR), "");
+            if let Some(file) = diff_file(&header["Diff in ".len()..]) {
+                self.files.insert(file.to_string());
+            }
+            self.hunks.push(header);
+            self.in_hunk = true;
+        } else if self.in_hunk
+            && (line.starts_with(' ') || line.starts_with('+') || line.starts_with('-'))
+        {
+            self.hunks.push(line.to_string());
+        } else {
+            self.in_hunk = false;
+        }
+    }
+
+    pub(super) fn into_diff(self) -> Option<RustfmtDiff> {
+        if self.files.is_empty() {
+            None
+        } else {
+            Some(RustfmtDiff {
+                files_changed: self.files.len() as u32,
+                hunks: self.hunks.join("\n"),
+            })
+        }
+    }
+}
+
+/// The file of a diff header, printed as `FILE at line N:` by older versions of rustfmt and as
+/// `FILE:N:` by newer ones.
+fn diff_file(header: &str) -> Option<&str> {
+    if let Some(idx) = header.rfind(" at line ") {
+        Some(&header[..idx])
+    } else {
+        let header = header.trim_end_matches(':');
+        header.rfind(':').map(|idx| &header[..idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiffCollector;
+    use crate::results::RustfmtDiff;
+
+    #[test]
+    fn test_collect_diff() {
+        let collect = |output: &str| {
+            let mut collector = DiffCollector::default();
+            for line in output.lines() {
+                collector.process_line(line);
+            }
+            collector.into_diff()
+        };
+
+        assert_eq!(collect("[INFO] running `cargo fmt`"), None);
+
+        let output = "\
+Diff in /opt/rustwide/workdir/src/lib.rs at line 1:
+-fn foo( ) {}
++fn foo() {}
+
+Diff in /opt/rustwide/workdir/src/lib.rs:12:
+ fn bar() {
+-    baz( );
++    baz();
+ }
+Diff in /opt/rustwide/workdir/src/main.rs:3:
+-fn main() { }
++fn main() {}
+[INFO] running `cargo fmt` finished";
+        assert_eq!(
+            collect(output),
+            Some(RustfmtDiff {
+                files_changed: 2,
+                hunks: "\
+Diff in src/lib.rs at line 1:
+-fn foo( ) {}
++fn foo() {}
+Diff in src/lib.rs:12:
+ fn bar() {
+-    baz( );
++    baz();
+ }
+Diff in src/main.rs:3:
+-fn main() { }
++fn main() {}"
+                    .into(),
+            })
+        );
+    }
+}
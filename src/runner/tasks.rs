@@ -1,10 +1,10 @@
 use crate::config::Config;
 use crate::crates::{Crate, GitHubRepo};
-use crate::experiments::Experiment;
+use crate::experiments::{Experiment, Mode};
 use crate::prelude::*;
 use crate::results::{EncodingType, TestResult, WriteResults};
 use crate::runner::test::detect_broken;
-use crate::runner::{test, RunnerState};
+use crate::runner::{edition, lockfile, test, RunnerState};
 use crate::toolchain::Toolchain;
 use crate::utils;
 use rustwide::{BuildDirectory, Workspace};
@@ -14,6 +14,7 @@ use rustwide::logging::{self, LogStorage};
 use std::fmt;
 
 pub(super) struct TaskCtx<'ctx, DB: WriteResults + 'ctx> {
+    pub(super) workspace: &'ctx Workspace,
     pub(super) build_dir: &'ctx Mutex<BuildDirectory>,
     pub(super) config: &'ctx Config,
     pub(super) db: &'ctx DB,
@@ -22,10 +23,13 @@ pub(super) struct TaskCtx<'ctx, DB: WriteResults + 'ctx> {
     pub(super) krate: &'ctx Crate,
     pub(super) state: &'ctx RunnerState,
     pub(super) quiet: bool,
+    /// The Cargo flags selecting the combination of features being built.
+    pub(super) features: &'ctx [String],
 }
 
 impl<'ctx, DB: WriteResults + 'ctx> TaskCtx<'ctx, DB> {
     fn new(
+        workspace: &'ctx Workspace,
         build_dir: &'ctx Mutex<BuildDirectory>,
         config: &'ctx Config,
         db: &'ctx DB,
@@ -36,6 +40,7 @@ impl<'ctx, DB: WriteResults + 'ctx> TaskCtx<'ctx, DB> {
         quiet: bool,
     ) -> Self {
         TaskCtx {
+            workspace,
             build_dir,
             config,
             db,
@@ -44,6 +49,7 @@ impl<'ctx, DB: WriteResults + 'ctx> TaskCtx<'ctx, DB> {
             krate,
             state,
             quiet,
+            features: &[],
         }
     }
 }
@@ -58,6 +64,13 @@ pub(super) enum TaskStep {
     Clippy { tc: Toolchain, quiet: bool },
     Rustdoc { tc: Toolchain, quiet: bool },
     UnstableFeatures { tc: Toolchain },
+    CompileTime { tc: Toolchain, quiet: bool },
+    LintDiff { tc: Toolchain, quiet: bool },
+    FutureIncompat { tc: Toolchain, quiet: bool },
+    Miri { tc: Toolchain, quiet: bool },
+    Rustfmt { tc: Toolchain, quiet: bool },
+    SemverCheck { tc: Toolchain, quiet: bool },
+    Query { tc: Toolchain },
 }
 
 impl fmt::Debug for TaskStep {
@@ -72,6 +85,15 @@ impl fmt::Debug for TaskStep {
             TaskStep::Clippy { ref tc, quiet } => ("clippy", quiet, Some(tc)),
             TaskStep::Rustdoc { ref tc, quiet } => ("doc", quiet, Some(tc)),
             TaskStep::UnstableFeatures { ref tc } => ("find unstable features on", false, Some(tc)),
+            TaskStep::CompileTime { ref tc, quiet } => ("measure compile time", quiet, Some(tc)),
+            TaskStep::LintDiff { ref tc, quiet } => ("collect warnings", quiet, Some(tc)),
+            TaskStep::FutureIncompat { ref tc, quiet } => {
+                ("collect future-incompat lints", quiet, Some(tc))
+            }
+            TaskStep::Miri { ref tc, quiet } => ("miri test", quiet, Some(tc)),
+            TaskStep::Rustfmt { ref tc, quiet } => ("check formatting", quiet, Some(tc)),
+            TaskStep::SemverCheck { ref tc, quiet } => ("check semver", quiet, Some(tc)),
+            TaskStep::Query { ref tc } => ("search source on", false, Some(tc)),
         };
 
         write!(f, "{}", name)?;
@@ -97,7 +119,13 @@ impl fmt::Debug for Task {
 }
 
 impl Task {
-    pub(super) fn needs_exec<DB: WriteResults>(&self, ex: &Experiment, db: &DB) -> bool {
+    pub(super) fn needs_exec<DB: WriteResults>(
+        &self,
+        ex: &Experiment,
+        db: &DB,
+        state: &RunnerState,
+        config: &Config,
+    ) -> bool {
         // If an error happens while checking if the task should be executed, the error is ignored
         // and the function returns true.
         match self.step {
@@ -106,15 +134,33 @@ impl Task {
             // It will not be executed if all the dependent tasks are already executed, since the
             // runner will not reach the prepare task in that case.
             TaskStep::Prepare => true,
-            // Build tasks should only be executed if there are no results for them
-            TaskStep::Skip { ref tc }
-            | TaskStep::BuildAndTest { ref tc, .. }
+            // Skipped crates should only be recorded if there are no results for them
+            TaskStep::Skip { ref tc } => {
+                db.get_result(ex, tc, &self.krate).unwrap_or(None).is_none()
+            }
+            // Build tasks should only be executed if there are no results for them, and no
+            // result built by the same compiler can be reused from another experiment
+            TaskStep::BuildAndTest { ref tc, .. }
             | TaskStep::BuildOnly { ref tc, .. }
             | TaskStep::CheckOnly { ref tc, .. }
             | TaskStep::Clippy { ref tc, .. }
             | TaskStep::Rustdoc { ref tc, .. }
-            | TaskStep::UnstableFeatures { ref tc } => {
+            | TaskStep::UnstableFeatures { ref tc }
+            | TaskStep::CompileTime { ref tc, .. }
+            | TaskStep::LintDiff { ref tc, .. }
+            | TaskStep::FutureIncompat { ref tc, .. }
+            | TaskStep::Miri { ref tc, .. }
+            | TaskStep::Rustfmt { ref tc, .. }
+            | TaskStep::SemverCheck { ref tc, .. }
+            | TaskStep::Query { ref tc } => {
+                let compiler = state.lock().compilers.get(tc).cloned();
                 db.get_result(ex, tc, &self.krate).unwrap_or(None).is_none()
+                    && compiler
+                        .and_then(|compiler| {
+                            db.reuse_cached_result(ex, tc, &compiler, &self.krate, config)
+                                .unwrap_or(None)
+                        })
+                        .is_none()
             }
         }
     }
@@ -136,15 +182,24 @@ impl Task {
             | TaskStep::CheckOnly { ref tc, .. }
             | TaskStep::Clippy { ref tc, .. }
             | TaskStep::Rustdoc { ref tc, .. }
-            | TaskStep::UnstableFeatures { ref tc } => {
+            | TaskStep::UnstableFeatures { ref tc }
+            | TaskStep::CompileTime { ref tc, .. }
+            | TaskStep::LintDiff { ref tc, .. }
+            | TaskStep::FutureIncompat { ref tc, .. }
+            | TaskStep::Miri { ref tc, .. }
+            | TaskStep::Rustfmt { ref tc, .. }
+            | TaskStep::SemverCheck { ref tc, .. }
+            | TaskStep::Query { ref tc } => {
                 let log_storage = state
                     .lock()
                     .prepare_logs
                     .get(&self.krate)
                     .map(|s| s.duplicate());
+                let compiler = state.lock().compilers.get(tc).cloned();
                 db.record_result(
                     ex,
                     tc,
+                    compiler.as_deref(),
                     &self.krate,
                     log_storage,
                     config,
@@ -164,16 +219,34 @@ impl Task {
     pub(super) fn run<'ctx, 's: 'ctx, DB: WriteResults>(
         &'s self,
         config: &'ctx Config,
-        workspace: &Workspace,
+        workspace: &'ctx Workspace,
         build_dir: &'ctx Mutex<BuildDirectory>,
         ex: &'ctx Experiment,
         db: &'ctx DB,
         state: &'ctx RunnerState,
     ) -> Fallible<()> {
+        let ctx = |tc: &'ctx Toolchain, quiet: bool| {
+            TaskCtx::new(
+                workspace,
+                build_dir,
+                config,
+                db,
+                ex,
+                tc,
+                &self.krate,
+                state,
+                quiet,
+            )
+        };
+
         match self.step {
             TaskStep::Cleanup => {
-                // Remove stored logs
-                state.lock().prepare_logs.remove(&self.krate);
+                // Remove stored logs, lockfiles, migrated source code and previous releases
+                let mut state = state.lock();
+                state.prepare_logs.remove(&self.krate);
+                state.lockfiles.remove(&self.krate);
+                state.migrated_sources.remove(&self.krate);
+                state.previous_releases.remove(&self.krate);
             }
             TaskStep::Prepare => {
                 let storage = LogStorage::from(config);
@@ -203,37 +276,79 @@ impl Task {
                             bail!("unable to capture sha for {}", repo.slug());
                         }
                     }
+
+                    if let Some(lockfile) = lockfile::resolve(workspace, ex, &self.krate)? {
+                        state.lock().lockfiles.insert(self.krate.clone(), lockfile);
+                    }
+                    if ex.mode == Mode::EditionMigration {
+                        let migrated = edition::migrate(&ctx(&ex.toolchains[0], false))?;
+                        state
+                            .lock()
+                            .migrated_sources
+                            .insert(self.krate.clone(), migrated);
+                    }
                     Ok(())
                 })?;
             }
             TaskStep::BuildAndTest { ref tc, quiet } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, quiet);
-                test::run_test("testing", &ctx, test::test_build_and_test)?;
+                test::run_test("testing", &ctx(tc, quiet), test::test_build_and_test)?;
             }
             TaskStep::BuildOnly { ref tc, quiet } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, quiet);
-                test::run_test("building", &ctx, test::test_build_only)?;
+                test::run_test("building", &ctx(tc, quiet), test::test_build_only)?;
             }
             TaskStep::CheckOnly { ref tc, quiet } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, quiet);
-                test::run_test("checking", &ctx, test::test_check_only)?;
+                test::run_test("checking", &ctx(tc, quiet), test::test_check_only)?;
             }
             TaskStep::Clippy { ref tc, quiet } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, quiet);
-                test::run_test("linting", &ctx, test::test_clippy_only)?;
+                test::run_test("linting", &ctx(tc, quiet), test::test_clippy_only)?;
             }
             TaskStep::Rustdoc { ref tc, quiet } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, quiet);
-                test::run_test("documenting", &ctx, test::test_rustdoc)?;
+                test::run_test("documenting", &ctx(tc, quiet), test::test_rustdoc)?;
             }
             TaskStep::UnstableFeatures { ref tc } => {
-                let ctx = TaskCtx::new(build_dir, config, db, ex, tc, &self.krate, state, false);
                 test::run_test(
                     "checking unstable",
-                    &ctx,
+                    &ctx(tc, false),
                     crate::runner::unstable_features::find_unstable_features,
                 )?;
             }
+            TaskStep::CompileTime { ref tc, quiet } => {
+                test::run_test("measuring", &ctx(tc, quiet), test::test_compile_time)?;
+            }
+            TaskStep::LintDiff { ref tc, quiet } => {
+                test::run_test(
+                    "collecting warnings of",
+                    &ctx(tc, quiet),
+                    test::test_lint_diff,
+                )?;
+            }
+            TaskStep::FutureIncompat { ref tc, quiet } => {
+                test::run_test(
+                    "collecting future-incompat lints of",
+                    &ctx(tc, quiet),
+                    test::test_future_incompat,
+                )?;
+            }
+            TaskStep::Miri { ref tc, quiet } => {
+                test::run_test("testing under miri", &ctx(tc, quiet), test::test_miri)?;
+            }
+            TaskStep::Rustfmt { ref tc, quiet } => {
+                test::run_test(
+                    "checking the formatting of",
+                    &ctx(tc, quiet),
+                    test::test_rustfmt,
+                )?;
+            }
+            TaskStep::SemverCheck { ref tc, quiet } => {
+                test::run_test(
+                    "checking the public API of",
+                    &ctx(tc, quiet),
+                    test::test_semver_check,
+                )?;
+            }
+            TaskStep::Query { ref tc } => {
+                crate::runner::query::run_query(&ctx(tc, false))?;
+            }
             TaskStep::Skip { ref tc } => {
                 // If a skipped crate is somehow sent to the agent (for example, when a crate was
                 // added to the experiment and *then* blacklisted) report the crate as skipped
@@ -241,6 +356,7 @@ impl Task {
                 db.record_result(
                     ex,
                     tc,
+                    None,
                     &self.krate,
                     None,
                     config,
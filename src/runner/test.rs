@@ -1,7 +1,21 @@
 use crate::crates::Crate;
+use crate::experiments::{FeatureSet, Mode};
 use crate::prelude::*;
 use crate::results::DiagnosticCode;
-use crate::results::{BrokenReason, EncodingType, FailureReason, TestResult, WriteResults};
+use crate::results::{
+    BrokenReason, EncodingType, FailureReason, TestOutcome, TestResult, WriteResults,
+};
+use crate::runner::classify::{IceCollector, OutputClassifier};
+use crate::runner::compile_time::{self, SampleCollector, TIME_BINARY, TIME_FORMAT};
+use crate::runner::edition;
+use crate::runner::features;
+use crate::runner::future_incompat::FutureIncompatCollector;
+use crate::runner::libtest::OutcomesCollector;
+use crate::runner::lints::WarningsCollector;
+use crate::runner::lockfile;
+use crate::runner::miri;
+use crate::runner::public_api::{self, PublicApi};
+use crate::runner::rustfmt::DiffCollector;
 use crate::runner::tasks::TaskCtx;
 use crate::runner::OverrideResult;
 use cargo_metadata::diagnostic::DiagnosticLevel;
@@ -12,6 +26,8 @@ use rustwide::cmd::{CommandError, ProcessLinesActions, SandboxBuilder};
 use rustwide::{Build, PrepareError};
 use std::collections::{BTreeSet, HashSet};
 use std::convert::TryFrom;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 fn failure_reason(err: &Error) -> FailureReason {
     for cause in err.iter_chain() {
@@ -61,8 +77,11 @@ pub(super) fn detect_broken<T>(res: Result<T, Error>) -> Result<T, Error> {
     }
 }
 
-fn get_local_packages(build_env: &Build) -> Fallible<HashSet<PackageId>> {
-    Ok(build_env
+/// Get the packages of the crate being built, and the features of its root package.
+fn get_local_packages(build_env: &Build) -> Fallible<(HashSet<PackageId>, Vec<String>)> {
+    let mut local_packages_id = HashSet::new();
+    let mut features = Vec::new();
+    for metadata in build_env
         .cargo()
         .args(&["metadata", "--no-deps", "--format-version=1"])
         .log_output(false)
@@ -70,8 +89,23 @@ fn get_local_packages(build_env: &Build) -> Fallible<HashSet<PackageId>> {
         .stdout_lines()
         .iter()
         .filter_map(|line| serde_json::from_str::<Metadata>(line).ok())
-        .flat_map(|metadata| metadata.packages.into_iter().map(|pkg| pkg.id))
-        .collect::<HashSet<_>>())
+    {
+        // Virtual workspaces don't have a root package, and thus no features to select
+        let root_manifest = metadata.workspace_root.join("Cargo.toml");
+        for package in metadata.packages {
+            if package.manifest_path == root_manifest {
+                features = package
+                    .features
+                    .keys()
+                    .filter(|feature| *feature != "default")
+                    .cloned()
+                    .collect();
+                features.sort();
+            }
+            local_packages_id.insert(package.id);
+        }
+    }
+    Ok((local_packages_id, features))
 }
 
 fn run_cargo<DB: WriteResults>(
@@ -80,12 +114,33 @@ fn run_cargo<DB: WriteResults>(
     args: &[&str],
     check_errors: bool,
     local_packages_id: &HashSet<PackageId>,
+    env: &[(&str, &str)],
+    mut inspect_line: Option<&mut dyn FnMut(&str)>,
 ) -> Fallible<()> {
     let mut rustflags = format!("--cap-lints={}", ctx.experiment.cap_lints.to_str());
     if let Some(ref tc_rustflags) = ctx.toolchain.rustflags {
         rustflags.push(' ');
         rustflags.push_str(tc_rustflags);
     }
+    for flag in ctx.experiment.profile.rustflags() {
+        rustflags.push(' ');
+        rustflags.push_str(&flag);
+    }
+
+    let mut args = args.to_vec();
+    // rustfmt doesn't build anything, and the arguments after `--` are not for cargo
+    if args.get(0) != Some(&"fmt") {
+        let pos = args
+            .iter()
+            .position(|&arg| arg == "--")
+            .unwrap_or(args.len());
+        args.splice(pos..pos, ctx.features.iter().map(String::as_str));
+        args.splice(pos..pos, ctx.experiment.profile.cargo_args());
+        if let Some(ref target) = ctx.toolchain.target {
+            args.insert(pos, target);
+            args.insert(pos, "--target");
+        }
+    }
 
     let rustflags_env = if let Some(&"doc") = args.get(0) {
         "RUSTDOCFLAGS"
@@ -96,10 +151,18 @@ fn run_cargo<DB: WriteResults>(
     let mut did_ice = false;
     let mut error_codes = BTreeSet::new();
     let mut deps = BTreeSet::new();
+    let mut classifier = OutputClassifier::default();
+    let mut ice_collector = IceCollector::default();
 
     let mut detect_error = |line: &str, actions: &mut ProcessLinesActions| {
+        if let Some(inspect_line) = inspect_line.as_mut() {
+            inspect_line(line);
+        }
+
         // Avoid trying to deserialize non JSON output
-        if !line.starts_with('{') {
+        if !check_errors || !line.starts_with('{') {
+            classifier.process_line(line);
+            ice_collector.process_line(line);
             return;
         }
 
@@ -136,7 +199,12 @@ fn run_cargo<DB: WriteResults>(
                     _ => (),
                 }
 
-                actions.replace_with_lines(inner_message.rendered.unwrap_or_default().split('\n'));
+                let rendered = inner_message.rendered.unwrap_or_default();
+                for line in rendered.split('\n') {
+                    classifier.process_line(line);
+                    ice_collector.process_line(line);
+                }
+                actions.replace_with_lines(rendered.split('\n'));
             }
             _ => actions.remove_line(),
         }
@@ -144,30 +212,49 @@ fn run_cargo<DB: WriteResults>(
 
     let mut command = build_env
         .cargo()
-        .args(args)
+        .args(&args)
         .env("CARGO_INCREMENTAL", "0")
         .env("RUST_BACKTRACE", "full")
         .env(rustflags_env, rustflags);
-
-    if check_errors {
-        command = command.process_lines(&mut detect_error);
+    for (key, value) in env {
+        command = command.env(key, value);
     }
+    command = command.process_lines(&mut detect_error);
 
     if ctx.quiet {
         command = command.no_output_timeout(None);
     }
+    if ctx.experiment.mode == Mode::Miri {
+        // Interpreting the tests is orders of magnitude slower than running them natively
+        command = command
+            .timeout(Some(Duration::from_secs(ctx.config.miri.timeout)))
+            .no_output_timeout(None);
+    }
 
     match command.run() {
         Ok(()) => Ok(()),
         Err(e) => {
             if did_ice {
+                if let Some(ice) = ice_collector.into_ice() {
+                    ctx.db
+                        .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                            extras.ice = Some(ice)
+                        })?;
+                }
                 Err(e.context(FailureReason::ICE).into())
             } else if !deps.is_empty() {
                 Err(e.context(FailureReason::DependsOn(deps)).into())
             } else if !error_codes.is_empty() {
                 Err(e.context(FailureReason::CompilerError(error_codes)).into())
             } else {
-                Err(e)
+                // Failures detected from the output don't override the ones detected by the
+                // sandbox, such as running out of memory
+                match classifier.into_reason() {
+                    Some(reason) if failure_reason(&e) == FailureReason::Unknown => {
+                        Err(e.context(reason).into())
+                    }
+                    _ => Err(e),
+                }
             }
         }
     }
@@ -190,9 +277,11 @@ pub(super) fn run_test<DB: WriteResults>(
             .prepare_logs
             .get(&ctx.krate)
             .map(|s| s.duplicate());
+        let compiler = ctx.state.lock().compilers.get(ctx.toolchain).cloned();
         ctx.db.record_result(
             ctx.experiment,
             ctx.toolchain,
+            compiler.as_deref(),
             ctx.krate,
             log_storage,
             ctx.config,
@@ -205,9 +294,12 @@ pub(super) fn run_test<DB: WriteResults>(
                     ctx.toolchain.to_string(),
                     ctx.experiment.name
                 );
-                let sandbox = SandboxBuilder::new()
+                let mut sandbox = SandboxBuilder::new()
                     .memory_limit(Some(ctx.config.sandbox.memory_limit.to_bytes()))
                     .enable_networking(false);
+                if ctx.experiment.mode == Mode::Miri {
+                    sandbox = miri::mount_sysroot(sandbox, ctx.toolchain);
+                }
 
                 let krate = &ctx.krate.to_rustwide();
                 let mut build_dir = ctx.build_dir.lock().unwrap();
@@ -218,8 +310,22 @@ pub(super) fn run_test<DB: WriteResults>(
                 }
 
                 detect_broken(build.run(|build| {
-                    let local_packages_id = get_local_packages(build)?;
-                    test_fn(ctx, build, &local_packages_id)
+                    if ctx.experiment.mode == Mode::EditionMigration {
+                        edition::apply_migration(ctx, build)?;
+                    }
+                    lockfile::update(ctx, build)?;
+                    let (local_packages_id, features) = get_local_packages(build)?;
+                    if ctx.experiment.features == FeatureSet::Default {
+                        test_fn(ctx, build, &local_packages_id)
+                    } else {
+                        test_feature_combinations(
+                            ctx,
+                            build,
+                            &local_packages_id,
+                            &features,
+                            test_fn,
+                        )
+                    }
                 }))
             },
         )?;
@@ -227,6 +333,39 @@ pub(super) fn run_test<DB: WriteResults>(
     Ok(())
 }
 
+/// Run the test once for each combination of features selected by the experiment, reporting the
+/// crate with the result of the first failing combination.
+fn test_feature_combinations<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+    features: &[String],
+    test_fn: fn(&TaskCtx<DB>, &Build, &HashSet<PackageId>) -> Fallible<TestResult>,
+) -> Fallible<TestResult> {
+    let mut results = Vec::new();
+    for args in &features::combinations(ctx.experiment.features, features) {
+        let label = features::label(args);
+        info!("building {} with {}", ctx.krate, label);
+        let ctx = TaskCtx {
+            features: args,
+            ..*ctx
+        };
+        results.push((label, test_fn(&ctx, build_env, local_packages_id)?));
+    }
+
+    let overall = results
+        .iter()
+        .map(|(_, result)| result)
+        .find(|result| !matches!(result, TestResult::TestPass | TestResult::TestSkipped))
+        .unwrap_or(&results[0].1)
+        .clone();
+    ctx.db
+        .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+            extras.feature_results = results.into_iter().collect()
+        })?;
+    Ok(overall)
+}
+
 fn build<DB: WriteResults>(
     ctx: &TaskCtx<DB>,
     build_env: &Build,
@@ -238,6 +377,8 @@ fn build<DB: WriteResults>(
         &["build", "--frozen", "--message-format=json"],
         true,
         local_packages_id,
+        &[],
+        None,
     )?;
     run_cargo(
         ctx,
@@ -245,18 +386,67 @@ fn build<DB: WriteResults>(
         &["test", "--frozen", "--no-run", "--message-format=json"],
         true,
         local_packages_id,
+        &[],
+        None,
     )?;
     Ok(())
 }
 
 fn test<DB: WriteResults>(ctx: &TaskCtx<DB>, build_env: &Build) -> Fallible<()> {
-    run_cargo(
+    // Every toolchain has to run the tests the same way for their results to be comparable
+    let args: &[&str] = if ctx
+        .experiment
+        .toolchains
+        .iter()
+        .all(|tc| tc.supports_unstable_options())
+    {
+        &[
+            "test",
+            "--frozen",
+            "--",
+            "-Z",
+            "unstable-options",
+            "--format",
+            "json",
+        ]
+    } else {
+        &["test", "--frozen"]
+    };
+
+    let mut collector = OutcomesCollector::default();
+    let res = run_cargo(
         ctx,
         build_env,
-        &["test", "--frozen"],
+        args,
         false,
         &HashSet::new(),
-    )
+        &[],
+        Some(&mut |line: &str| collector.process_line(line)),
+    );
+
+    let outcomes = collector.into_outcomes();
+    if !outcomes.is_empty() {
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.tests = outcomes.clone()
+            })?;
+    }
+
+    match res {
+        Err(err) if failure_reason(&err) == FailureReason::Unknown => {
+            let failed = outcomes
+                .into_iter()
+                .filter(|&(_, outcome)| outcome == TestOutcome::Failed)
+                .map(|(test, _)| test)
+                .collect::<BTreeSet<_>>();
+            if failed.is_empty() {
+                Err(err)
+            } else {
+                Err(err.context(FailureReason::FailedTests(failed)).into())
+            }
+        }
+        other => other,
+    }
 }
 
 pub(super) fn test_build_and_test<DB: WriteResults>(
@@ -308,6 +498,8 @@ pub(super) fn test_check_only<DB: WriteResults>(
         ],
         true,
         local_packages_id,
+        &[],
+        None,
     ) {
         Ok(TestResult::BuildFail(failure_reason(&err)))
     } else {
@@ -332,6 +524,8 @@ pub(super) fn test_clippy_only<DB: WriteResults>(
         ],
         true,
         local_packages_id,
+        &[],
+        None,
     ) {
         Ok(TestResult::BuildFail(failure_reason(&err)))
     } else {
@@ -356,11 +550,17 @@ pub(super) fn test_rustdoc<DB: WriteResults>(
         ],
         true,
         local_packages_id,
+        &[],
+        None,
     );
 
     // Make sure to remove the built documentation
     // There is no point in storing it after the build is done
-    remove_dir_all(&build_env.host_target_dir().join("doc"))?;
+    let mut target_dir = build_env.host_target_dir();
+    if let Some(ref target) = ctx.toolchain.target {
+        target_dir = target_dir.join(target);
+    }
+    remove_dir_all(&target_dir.join("doc"))?;
 
     if let Err(err) = res {
         Ok(TestResult::BuildFail(failure_reason(&err)))
@@ -368,3 +568,251 @@ pub(super) fn test_rustdoc<DB: WriteResults>(
         Ok(TestResult::TestPass)
     }
 }
+
+pub(super) fn test_compile_time<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    let mut samples = Vec::new();
+    for _ in 0..ctx.config.compile_time.samples.max(1) {
+        // Every sample builds the crate and all its dependencies from scratch
+        build_env.cargo().args(&["clean"]).run()?;
+
+        let mut collector = SampleCollector::default();
+        let start = Instant::now();
+        let res = run_cargo(
+            ctx,
+            build_env,
+            &["build", "--frozen", "--message-format=json"],
+            true,
+            local_packages_id,
+            &[("RUSTC_WRAPPER", TIME_BINARY), ("TIME", TIME_FORMAT)],
+            Some(&mut |line: &str| collector.process_line(line)),
+        );
+        let wall_time = start.elapsed();
+
+        if let Err(err) = res {
+            return Ok(TestResult::BuildFail(failure_reason(&err)));
+        }
+        samples.push(collector.into_sample(wall_time));
+    }
+
+    if let Some(compile_time) = compile_time::combine_samples(&samples) {
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.compile_time = Some(compile_time)
+            })?;
+    }
+    Ok(TestResult::TestSkipped)
+}
+
+pub(super) fn test_lint_diff<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    let mut collector = WarningsCollector::new(local_packages_id);
+    let res = run_cargo(
+        ctx,
+        build_env,
+        &[
+            "check",
+            "--frozen",
+            "--all",
+            "--all-targets",
+            "--message-format=json",
+        ],
+        true,
+        local_packages_id,
+        &[],
+        Some(&mut |line: &str| collector.process_line(line)),
+    );
+
+    if let Err(err) = res {
+        Ok(TestResult::BuildFail(failure_reason(&err)))
+    } else {
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.warnings = collector.into_warnings()
+            })?;
+        Ok(TestResult::TestPass)
+    }
+}
+
+pub(super) fn test_future_incompat<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    let mut collector = FutureIncompatCollector::new(local_packages_id);
+    let res = run_cargo(
+        ctx,
+        build_env,
+        &[
+            "check",
+            "--frozen",
+            "--all",
+            "--all-targets",
+            "--message-format=json",
+            "--future-incompat-report",
+        ],
+        true,
+        local_packages_id,
+        &[],
+        Some(&mut |line: &str| collector.process_line(line)),
+    );
+
+    if let Err(err) = res {
+        Ok(TestResult::BuildFail(failure_reason(&err)))
+    } else {
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.future_incompat = collector.into_future_incompat()
+            })?;
+        Ok(TestResult::TestPass)
+    }
+}
+
+pub(super) fn test_miri<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    // The standard library was built for Miri before running the experiment
+    let res = run_cargo(
+        ctx,
+        build_env,
+        &["miri", "test", "--frozen"],
+        false,
+        local_packages_id,
+        &[("MIRI_SYSROOT", miri::SANDBOX_SYSROOT_DIR)],
+        None,
+    );
+
+    if let Err(err) = res {
+        Ok(TestResult::TestFail(failure_reason(&err)))
+    } else {
+        Ok(TestResult::TestPass)
+    }
+}
+
+pub(super) fn test_rustfmt<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    let mut collector = DiffCollector::default();
+    let res = run_cargo(
+        ctx,
+        build_env,
+        &["fmt", "--all", "--", "--check"],
+        false,
+        local_packages_id,
+        &[],
+        Some(&mut |line: &str| collector.process_line(line)),
+    );
+
+    let diff = collector.into_diff();
+    if let Some(diff) = &diff {
+        ctx.db
+            .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+                extras.rustfmt_diff = Some(diff.clone())
+            })?;
+    }
+
+    match (res, diff) {
+        (Ok(()), _) => Ok(TestResult::TestPass),
+        (Err(_), Some(_)) => Ok(TestResult::TestFail(FailureReason::Unformatted)),
+        // rustfmt failed without printing any diff, for example because of a syntax error
+        (Err(err), None) => Ok(TestResult::BuildFail(failure_reason(&err))),
+    }
+}
+
+/// Build the rustdoc JSON output of the library of the crate, or of the one at `manifest_path`.
+fn rustdoc_json<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+    doc_dir: &Path,
+    manifest_path: Option<&str>,
+) -> Fallible<()> {
+    if doc_dir.exists() {
+        remove_dir_all(doc_dir)?;
+    }
+
+    let mut args = vec!["rustdoc", "--frozen", "--lib", "--message-format=json"];
+    if let Some(manifest_path) = manifest_path {
+        args.push("--manifest-path");
+        args.push(manifest_path);
+    }
+    args.extend(&["--", "-Zunstable-options", "--output-format", "json"]);
+    run_cargo(
+        ctx,
+        build_env,
+        &args,
+        true,
+        local_packages_id,
+        // The JSON output is unstable, so it's otherwise only available on nightly
+        &[("RUSTC_BOOTSTRAP", "1")],
+        None,
+    )
+}
+
+pub(super) fn test_semver_check<DB: WriteResults>(
+    ctx: &TaskCtx<DB>,
+    build_env: &Build,
+    local_packages_id: &HashSet<PackageId>,
+) -> Fallible<TestResult> {
+    let baseline = ctx.state.lock().previous_releases.get(ctx.krate).cloned();
+    let baseline = match baseline {
+        Some(baseline) => baseline,
+        None => {
+            info!("there is no previous semver-compatible release to compare the crate with");
+            return Ok(TestResult::TestSkipped);
+        }
+    };
+
+    let mut doc_dir = build_env.host_target_dir();
+    if let Some(ref target) = ctx.toolchain.target {
+        doc_dir = doc_dir.join(target);
+    }
+    let doc_dir = doc_dir.join("doc");
+
+    if let Err(err) = rustdoc_json(ctx, build_env, local_packages_id, &doc_dir, None) {
+        return Ok(TestResult::BuildFail(failure_reason(&err)));
+    }
+    let api = PublicApi::read(&doc_dir)?;
+
+    info!(
+        "comparing the public API with the one of {}",
+        baseline.version
+    );
+    let res = public_api::prepare_baseline(ctx, build_env, &baseline).and_then(|manifest_path| {
+        rustdoc_json(
+            ctx,
+            build_env,
+            local_packages_id,
+            &doc_dir,
+            Some(&manifest_path),
+        )
+    });
+    // The crate can't be checked if its previous release doesn't build anymore
+    let previous_api = match res {
+        Ok(()) => Some(PublicApi::read(&doc_dir)?),
+        Err(err) => {
+            info!("failed to build the previous release: {}", err);
+            None
+        }
+    };
+    public_api::cleanup_baseline(build_env)?;
+    remove_dir_all(&doc_dir)?;
+
+    match previous_api.map(|previous| api.breaking_changes(&previous)) {
+        None => Ok(TestResult::TestSkipped),
+        Some(ref changes) if changes.is_empty() => Ok(TestResult::TestPass),
+        Some(changes) => Ok(TestResult::TestFail(FailureReason::BreakingChanges(
+            changes,
+        ))),
+    }
+}
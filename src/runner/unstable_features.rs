@@ -1,6 +1,5 @@
 use crate::prelude::*;
-use crate::results::TestResult;
-use crate::results::WriteResults;
+use crate::results::{TestResult, UnstableFeatures, WriteResults};
 use crate::runner::tasks::TaskCtx;
 use cargo_metadata::PackageId;
 use rustwide::Build;
@@ -9,11 +8,11 @@ use std::path::Path;
 use walkdir::{DirEntry, WalkDir};
 
 pub(super) fn find_unstable_features<DB: WriteResults>(
-    _ctx: &TaskCtx<DB>,
+    ctx: &TaskCtx<DB>,
     build: &Build,
     _local_packages_id: &HashSet<PackageId>,
 ) -> Fallible<TestResult> {
-    let mut features = HashSet::new();
+    let mut features = UnstableFeatures::new();
 
     for entry in WalkDir::new(build.host_source_dir())
         .into_iter()
@@ -34,16 +33,19 @@ pub(super) fn find_unstable_features<DB: WriteResults>(
 
         let new_features = parse_features(entry.path())?;
 
-        for feature in new_features {
+        // Empty names come from trailing commas in the list of features
+        for feature in new_features.into_iter().filter(|f| !f.is_empty()) {
             features.insert(feature);
         }
     }
 
-    let mut features: Vec<_> = features.into_iter().collect();
-    features.sort();
-    for feature in features {
+    for feature in &features {
         info!("unstable-feature: {}", feature);
     }
+    ctx.db
+        .record_extras(ctx.experiment, ctx.toolchain, ctx.krate, |extras| {
+            extras.unstable_features = features
+        })?;
 
     Ok(TestResult::TestPass)
 }
@@ -63,7 +65,7 @@ fn parse_features(path: &Path) -> Fallible<Vec<String>> {
     Ok(features)
 }
 
-fn is_hidden(entry: &DirEntry) -> bool {
+pub(super) fn is_hidden(entry: &DirEntry) -> bool {
     entry
         .file_name()
         .to_str()
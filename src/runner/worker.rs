@@ -63,11 +63,13 @@ impl<'a, DB: WriteResults + Sync> Worker<'a, DB> {
         // This uses a `loop` instead of a `while let` to avoid locking the graph too much
         loop {
             self.maybe_cleanup_target_dir()?;
-            let walk_result = self
-                .graph
-                .lock()
-                .unwrap()
-                .next_task(self.ex, self.db, &self.name);
+            let walk_result = self.graph.lock().unwrap().next_task(
+                self.ex,
+                self.db,
+                self.state,
+                self.config,
+                &self.name,
+            );
             match walk_result {
                 WalkResult::Task(id, task) => {
                     info!("running task: {:?}", task);
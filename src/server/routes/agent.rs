@@ -1,11 +1,13 @@
 use crate::agent::Capabilities;
+use crate::crates::Crate;
 use crate::experiments::{Assignee, Experiment, Status};
 use crate::prelude::*;
-use crate::results::{DatabaseDB, EncodingType, ProgressData};
+use crate::results::{DatabaseDB, EncodingType, ProgressData, WriteResults};
 use crate::server::api_types::{AgentConfig, ApiResponse};
 use crate::server::auth::{auth_filter, AuthDetails, TokenType};
 use crate::server::messages::Message;
 use crate::server::{Data, HttpError};
+use crate::toolchain::Toolchain;
 use failure::Compat;
 use http::{Response, StatusCode};
 use hyper::Body;
@@ -21,6 +23,14 @@ pub struct ExperimentData<T> {
     data: T,
 }
 
+#[derive(Deserialize)]
+pub struct CachedResultRequest {
+    #[serde(rename = "crate")]
+    krate: Crate,
+    toolchain: Toolchain,
+    compiler: String,
+}
+
 pub fn routes(
     data: Arc<Data>,
     mutex: Arc<Mutex<Data>>,
@@ -60,6 +70,14 @@ pub fn routes(
         .and(auth_filter(data.clone(), TokenType::Agent))
         .map(endpoint_record_progress);
 
+    let reuse_cached_result = warp::post2()
+        .and(warp::path("reuse-cached-result"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and(mutex_filter.clone())
+        .and(auth_filter(data.clone(), TokenType::Agent))
+        .map(endpoint_reuse_cached_result);
+
     let heartbeat = warp::post2()
         .and(warp::path("heartbeat"))
         .and(warp::path::end())
@@ -84,6 +102,8 @@ pub fn routes(
                 .unify()
                 .or(record_progress)
                 .unify()
+                .or(reuse_cached_result)
+                .unify()
                 .or(heartbeat)
                 .unify()
                 .or(error)
@@ -167,15 +187,53 @@ fn endpoint_record_progress(
 
     let db = DatabaseDB::new(&data.db);
     db.store(&ex, &result.data, EncodingType::Gzip)?;
+    check_completed(&data, &mut ex)?;
+
+    Ok(ApiResponse::Success { result: true }.into_response()?)
+}
 
+fn endpoint_reuse_cached_result(
+    request: ExperimentData<CachedResultRequest>,
+    mutex: Arc<Mutex<Data>>,
+    _auth: AuthDetails,
+) -> Fallible<Response<Body>> {
+    let data = mutex.lock().unwrap();
+    let mut ex = Experiment::get(&data.db, &request.experiment_name)?
+        .ok_or_else(|| err_msg("no experiment run by this agent"))?;
+
+    let db = DatabaseDB::new(&data.db);
+    let result = db.reuse_cached_result(
+        &ex,
+        &request.data.toolchain,
+        &request.data.compiler,
+        &request.data.krate,
+        &data.config,
+    )?;
+    if result.is_some() {
+        check_completed(&data, &mut ex)?;
+    }
+
+    Ok(ApiResponse::Success { result }.into_response()?)
+}
+
+/// Move the experiment to its next status once all the results were recorded.
+fn check_completed(data: &Data, ex: &mut Experiment) -> Fallible<()> {
     let (completed, all) = ex.raw_progress(&data.db)?;
     if completed == all {
-        ex.set_status(&data.db, Status::NeedsReport)?;
-        info!("experiment {} completed, marked as needs-report", ex.name);
-        data.reports_worker.wake(); // Ensure the reports worker is awake
+        if ex.status == Status::Running
+            && ex.verify_runs > 0
+            && ex.requeue_for_verification(&data.db, &data.config)? > 0
+        {
+            ex.set_status(&data.db, Status::Verifying)?;
+            info!("experiment {} completed, marked as verifying", ex.name);
+        } else if !ex.has_pending_crates(&data.db)? {
+            ex.set_status(&data.db, Status::NeedsReport)?;
+            info!("experiment {} completed, marked as needs-report", ex.name);
+            data.reports_worker.wake(); // Ensure the reports worker is awake
+        }
     }
 
-    Ok(ApiResponse::Success { result: true }.into_response()?)
+    Ok(())
 }
 
 fn endpoint_heartbeat(data: Arc<Data>, auth: AuthDetails) -> Fallible<Response<Body>> {
@@ -27,6 +27,7 @@ impl ExperimentData {
         let (status_class, status_pretty, show_progress) = match experiment.status {
             Status::Queued => ("", "Queued", true),
             Status::Running => ("orange", "Running", true),
+            Status::Verifying => ("orange", "Verifying", false),
             Status::NeedsReport => ("orange", "Needs report", false),
             Status::Failed => ("red", "Failed", false),
             Status::GeneratingReport => ("orange", "Generating report", false),
@@ -45,6 +46,14 @@ impl ExperimentData {
                 Mode::Clippy => "cargo clippy",
                 Mode::Rustdoc => "cargo doc",
                 Mode::UnstableFeatures => "unstable features",
+                Mode::CompileTime => "compile time",
+                Mode::LintDiff => "lint diff",
+                Mode::FutureIncompat => "future incompat",
+                Mode::Miri => "cargo miri test",
+                Mode::Rustfmt => "cargo fmt",
+                Mode::EditionMigration => "cargo fix --edition",
+                Mode::SemverCheck => "semver check",
+                Mode::Query => "source query",
             },
             assigned_to: experiment.assigned_to.as_ref().map(|a| a.to_string()),
             priority: experiment.priority,
@@ -67,6 +76,7 @@ struct ListContext {
 pub fn endpoint_queue(data: Arc<Data>) -> Fallible<Response<Body>> {
     let mut queued = Vec::new();
     let mut running = Vec::new();
+    let mut verifying = Vec::new();
     let mut needs_report = Vec::new();
     let mut failed = Vec::new();
     let mut generating_report = Vec::new();
@@ -83,6 +93,7 @@ pub fn endpoint_queue(data: Arc<Data>) -> Fallible<Response<Body>> {
         match experiment.status {
             Status::Queued => queued.push(ex),
             Status::Running => running.push(ex),
+            Status::Verifying => verifying.push(ex),
             Status::NeedsReport => needs_report.push(ex),
             Status::Failed => failed.push(ex),
             Status::GeneratingReport => generating_report.push(ex),
@@ -96,6 +107,7 @@ pub fn endpoint_queue(data: Arc<Data>) -> Fallible<Response<Body>> {
     experiments.append(&mut generating_report);
     experiments.append(&mut needs_report);
     experiments.append(&mut failed);
+    experiments.append(&mut verifying);
     experiments.append(&mut running);
     experiments.append(&mut queued);
 
@@ -138,7 +150,7 @@ pub fn endpoint_experiment(name: String, data: Arc<Data>) -> Fallible<Response<B
     if let Some(ex) = Experiment::get(&data.db, &name)? {
         let (completed_jobs, total_jobs) = ex.raw_progress(&data.db)?;
         // this is done to avoid having tons of different test result types in the experiment page
-        // all CompilerError and DependsOn failures are grouped together
+        // all CompilerError, DependsOn and FailedTests failures are grouped together
         let mut result_counts = HashMap::new();
         for (res, count) in ex.get_result_counts(&data.db)? {
             *result_counts.entry(res.name()).or_default() += count;
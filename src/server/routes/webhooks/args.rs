@@ -1,5 +1,7 @@
-use crate::experiments::{Assignee, CapLints, DeferredCrateSelect, Mode};
-use crate::toolchain::Toolchain;
+use crate::experiments::{
+    Assignee, CapLints, CargoProfile, DeferredCrateSelect, FeatureSet, LockfilePolicy, Mode,
+};
+use crate::toolchain::{Toolchain, ToolchainList};
 use failure::{self, Fallible};
 
 #[derive(Debug, Fail)]
@@ -107,6 +109,7 @@ generate_parser!(pub enum Command {
         name: Option<String> = "name",
         start: Option<Toolchain> = "start",
         end: Option<Toolchain> = "end",
+        extra: Option<ToolchainList> = "extra",
         mode: Option<Mode> = "mode",
         crates: Option<DeferredCrateSelect> = "crates",
         cap_lints: Option<CapLints> = "cap-lints",
@@ -114,18 +117,29 @@ generate_parser!(pub enum Command {
         ignore_blacklist: Option<bool> = "ignore-blacklist",
         assign: Option<Assignee> = "assign",
         requirement: Option<String> = "requirement",
+        verify_runs: Option<u32> = "verify-runs",
+        baseline: Option<String> = "baseline",
+        features: Option<FeatureSet> = "features",
+        profile: Option<CargoProfile> = "profile",
+        lockfile: Option<LockfilePolicy> = "lockfile",
     })
 
     "check" => Check(CheckArgs {
         name: Option<String> = "name",
         start: Option<Toolchain> = "start",
         end: Option<Toolchain> = "end",
+        extra: Option<ToolchainList> = "extra",
         crates: Option<DeferredCrateSelect> = "crates",
         cap_lints: Option<CapLints> = "cap-lints",
         priority: Option<i32> = "p",
         ignore_blacklist: Option<bool> = "ignore-blacklist",
         assign: Option<Assignee> = "assign",
         requirement: Option<String> = "requirement",
+        verify_runs: Option<u32> = "verify-runs",
+        baseline: Option<String> = "baseline",
+        features: Option<FeatureSet> = "features",
+        profile: Option<CargoProfile> = "profile",
+        lockfile: Option<LockfilePolicy> = "lockfile",
     })
 
     "abort" => Abort(AbortArgs {
@@ -144,10 +158,32 @@ generate_parser!(pub enum Command {
 
     "reload-acl" => ReloadACL(ReloadACLArgs {})
 
+    "create-list" => CreateList(CreateListArgs {
+        list: Option<String> = "list",
+        crates: Option<String> = "crates",
+    })
+
+    "add-to-list" => AddToList(AddToListArgs {
+        list: Option<String> = "list",
+        crates: Option<String> = "crates",
+    })
+
+    "remove-from-list" => RemoveFromList(RemoveFromListArgs {
+        list: Option<String> = "list",
+        crates: Option<String> = "crates",
+    })
+
+    "delete-list" => DeleteList(DeleteListArgs {
+        list: Option<String> = "list",
+    })
+
+    "lists" => Lists(ListsArgs {})
+
     _ => Edit(EditArgs {
         name: Option<String> = "name",
         start: Option<Toolchain> = "start",
         end: Option<Toolchain> = "end",
+        extra: Option<ToolchainList> = "extra",
         mode: Option<Mode> = "mode",
         crates: Option<DeferredCrateSelect> = "crates",
         cap_lints: Option<CapLints> = "cap-lints",
@@ -155,6 +191,10 @@ generate_parser!(pub enum Command {
         ignore_blacklist: Option<bool> = "ignore-blacklist",
         assign: Option<Assignee> = "assign",
         requirement: Option<String> = "requirement",
+        verify_runs: Option<u32> = "verify-runs",
+        features: Option<FeatureSet> = "features",
+        profile: Option<CargoProfile> = "profile",
+        lockfile: Option<LockfilePolicy> = "lockfile",
     })
 });
 
@@ -1,15 +1,20 @@
 use crate::actions::{self, Action, ActionsCtx};
+use crate::crates::CustomList;
 use crate::db::{Database, QueryUtils};
-use crate::experiments::{CapLints, CrateSelect, Experiment, GitHubIssue, Mode, Status};
+use crate::experiments::{
+    CapLints, CrateSelect, Experiment, FeatureSet, GitHubIssue, LockfilePolicy, Mode, Status,
+};
 use crate::prelude::*;
 use crate::server::github::{Issue, Repository};
 use crate::server::messages::{Label, Message};
 use crate::server::routes::webhooks::args::{
-    AbortArgs, CheckArgs, EditArgs, RetryArgs, RetryReportArgs, RunArgs,
+    AbortArgs, AddToListArgs, CheckArgs, CreateListArgs, DeleteListArgs, EditArgs,
+    RemoveFromListArgs, RetryArgs, RetryReportArgs, RunArgs,
 };
 use crate::server::Data;
 use crate::toolchain::Toolchain;
 use rustwide::Toolchain as RustwideToolchain;
+use std::collections::HashSet;
 
 pub fn ping(data: &Data, issue: &Issue) -> Fallible<()> {
     Message::new()
@@ -36,12 +41,18 @@ pub fn check(
             name: args.name,
             start: args.start,
             end: args.end,
+            extra: args.extra,
             crates: args.crates,
             cap_lints: args.cap_lints,
             priority: args.priority,
             ignore_blacklist: args.ignore_blacklist,
             assign: args.assign,
             requirement: args.requirement,
+            verify_runs: args.verify_runs,
+            baseline: args.baseline,
+            features: args.features,
+            profile: args.profile,
+            lockfile: args.lockfile,
         },
     )
 }
@@ -55,6 +66,13 @@ pub fn run(
 ) -> Fallible<()> {
     let name = setup_run_name(&data.db, issue, args.name)?;
 
+    // The start toolchain is provided by the baseline experiment, if any
+    if args.baseline.is_some() && args.start.is_some() {
+        return Err(err_msg(
+            "the start toolchain can't be set when a baseline is used",
+        ));
+    }
+
     // Autodetect toolchains only if none of them was specified
     let (mut detected_start, mut detected_end, mut try_build) = (None, None, None);
     if args.start.is_none() && args.end.is_none() {
@@ -65,12 +83,14 @@ pub fn run(
             detected_start = Some(Toolchain {
                 source: RustwideToolchain::ci(&build.base_sha, false),
                 rustflags: None,
+                target: None,
                 ci_try: false,
                 patches: Vec::new(),
             });
             detected_end = Some(Toolchain {
                 source: RustwideToolchain::ci(&build.merge_sha, false),
                 rustflags: None,
+                target: None,
                 ci_try: true,
                 patches: Vec::new(),
             });
@@ -85,16 +105,24 @@ pub fn run(
         .transpose()
         .map_err(|e| e.context("Failed to resolve crate list"))?;
 
-    actions::CreateExperiment {
-        name: name.clone(),
-        toolchains: [
+    let mut toolchains = Vec::new();
+    if args.baseline.is_none() {
+        toolchains.push(
             args.start
                 .or(detected_start)
                 .ok_or_else(|| err_msg("missing start toolchain"))?,
-            args.end
-                .or(detected_end)
-                .ok_or_else(|| err_msg("missing end toolchain"))?,
-        ],
+        );
+    }
+    toolchains.push(
+        args.end
+            .or(detected_end)
+            .ok_or_else(|| err_msg("missing end toolchain"))?,
+    );
+    toolchains.extend(args.extra.map(|list| list.0).unwrap_or_default());
+
+    actions::CreateExperiment {
+        name: name.clone(),
+        toolchains,
         mode: args.mode.unwrap_or(Mode::BuildAndTest),
         crates: crates.unwrap_or(CrateSelect::Full),
         cap_lints: args.cap_lints.unwrap_or(CapLints::Forbid),
@@ -107,6 +135,13 @@ pub fn run(
         ignore_blacklist: args.ignore_blacklist.unwrap_or(false),
         assign: args.assign,
         requirement: Some(requirement),
+        verify_runs: args.verify_runs.unwrap_or(0),
+        baseline: args.baseline,
+        features: args.features.unwrap_or(FeatureSet::Default),
+        profile: args.profile.unwrap_or_default(),
+        lockfile: args.lockfile.unwrap_or(LockfilePolicy::AsPrepared),
+        // The regexes of the queries don't fit in the arguments of the bot
+        queries: Vec::new(),
     }
     .apply(&ActionsCtx::new(&data.db, &data.config))?;
 
@@ -141,6 +176,7 @@ pub fn edit(data: &Data, issue: &Issue, args: EditArgs) -> Fallible<()> {
     actions::EditExperiment {
         name: name.clone(),
         toolchains: [args.start, args.end],
+        extra_toolchains: args.extra.map(|list| list.0),
         crates,
         mode: args.mode,
         cap_lints: args.cap_lints,
@@ -148,6 +184,11 @@ pub fn edit(data: &Data, issue: &Issue, args: EditArgs) -> Fallible<()> {
         ignore_blacklist: args.ignore_blacklist,
         assign: args.assign,
         requirement: args.requirement,
+        verify_runs: args.verify_runs,
+        features: args.features,
+        profile: args.profile,
+        lockfile: args.lockfile,
+        queries: None,
     }
     .apply(&ActionsCtx::new(&data.db, &data.config))?;
 
@@ -240,6 +281,104 @@ pub fn reload_acl(data: &Data, issue: &Issue) -> Fallible<()> {
     Ok(())
 }
 
+pub fn create_list(data: &Data, issue: &Issue, args: CreateListArgs) -> Fallible<()> {
+    let list = get_list_name(args.list)?;
+
+    actions::CreateList {
+        name: list.clone(),
+        crates: parse_crate_names(&args.crates.unwrap_or_default()),
+    }
+    .apply(&ActionsCtx::new(&data.db, &data.config))?;
+
+    Message::new()
+        .line("memo", format!("List **`{}`** created!", list))
+        .send(&issue.url, data)?;
+
+    Ok(())
+}
+
+pub fn add_to_list(data: &Data, issue: &Issue, args: AddToListArgs) -> Fallible<()> {
+    let list = get_list_name(args.list)?;
+
+    actions::AddToList {
+        name: list.clone(),
+        crates: parse_crate_names(&get_crate_names(args.crates)?),
+    }
+    .apply(&ActionsCtx::new(&data.db, &data.config))?;
+
+    Message::new()
+        .line("memo", format!("Crates added to the **`{}`** list.", list))
+        .send(&issue.url, data)?;
+
+    Ok(())
+}
+
+pub fn remove_from_list(data: &Data, issue: &Issue, args: RemoveFromListArgs) -> Fallible<()> {
+    let list = get_list_name(args.list)?;
+
+    actions::RemoveFromList {
+        name: list.clone(),
+        crates: parse_crate_names(&get_crate_names(args.crates)?),
+    }
+    .apply(&ActionsCtx::new(&data.db, &data.config))?;
+
+    Message::new()
+        .line(
+            "memo",
+            format!("Crates removed from the **`{}`** list.", list),
+        )
+        .send(&issue.url, data)?;
+
+    Ok(())
+}
+
+pub fn delete_list(data: &Data, issue: &Issue, args: DeleteListArgs) -> Fallible<()> {
+    let list = get_list_name(args.list)?;
+
+    actions::DeleteList { name: list.clone() }.apply(&ActionsCtx::new(&data.db, &data.config))?;
+
+    Message::new()
+        .line("wastebasket", format!("List **`{}`** deleted!", list))
+        .send(&issue.url, data)?;
+
+    Ok(())
+}
+
+pub fn lists(data: &Data, issue: &Issue) -> Fallible<()> {
+    let lists = CustomList::all(&data.db)?;
+
+    let mut message = Message::new();
+    if lists.is_empty() {
+        message = message.line("page_facing_up", "There are no custom lists yet.");
+    }
+    for list in lists {
+        message = message.line(
+            "page_facing_up",
+            format!("**`{}`**: {} crates", list.name, list.crates.len()),
+        );
+    }
+    message.send(&issue.url, data)?;
+
+    Ok(())
+}
+
+fn get_list_name(list: Option<String>) -> Fallible<String> {
+    list.ok_or_else(|| err_msg("missing list name (provide it with `list=`)"))
+}
+
+fn get_crate_names(crates: Option<String>) -> Fallible<String> {
+    crates.ok_or_else(|| err_msg("missing crates (provide them with `crates=`)"))
+}
+
+fn parse_crate_names(crates: &str) -> HashSet<String> {
+    crates
+        .split(',')
+        .map(|krate| krate.trim())
+        .filter(|krate| !krate.is_empty())
+        .map(|krate| krate.to_string())
+        .collect()
+}
+
 fn get_name(db: &Database, issue: &Issue, name: Option<String>) -> Fallible<String> {
     if let Some(name) = name {
         store_experiment_name(db, issue, &name)?;
@@ -308,8 +447,8 @@ fn generate_new_experiment_name(db: &Database, issue: &Issue) -> Fallible<String
 #[cfg(test)]
 mod tests {
     use super::{
-        default_experiment_name, generate_new_experiment_name, get_name, setup_run_name,
-        store_experiment_name,
+        default_experiment_name, generate_new_experiment_name, get_name, parse_crate_names,
+        setup_run_name, store_experiment_name,
     };
     use crate::actions::{self, Action, ActionsCtx};
     use crate::config::Config;
@@ -515,4 +654,16 @@ mod tests {
             "pr-12345-2"
         );
     }
+
+    #[test]
+    fn test_parse_crate_names() {
+        assert!(parse_crate_names("").is_empty());
+        assert_eq!(
+            parse_crate_names("cortex-m, brson/hello-rs,,cortex-m"),
+            ["cortex-m", "brson/hello-rs"]
+                .iter()
+                .map(|krate| krate.to_string())
+                .collect()
+        );
+    }
 }
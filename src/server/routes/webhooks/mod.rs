@@ -142,6 +142,26 @@ fn process_command(
             Command::ReloadACL(_) => {
                 commands::reload_acl(data, issue)?;
             }
+
+            Command::CreateList(args) => {
+                commands::create_list(data, issue, args)?;
+            }
+
+            Command::AddToList(args) => {
+                commands::add_to_list(data, issue, args)?;
+            }
+
+            Command::RemoveFromList(args) => {
+                commands::remove_from_list(data, issue, args)?;
+            }
+
+            Command::DeleteList(args) => {
+                commands::delete_list(data, issue, args)?;
+            }
+
+            Command::Lists(_) => {
+                commands::lists(data, issue)?;
+            }
         }
 
         break;
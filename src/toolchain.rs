@@ -1,6 +1,7 @@
 use crate::prelude::*;
 use crate::utils;
-use rustwide::Toolchain as RustwideToolchain;
+use rustwide::cmd::Command;
+use rustwide::{Toolchain as RustwideToolchain, Workspace};
 use std::fmt;
 use std::str::FromStr;
 
@@ -10,6 +11,7 @@ lazy_static! {
     pub(crate) static ref MAIN_TOOLCHAIN: Toolchain = Toolchain {
         source: RustwideToolchain::dist("stable"),
         rustflags: None,
+        target: None,
         ci_try: false,
         patches: Vec::new(),
     };
@@ -18,6 +20,7 @@ lazy_static! {
     pub(crate) static ref TEST_TOOLCHAIN: Toolchain = Toolchain {
         source: RustwideToolchain::dist("beta"),
         rustflags: None,
+        target: None,
         ci_try: false,
         patches: Vec::new(),
     };
@@ -27,6 +30,9 @@ lazy_static! {
 pub struct Toolchain {
     pub source: RustwideToolchain,
     pub rustflags: Option<String>,
+    /// The target to build the crates for, instead of the host.
+    #[serde(default)]
+    pub target: Option<String>,
     pub ci_try: bool,
     pub patches: Vec<CratePatch>,
 }
@@ -37,6 +43,42 @@ impl Toolchain {
 
         encode(&self.to_string(), &utils::FILENAME_ENCODE_SET).to_string()
     }
+
+    /// The compiler the installed toolchain resolves to, identified by the commit it was built
+    /// from or by its release if the commit is unknown. Channels like `stable` or `nightly`
+    /// resolve to a different compiler over time.
+    pub(crate) fn resolve_compiler(&self, workspace: &Workspace) -> Fallible<String> {
+        let output = Command::new(workspace, self.source.rustc())
+            .args(&["-vV"])
+            .log_output(false)
+            .run_capture()?;
+        compiler_from_version_info(&output.stdout_lines())
+            .ok_or_else(|| err_msg(format!("failed to resolve the compiler of {}", self)))
+    }
+
+    /// Whether the toolchain accepts unstable options, such as libtest's JSON output.
+    pub fn supports_unstable_options(&self) -> bool {
+        if let Some(dist) = self.source.as_dist() {
+            dist.name().starts_with("nightly")
+        } else {
+            // Builds from rust-lang/rust CI are nightly compilers
+            self.source.as_ci().is_some()
+        }
+    }
+}
+
+/// Parse the identity of the compiler from the output of `rustc -vV`.
+fn compiler_from_version_info(lines: &[String]) -> Option<String> {
+    let field = |name: &str| {
+        lines
+            .iter()
+            .filter_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim())
+            .find(|value| !value.is_empty() && *value != "unknown")
+    };
+    field("commit-hash:")
+        .or_else(|| field("release:"))
+        .map(|value| value.to_string())
 }
 
 impl std::ops::Deref for Toolchain {
@@ -65,6 +107,10 @@ impl fmt::Display for Toolchain {
             write!(f, "+rustflags={}", flag)?;
         }
 
+        if let Some(ref target) = self.target {
+            write!(f, "+target={}", target)?;
+        }
+
         for patch in self.patches.iter() {
             write!(f, "+patch={}", patch)?;
         }
@@ -114,6 +160,7 @@ impl FromStr for Toolchain {
         };
 
         let mut rustflags = None;
+        let mut target = None;
         let mut patches: Vec<CratePatch> = vec![];
         for part in parts {
             if let Some(equal_idx) = part.find('=') {
@@ -126,6 +173,7 @@ impl FromStr for Toolchain {
 
                 match flag {
                     "rustflags" => rustflags = Some(value),
+                    "target" => target = Some(value),
                     "patch" => patches.push(value.parse()?),
                     unknown => return Err(ToolchainParseError::InvalidFlag(unknown.to_string())),
                 }
@@ -137,12 +185,33 @@ impl FromStr for Toolchain {
         Ok(Toolchain {
             source,
             rustflags,
+            target,
             ci_try,
             patches,
         })
     }
 }
 
+/// A comma-separated list of toolchains, used to specify the additional toolchains of an
+/// experiment.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ToolchainList(pub Vec<Toolchain>);
+
+impl FromStr for ToolchainList {
+    type Err = ToolchainParseError;
+
+    fn from_str(input: &str) -> Result<Self, ToolchainParseError> {
+        Ok(ToolchainList(
+            input
+                .split(',')
+                .map(|tc| tc.trim())
+                .filter(|tc| !tc.is_empty())
+                .map(|tc| tc.parse())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone)]
 pub struct CratePatch {
     pub name: String,
@@ -176,7 +245,7 @@ impl fmt::Display for CratePatch {
 
 #[cfg(test)]
 mod tests {
-    use super::{CratePatch, Toolchain};
+    use super::{compiler_from_version_info, CratePatch, Toolchain, ToolchainList};
     use rustwide::Toolchain as RustwideToolchain;
     use std::str::FromStr;
 
@@ -189,6 +258,7 @@ mod tests {
                     test_from_str!($str => Toolchain {
                         source: $source,
                         rustflags: None,
+                        target: None,
                         ci_try: $ci_try,
                         patches: Vec::new(),
                     });
@@ -197,6 +267,16 @@ mod tests {
                     test_from_str!(concat!($str, "+rustflags=foo bar") => Toolchain {
                         source: $source,
                         rustflags: Some("foo bar".to_string()),
+                        target: None,
+                        ci_try: $ci_try,
+                        patches: Vec::new(),
+                    });
+
+                    // Test parsing with a target
+                    test_from_str!(concat!($str, "+target=wasm32-unknown-unknown") => Toolchain {
+                        source: $source,
+                        rustflags: None,
+                        target: Some("wasm32-unknown-unknown".to_string()),
                         ci_try: $ci_try,
                         patches: Vec::new(),
                     });
@@ -205,6 +285,7 @@ mod tests {
                     test_from_str!(concat!($str, "+patch=example=https://git.example.com/some/repo=master") => Toolchain {
                         source: $source,
                         rustflags: None,
+                        target: None,
                         ci_try: $ci_try,
                         patches: vec![CratePatch {
                             name: "example".to_string(),
@@ -217,6 +298,7 @@ mod tests {
                     test_from_str!(concat!($str, "+rustflags=foo bar+patch=example=https://git.example.com/some/repo=master") => Toolchain {
                         source: $source,
                         rustflags: Some("foo bar".to_string()),
+                        target: None,
                         ci_try: $ci_try,
                         patches: vec![CratePatch {
                             name: "example".to_string(),
@@ -268,7 +350,64 @@ mod tests {
         assert!(Toolchain::from_str("foo#0000000000000000000000000000000000000000").is_err());
         assert!(Toolchain::from_str("stable+rustflags").is_err());
         assert!(Toolchain::from_str("stable+rustflags=").is_err());
+        assert!(Toolchain::from_str("stable+target=").is_err());
         assert!(Toolchain::from_str("stable+donotusethisflag=ever").is_err());
         assert!(Toolchain::from_str("stable+patch=").is_err())
     }
+
+    #[test]
+    fn test_toolchain_list() {
+        assert_eq!(
+            ToolchainList::from_str("").unwrap(),
+            ToolchainList(Vec::new())
+        );
+        assert_eq!(
+            ToolchainList::from_str("beta, nightly+rustflags=-Zfoo").unwrap(),
+            ToolchainList(vec![
+                Toolchain::from_str("beta").unwrap(),
+                Toolchain::from_str("nightly+rustflags=-Zfoo").unwrap(),
+            ])
+        );
+        assert!(ToolchainList::from_str("beta,foo#bar").is_err());
+    }
+
+    #[test]
+    fn test_compiler_from_version_info() {
+        let lines = |output: &str| output.lines().map(String::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            compiler_from_version_info(&lines(
+                "rustc 1.50.0 (cb75ad5db 2021-02-10)\n\
+                 binary: rustc\n\
+                 commit-hash: cb75ad5db02783e8b0222fee363c5f63f7e2cf5b\n\
+                 release: 1.50.0\n"
+            )),
+            Some("cb75ad5db02783e8b0222fee363c5f63f7e2cf5b".into())
+        );
+        assert_eq!(
+            compiler_from_version_info(&lines(
+                "rustc 1.50.0\nbinary: rustc\ncommit-hash: unknown\nrelease: 1.50.0\n"
+            )),
+            Some("1.50.0".into())
+        );
+        assert_eq!(compiler_from_version_info(&lines("error")), None);
+    }
+
+    #[test]
+    fn test_supports_unstable_options() {
+        for (toolchain, supported) in &[
+            ("stable", false),
+            ("beta-1970-01-01", false),
+            ("nightly", true),
+            ("nightly-1970-01-01+rustflags=-Zfoo", true),
+            ("try#0000000000000000000000000000000000000000", true),
+        ] {
+            assert_eq!(
+                Toolchain::from_str(toolchain)
+                    .unwrap()
+                    .supports_unstable_options(),
+                *supported
+            );
+        }
+    }
 }